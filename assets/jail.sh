@@ -2,7 +2,43 @@
 
 # Script for running programs in a jail
 # Usage: jail.sh <jail dir> <program> <...dependencies>
+#        JAIL_ROOTFS=<rootfs> jail.sh <jail dir> <code dir> <program> <...arguments>
+#        JAIL_CLEANUP=1 [JAIL_ROOTFS=<rootfs>] jail.sh <jail dir>
 # Example: jail.sh /home/jail /bin/bash
+# The script replaces itself with the jailed program, so its exit status (or signal) is the program's own.
+# Jail has to be cleaned up afterwards by running the script again with JAIL_CLEANUP set.
+# If JAIL_START_FILE is set, time the program started at (in nanoseconds since epoch) is written to it.
+
+# Replace this script with the jailed program
+run() {
+    if [ -n "$JAIL_START_FILE" ]; then
+        date +%s%N > "$JAIL_START_FILE"
+    fi
+    exec sudo chroot "$@"
+}
+
+# Remove the jail after the program finished
+if [ -n "$JAIL_CLEANUP" ]; then
+    if [ -n "$JAIL_ROOTFS" ]; then
+        sudo umount $1/exers
+        sudo umount $1
+        rmdir $1
+    else
+        rm -rf $1
+    fi
+    exit 0
+fi
+
+# Run the program inside prepared rootfs, with code directory mounted at /exers
+if [ -n "$JAIL_ROOTFS" ]; then
+    mkdir -p $1
+    sudo mount --bind $JAIL_ROOTFS $1
+    sudo mount -o remount,bind,ro $1
+    sudo mount --bind $2 $1/exers
+    sudo mount -o remount,bind,ro $1/exers
+
+    run $1 ${@:3}
+fi
 
 # Create jail directory if it doesn't exist
 mkdir -p $1
@@ -30,7 +66,4 @@ if [ $# -gt 2 ]; then
 fi
 
 # Run the program in the jail
-sudo chroot $1 /bin/$(basename $2) ${@:3}
-
-# Clean up
-rm -rf $1
\ No newline at end of file
+run $1 /bin/$(basename $2) ${@:3}
\ No newline at end of file
@@ -0,0 +1,237 @@
+//! Compiling code submitted as an archive of source files.
+
+use std::{
+    io::{self, Read},
+    path::{Component, Path},
+    process::Command,
+};
+
+use crate::{
+    compilers::{CompiledCode, Compiler},
+    runtimes::CodeRuntime,
+};
+
+use super::compiler::{resolve_program, CompilationResult};
+
+/// Archive containing source files (e.g. multi-file project). <br/>
+/// Entries escaping the target directory (absolute paths, `..`) and links are rejected.
+#[derive(Debug, Clone)]
+pub enum ArchiveSource {
+    /// Uncompressed tar archive.
+    Tar(Vec<u8>),
+    /// Gzip compressed tar archive (`.tar.gz`).
+    TarGz(Vec<u8>),
+    /// Zip archive, extracted using `unzip` program.
+    Zip(Vec<u8>),
+}
+
+impl ArchiveSource {
+    /// Extracts the archive into temporary directory and compiles the entry point
+    /// (path of a file relative to the root of the archive, e.g. `src/main.py`). <br/>
+    /// Interpreted languages run the entry point in place, so it can use other files from the archive.
+    /// Compilers that copy the code only see the entry point.
+    pub fn compile<R: CodeRuntime, C: Compiler<R>>(
+        &self,
+        compiler: &C,
+        entry_point: impl AsRef<Path>,
+        config: C::Config,
+    ) -> CompilationResult<CompiledCode<R>> {
+        let entry_point = entry_point.as_ref();
+        check_entry_path(entry_point)?;
+
+        let dir = tempfile::Builder::new()
+            .prefix("exers-archive-")
+            .tempdir()?;
+        self.extract_to(dir.path())?;
+
+        let compiled = compiler.compile_file(&dir.path().join(entry_point), config)?;
+
+        // Code run in place needs the sources, so they are removed along with it.
+        {
+            let mut temp_dir_handle = compiled.temp_dir_handle.lock().unwrap();
+            if temp_dir_handle.is_none() {
+                *temp_dir_handle = Some(dir);
+            }
+        }
+
+        Ok(compiled)
+    }
+
+    /// Extracts the archive into existing directory.
+    pub fn extract_to(&self, dir: &Path) -> CompilationResult<()> {
+        match self {
+            Self::Tar(bytes) => extract_tar(bytes.as_slice(), dir)?,
+            Self::TarGz(bytes) => extract_tar(flate2::read::GzDecoder::new(bytes.as_slice()), dir)?,
+            Self::Zip(bytes) => extract_zip(bytes, dir)?,
+        }
+        Ok(())
+    }
+}
+
+/// Checks that the path stays inside directory it's joined to.
+fn check_entry_path(path: &Path) -> io::Result<()> {
+    let is_inside = path
+        .components()
+        .all(|component| matches!(component, Component::Normal(_) | Component::CurDir));
+    if !is_inside {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("archive entry escapes target directory: {}", path.display()),
+        ));
+    }
+    Ok(())
+}
+
+/// Returns error for link entry, links could point outside of target directory.
+fn link_error(path: &Path) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("archive entry is a link: {}", path.display()),
+    )
+}
+
+/// Extracts tar archive, validating each entry before it's written.
+fn extract_tar(reader: impl Read, dir: &Path) -> io::Result<()> {
+    let mut archive = tar::Archive::new(reader);
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let path = entry.path()?.into_owned();
+        check_entry_path(&path)?;
+        if entry.header().entry_type().is_symlink() || entry.header().entry_type().is_hard_link() {
+            return Err(link_error(&path));
+        }
+        entry.unpack_in(dir)?;
+    }
+    Ok(())
+}
+
+/// Extracts zip archive using `unzip`, all entries are validated before anything is written.
+fn extract_zip(bytes: &[u8], dir: &Path) -> CompilationResult<()> {
+    let unzip = resolve_program("unzip")?;
+
+    // Write the archive next to the target directory, not into it.
+    let mut archive = tempfile::NamedTempFile::new()?;
+    io::Write::write_all(&mut archive, bytes)?;
+
+    // List entry names (`-Z1`) and their types (`-Z`, lines start with unix-like mode).
+    let names = unzip_output(Command::new(&unzip).arg("-Z1").arg(archive.path()))?;
+    for name in names.lines() {
+        check_entry_path(Path::new(name))?;
+    }
+    let listing = unzip_output(Command::new(&unzip).arg("-Z").arg(archive.path()))?;
+    if let Some(link) = listing.lines().find(|line| line.starts_with('l')) {
+        return Err(link_error(Path::new(
+            link.split_whitespace().last().unwrap_or_default(),
+        ))
+        .into());
+    }
+
+    unzip_output(
+        Command::new(&unzip)
+            .args(["-q", "-o"])
+            .arg(archive.path())
+            .arg("-d")
+            .arg(dir),
+    )?;
+    Ok(())
+}
+
+/// Runs `unzip` command and returns its stdout.
+fn unzip_output(command: &mut Command) -> io::Result<String> {
+    let output = command.output()?;
+    if !output.status.success() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "failed to extract zip archive: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            ),
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Creates tar archive with given files, paths are written as is (without validation).
+    fn tar(files: &[(&str, &str)]) -> Vec<u8> {
+        let mut builder = tar::Builder::new(Vec::new());
+        for (path, contents) in files {
+            let mut header = tar::Header::new_gnu();
+            header.as_gnu_mut().unwrap().name[..path.len()].copy_from_slice(path.as_bytes());
+            header.set_size(contents.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder.append(&header, contents.as_bytes()).unwrap();
+        }
+        builder.into_inner().unwrap()
+    }
+
+    #[cfg(all(feature = "python", feature = "native"))]
+    #[test]
+    fn test_compile_archive() {
+        use crate::{
+            compilers::python_compiler::PythonCompiler,
+            runtimes::{native_runtime::NativeRuntime, CodeRuntime},
+        };
+
+        let archive = tar(&[
+            ("app/main.py", "import helper\nhelper.greet()\n"),
+            (
+                "app/helper.py",
+                "def greet():\n    print('Hello from helper')\n",
+            ),
+        ]);
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), Default::default());
+        io::Write::write_all(&mut encoder, &archive).unwrap();
+        let archive = ArchiveSource::TarGz(encoder.finish().unwrap());
+
+        let compiled_code = archive
+            .compile(&PythonCompiler, "app/main.py", Default::default())
+            .unwrap();
+        let result = NativeRuntime
+            .run(&compiled_code, Default::default())
+            .unwrap();
+
+        assert_eq!(result.stdout, Some("Hello from helper\n".to_string()));
+    }
+
+    #[test]
+    fn test_extract_rejects_path_traversal() {
+        let parent = tempfile::tempdir().unwrap();
+        let dir = parent.path().join("dir");
+        std::fs::create_dir(&dir).unwrap();
+
+        let archive = ArchiveSource::Tar(tar(&[("../escaped.txt", "oops")]));
+        assert!(archive.extract_to(&dir).is_err());
+        assert!(!parent.path().join("escaped.txt").exists());
+
+        let archive = ArchiveSource::Tar(tar(&[("/tmp/escaped.txt", "oops")]));
+        assert!(archive.extract_to(&dir).is_err());
+    }
+
+    #[test]
+    fn test_extract_zip() {
+        let sources = tempfile::tempdir().unwrap();
+        std::fs::create_dir(sources.path().join("src")).unwrap();
+        std::fs::write(sources.path().join("src/main.txt"), "hello").unwrap();
+        let status = Command::new("zip")
+            .args(["-q", "-r", "archive.zip", "src"])
+            .current_dir(sources.path())
+            .status()
+            .unwrap();
+        assert!(status.success());
+
+        let archive =
+            ArchiveSource::Zip(std::fs::read(sources.path().join("archive.zip")).unwrap());
+        let dir = tempfile::tempdir().unwrap();
+        archive.extract_to(dir.path()).unwrap();
+
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join("src/main.txt")).unwrap(),
+            "hello"
+        );
+    }
+}
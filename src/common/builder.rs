@@ -1,10 +1,16 @@
 //! Implements builder pattern for exers.
 
-use std::{io::Read, ops::Deref, sync::Arc};
+use std::{
+    io::Read,
+    ops::Deref,
+    sync::{Arc, Mutex},
+};
+
+use sha2::{Digest, Sha256};
 
 use crate::{
     compilers::{CompiledCode, Compiler},
-    runtimes::{CodeRuntime, ExecutionResult},
+    runtimes::{CodeRuntime, ExecutionOutcome, ExecutionResult},
 };
 
 use super::{
@@ -31,7 +37,7 @@ use super::{
 /// }
 pub struct RuntimeBuilder<C: Compiler<R>, R: CodeRuntime> {
     /// Preprocessors that will be used to preprocess code.
-    preprocessors: Vec<Arc<dyn Preprocessor>>,
+    preprocessors: PreprocessorBundle,
     /// Compiler that will be used to compile code.
     compiler: Option<C>,
     /// Runtime that will be used to run code.
@@ -41,6 +47,28 @@ pub struct RuntimeBuilder<C: Compiler<R>, R: CodeRuntime> {
     compiler_config: Option<C::Config>,
     /// Config for runtime.
     runtime_config: Option<R::Config>,
+
+    /// Function applied to every execution result before returning it.
+    post_run: Option<PostRunHook>,
+    /// Function grading execution result, see [`CustomRuntime::run_validated`].
+    validator: Option<Validator>,
+    /// Whether compiled code is reused for the same input, see [`RuntimeBuilder::cache_compilation`].
+    cache_compilation: bool,
+}
+
+/// Function transforming execution result (e.g. normalizing output).
+pub type PostRunHook = Arc<dyn Fn(ExecutionResult) -> ExecutionResult + Send + Sync>;
+
+/// Function grading execution result (e.g. comparing output with expected one).
+pub type Validator = Arc<dyn Fn(&ExecutionResult) -> ValidatorVerdict + Send + Sync>;
+
+/// Verdict given by [`Validator`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ValidatorVerdict {
+    /// Code passed.
+    Pass,
+    /// Code failed, with reason (e.g. `wrong answer`).
+    Fail(String),
 }
 
 /// Errors that can occur while building compiler.
@@ -54,28 +82,49 @@ pub enum RuntimeBuilderError {
 
 type RuntimeBuilderResult<T> = Result<T, RuntimeBuilderError>;
 
+// Builder can be cloned if compiler and runtime can, configs are always `Clone`.
+impl<C: Compiler<R> + Clone, R: CodeRuntime + Clone> Clone for RuntimeBuilder<C, R> {
+    fn clone(&self) -> Self {
+        Self {
+            preprocessors: self.preprocessors.clone(),
+            compiler: self.compiler.clone(),
+            runtime: self.runtime.clone(),
+            compiler_config: self.compiler_config.clone(),
+            runtime_config: self.runtime_config.clone(),
+            post_run: self.post_run.clone(),
+            validator: self.validator.clone(),
+            cache_compilation: self.cache_compilation,
+        }
+    }
+}
+
 impl<C: Compiler<R> + 'static, R: CodeRuntime + 'static> RuntimeBuilder<C, R> {
     /// Creates new builder.
     pub const fn new() -> Self {
         Self {
-            preprocessors: Vec::new(),
+            preprocessors: PreprocessorBundle::new(),
             compiler: None,
             runtime: None,
             compiler_config: None,
             runtime_config: None,
+            post_run: None,
+            validator: None,
+            cache_compilation: false,
         }
     }
 
     /// Adds preprocessor to the builder.
     pub fn preprocessor(mut self, preprocessor: impl Preprocessor + 'static) -> Self {
-        self.preprocessors.push(Arc::new(preprocessor));
+        self.preprocessors = self.preprocessors.add_preprocessor(preprocessor);
         self
     }
 
-    /// Adds preprocessor bundle to the builder.
-    pub fn preprocessor_bundle(mut self, bundle: &PreprocessorBundle) -> Self {
+    /// Adds all preprocessors from the bundle to the builder.
+    /// They run after preprocessors that were added before.
+    pub fn preprocessor_bundle(mut self, bundle: PreprocessorBundle) -> Self {
         self.preprocessors
-            .extend(bundle.preprocessors.iter().cloned());
+            .preprocessors
+            .extend(bundle.preprocessors);
         self
     }
 
@@ -93,6 +142,37 @@ impl<C: Compiler<R> + 'static, R: CodeRuntime + 'static> RuntimeBuilder<C, R> {
         self
     }
 
+    /// Sets function that will be applied to every execution result before returning it.
+    /// This can be used to normalize output (e.g. trim whitespace) in one place.
+    pub fn post_run(
+        mut self,
+        post_run: impl Fn(ExecutionResult) -> ExecutionResult + Send + Sync + 'static,
+    ) -> Self {
+        self.post_run = Some(Arc::new(post_run));
+        self
+    }
+
+    /// Sets function grading execution result (after [`post_run`](Self::post_run) is applied). <br/>
+    /// Verdict is returned by [`CustomRuntime::run_validated`], running the code normally is not affected.
+    pub fn validator(
+        mut self,
+        validator: impl Fn(&ExecutionResult) -> ValidatorVerdict + Send + Sync + 'static,
+    ) -> Self {
+        self.validator = Some(Arc::new(validator));
+        self
+    }
+
+    /// Keeps the last compiled code and reuses it when the same code (after preprocessing) is run again,
+    /// instead of compiling it every time. <br/>
+    /// This speeds up running one program many times, e.g. with different stdin
+    /// (see [`CustomRuntime::run_with_config`]). Compilations wait for each other while it's enabled,
+    /// so the same code is never compiled twice at once. <br/>
+    /// Default: false
+    pub fn cache_compilation(mut self, cache_compilation: bool) -> Self {
+        self.cache_compilation = cache_compilation;
+        self
+    }
+
     /// Builds new compiler from builder.
     pub fn build(mut self) -> RuntimeBuilderResult<CustomRuntime<R>> {
         // Take compiler and runtime from builder.
@@ -109,51 +189,167 @@ impl<C: Compiler<R> + 'static, R: CodeRuntime + 'static> RuntimeBuilder<C, R> {
         let compiler_config = self.compiler_config.take().unwrap_or_default();
         let runtime_config = self.runtime_config.take().unwrap_or_default();
 
+        // Last compiled code with hash of its source.
+        let cache = self
+            .cache_compilation
+            .then(|| Mutex::new(None::<(Vec<u8>, CompiledCode<R>)>));
+
         // Compilation function
-        let cf = move |code: &mut dyn std::io::Read| -> CompilationResult<CompiledCode<R>> {
-            let mut code = std::io::BufReader::new(code);
-            let mut code_str = String::new();
-            code.read_to_string(&mut code_str).unwrap();
-            let mut code = code_str;
-
-            for preprocessor in self.preprocessors.iter() {
-                code = preprocessor.preprocess(&code)?;
+        let cf = move |mut code: &mut dyn Read| -> CompilationResult<CompiledCode<R>> {
+            #[cfg(feature = "tracing")]
+            let _span = tracing::debug_span!("exers::compile").entered();
+
+            let code = self.preprocessors.preprocess(&mut code)?;
+
+            let Some(cache) = &cache else {
+                return compiler.compile(&mut code.as_bytes(), compiler_config.clone());
+            };
+
+            // Lock is held while compiling, so concurrent runs of the same code compile it once.
+            let hash = Sha256::digest(code.as_bytes()).to_vec();
+            let mut cache = cache.lock().unwrap();
+            if let Some((cached_hash, compiled_code)) = &*cache {
+                if *cached_hash == hash {
+                    return Ok(compiled_code.clone());
+                }
             }
 
             let compiled_code = compiler.compile(&mut code.as_bytes(), compiler_config.clone())?;
+            *cache = Some((hash, compiled_code.clone()));
             Ok(compiled_code)
         };
 
         // Runtime function
-        let rf = move |compiled_code: &CompiledCode<R>| -> Result<ExecutionResult, R::Error> {
-            runtime.run(compiled_code, runtime_config.clone())
+        let post_run = self.post_run.take();
+        let rf = move |compiled_code: &CompiledCode<R>,
+                       config: Option<R::Config>|
+              -> Result<ExecutionResult, R::Error> {
+            let config = config.unwrap_or_else(|| runtime_config.clone());
+            let result = runtime.run(compiled_code, config)?;
+            Ok(match &post_run {
+                Some(post_run) => post_run(result),
+                None => result,
+            })
         };
 
-        Ok(CustomRuntime::new(cf, rf))
+        // Code passes by default if it ran successfully.
+        let validator = self.validator.take().unwrap_or_else(|| {
+            Arc::new(|result: &ExecutionResult| match result.outcome() {
+                ExecutionOutcome::Success => ValidatorVerdict::Pass,
+                outcome => ValidatorVerdict::Fail(format!("{:?}", outcome)),
+            })
+        });
+
+        Ok(CustomRuntime::new(cf, rf, validator))
+    }
+
+    /// Builds new compiler from builder, without consuming it.
+    /// This can be used to create many runtimes with the same configuration (e.g. for a pool).
+    pub fn build_cloned(&self) -> RuntimeBuilderResult<CustomRuntime<R>>
+    where
+        C: Clone,
+        R: Clone,
+    {
+        self.clone().build()
     }
 }
 
+/// Runtime created by [`RuntimeBuilder`]. <br/>
+/// Cloning it is cheap, clones share the same compiler and runtime.
+/// It's `Send + Sync`, so it can be shared between threads (e.g. in web server state).
 pub struct CustomRuntime<R: CodeRuntime> {
+    /// Compiler (with preprocessors).
+    cf: Arc<CompileFn<R>>,
+    /// Runtime, which uses config from the builder if no config is given.
+    rf: Arc<RunFn<R>>,
     /// Combination of compiler and runtime.
     #[allow(clippy::type_complexity)]
-    crf: Box<dyn Fn(&mut dyn std::io::Read) -> Result<ExecutionResult, CustomRuntimeError<R>>>,
+    crf: Arc<
+        dyn Fn(&mut dyn std::io::Read) -> Result<ExecutionResult, CustomRuntimeError<R>>
+            + Send
+            + Sync,
+    >,
+    /// Function grading execution result.
+    validator: Validator,
 }
 
-impl<R: CodeRuntime> CustomRuntime<R> {
+/// Compilation function of [`CustomRuntime`].
+type CompileFn<R> = dyn Fn(&mut dyn Read) -> CompilationResult<CompiledCode<R>> + Send + Sync;
+
+/// Runtime function of [`CustomRuntime`].
+type RunFn<R> = dyn Fn(
+        &CompiledCode<R>,
+        Option<<R as CodeRuntime>::Config>,
+    ) -> Result<ExecutionResult, <R as CodeRuntime>::Error>
+    + Send
+    + Sync;
+
+impl<R: CodeRuntime> Clone for CustomRuntime<R> {
+    fn clone(&self) -> Self {
+        Self {
+            cf: self.cf.clone(),
+            rf: self.rf.clone(),
+            crf: self.crf.clone(),
+            validator: self.validator.clone(),
+        }
+    }
+}
+
+impl<R: CodeRuntime + 'static> CustomRuntime<R> {
     /// Creates new custom runtime. This should be used only by builder.
-    #[allow(clippy::type_complexity)]
     pub(crate) fn new(
-        cf: impl Fn(&mut dyn std::io::Read) -> CompilationResult<CompiledCode<R>> + 'static,
-        rf: impl Fn(&CompiledCode<R>) -> Result<ExecutionResult, R::Error> + 'static,
+        cf: impl Fn(&mut dyn Read) -> CompilationResult<CompiledCode<R>> + Send + Sync + 'static,
+        rf: impl Fn(&CompiledCode<R>, Option<R::Config>) -> Result<ExecutionResult, R::Error>
+            + Send
+            + Sync
+            + 'static,
+        validator: Validator,
     ) -> Self {
+        let cf: Arc<CompileFn<R>> = Arc::new(cf);
+        let rf: Arc<RunFn<R>> = Arc::new(rf);
         Self {
-            crf: Box::new(move |code| {
-                let compiled_code =
-                    cf(code).map_err(|e| CustomRuntimeError::CompilationError(e))?;
-                (rf)(&compiled_code).map_err(|e| CustomRuntimeError::RuntimeError(e))
+            crf: Arc::new({
+                let (cf, rf) = (cf.clone(), rf.clone());
+                move |code| {
+                    let compiled_code =
+                        cf(code).map_err(|e| CustomRuntimeError::CompilationError(e))?;
+                    (rf)(&compiled_code, None).map_err(|e| CustomRuntimeError::RuntimeError(e))
+                }
             }),
+            cf,
+            rf,
+            validator,
         }
     }
+}
+
+impl<R: CodeRuntime> CustomRuntime<R> {
+    /// Preprocesses and compiles the code, without running it. <br/>
+    /// With [`RuntimeBuilder::cache_compilation`] enabled, code that was compiled last is reused.
+    pub fn compile(&self, code: &mut dyn Read) -> CompilationResult<CompiledCode<R>> {
+        (self.cf)(code)
+    }
+
+    /// Runs already compiled code, with given config or the one from builder (if `None`).
+    pub fn run_compiled(
+        &self,
+        code: &CompiledCode<R>,
+        config: Option<R::Config>,
+    ) -> Result<ExecutionResult, R::Error> {
+        (self.rf)(code, config)
+    }
+
+    /// Compiles and runs code like [`CustomRuntime::run`], but with given runtime config
+    /// instead of the one from builder (e.g. with different stdin).
+    pub fn run_with_config(
+        &self,
+        code: &mut dyn Read,
+        config: R::Config,
+    ) -> Result<ExecutionResult, CustomRuntimeError<R>> {
+        let compiled_code = self.compile(code)?;
+        self.run_compiled(&compiled_code, Some(config))
+            .map_err(CustomRuntimeError::RuntimeError)
+    }
 
     /// Compiles and runs code using custom compiler and runtime.
     pub fn run(
@@ -162,14 +358,28 @@ impl<R: CodeRuntime> CustomRuntime<R> {
     ) -> Result<ExecutionResult, CustomRuntimeError<R>> {
         (self.crf)(code)
     }
+
+    /// Compiles and runs code like [`CustomRuntime::run`], then grades the result
+    /// using validator set in the builder (see [`RuntimeBuilder::validator`]). <br/>
+    /// Without validator, code passes if it ran successfully.
+    pub fn run_validated(
+        &self,
+        code: &mut dyn std::io::Read,
+    ) -> Result<(ExecutionResult, ValidatorVerdict), CustomRuntimeError<R>> {
+        let result = (self.crf)(code)?;
+        let verdict = (self.validator)(&result);
+        Ok((result, verdict))
+    }
 }
 
 #[allow(clippy::type_complexity)]
 impl<R: CodeRuntime + 'static> Deref for CustomRuntime<R> {
-    type Target = dyn Fn(&mut dyn std::io::Read) -> Result<ExecutionResult, CustomRuntimeError<R>>;
+    type Target = dyn Fn(&mut dyn std::io::Read) -> Result<ExecutionResult, CustomRuntimeError<R>>
+        + Send
+        + Sync;
 
     fn deref(&self) -> &Self::Target {
-        &self.crf
+        &*self.crf
     }
 }
 
@@ -190,8 +400,11 @@ impl<R: CodeRuntime> From<CompilationError> for CustomRuntimeError<R> {
 
 #[cfg(test)]
 mod tests {
+    #[cfg(feature = "native")]
+    use crate::runtimes::native_runtime::NativeRuntime;
     #[cfg(feature = "wasm")]
-    use crate::{compilers::rust_compiler::RustCompiler, runtimes::wasm_runtime::WasmRuntime};
+    use crate::runtimes::wasm_runtime::WasmRuntime;
+    use crate::{compilers::rust_compiler::RustCompiler, runtimes::ExecutionResult};
 
     use super::RuntimeBuilder;
 
@@ -227,4 +440,224 @@ mod tests {
             Some("Hello, world!\nHello, world!\n".to_string())
         );
     }
+
+    #[test]
+    #[cfg(feature = "native")]
+    fn test_builder_preprocessor_bundle() {
+        use crate::common::preprocessor::PreprocessorBundle;
+
+        let bundle = PreprocessorBundle::new()
+            .add_preprocessor(|code: &str| Ok(code.replace("NAME", "world")));
+        let rust_native_runtime = RuntimeBuilder::new()
+            .preprocessor(|code: &str| Ok(code.replace("GREETING", "Hello")))
+            .preprocessor_bundle(bundle)
+            .compiler(RustCompiler, None)
+            .runtime(NativeRuntime, None)
+            .build()
+            .unwrap();
+
+        let code = r#"
+            fn main() {
+                println!("GREETING, NAME!");
+            }
+        "#;
+
+        assert_eq!(
+            rust_native_runtime(&mut code.as_bytes()).unwrap().stdout,
+            Some("Hello, world!\n".to_string())
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "native")]
+    fn test_builder_preprocessor_error() {
+        use crate::common::{compiler::CompilationError, preprocessor::PreprocessorError};
+
+        let rust_native_runtime = RuntimeBuilder::new()
+            .preprocessor(|_: &str| Err(PreprocessorError::Other("invalid code".into())))
+            .compiler(RustCompiler, None)
+            .runtime(NativeRuntime, None)
+            .build()
+            .unwrap();
+
+        assert!(matches!(
+            rust_native_runtime(&mut "fn main() {}".as_bytes()),
+            Err(super::CustomRuntimeError::CompilationError(
+                CompilationError::PreprocessorError(PreprocessorError::Other(_))
+            ))
+        ));
+    }
+
+    #[test]
+    #[cfg(feature = "native")]
+    fn test_builder_build_cloned() {
+        let builder = RuntimeBuilder::new()
+            .compiler(RustCompiler, None)
+            .runtime(NativeRuntime, None);
+        let runtimes = [
+            builder.build_cloned().unwrap(),
+            builder.build_cloned().unwrap(),
+            builder.build().unwrap(),
+        ];
+
+        let code = r#"
+            fn main() {
+                println!("Hello, world!");
+            }
+        "#;
+
+        for runtime in runtimes.iter().chain([&runtimes[0].clone()]) {
+            assert_eq!(
+                runtime(&mut code.as_bytes()).unwrap().stdout,
+                Some("Hello, world!\n".to_string())
+            );
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "native")]
+    fn test_builder_runtime_in_thread() {
+        let rust_native_runtime = RuntimeBuilder::new()
+            .compiler(RustCompiler, None)
+            .runtime(NativeRuntime, None)
+            .build()
+            .unwrap();
+
+        let code = r#"
+            fn main() {
+                println!("Hello, world!");
+            }
+        "#;
+
+        let result = std::thread::spawn(move || rust_native_runtime(&mut code.as_bytes()))
+            .join()
+            .unwrap();
+        assert_eq!(result.unwrap().stdout, Some("Hello, world!\n".to_string()));
+    }
+
+    #[test]
+    #[cfg(feature = "native")]
+    fn test_builder_post_run() {
+        let rust_native_runtime = RuntimeBuilder::new()
+            .compiler(RustCompiler, None)
+            .runtime(NativeRuntime, None)
+            .post_run(|result| ExecutionResult {
+                stdout: result.stdout.map(|stdout| stdout.trim().to_string()),
+                ..result
+            })
+            .build()
+            .unwrap();
+
+        let code = r#"
+            fn main() {
+                println!("  Hello, world!  ");
+            }
+        "#;
+
+        assert_eq!(
+            rust_native_runtime(&mut code.as_bytes()).unwrap().stdout,
+            Some("Hello, world!".to_string())
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "native")]
+    fn test_builder_validator() {
+        use super::ValidatorVerdict;
+        use crate::{common::runtime::InputData, runtimes::native_runtime::NativeConfig};
+
+        let code = r#"
+            fn main() {
+                let mut line = String::new();
+                std::io::stdin().read_line(&mut line).unwrap();
+                println!("{}", line.trim().parse::<i32>().unwrap() * 2);
+            }
+        "#;
+        let builder = RuntimeBuilder::new().compiler(RustCompiler, None);
+
+        // Without validator, code passes if it runs successfully.
+        let runtime = builder
+            .clone()
+            .runtime(NativeRuntime, None)
+            .build()
+            .unwrap();
+        let (result, verdict) = runtime.run_validated(&mut code.as_bytes()).unwrap();
+        assert!(!result.success());
+        assert!(matches!(verdict, ValidatorVerdict::Fail(_)));
+
+        let runtime = builder
+            .runtime(
+                NativeRuntime,
+                Some(NativeConfig {
+                    stdin: InputData::String("21".to_string()),
+                    ..Default::default()
+                }),
+            )
+            .validator(|result| match result.stdout.as_deref() {
+                Some("42\n") => ValidatorVerdict::Pass,
+                _ => ValidatorVerdict::Fail("wrong answer".to_string()),
+            })
+            .build()
+            .unwrap();
+        let (result, verdict) = runtime.run_validated(&mut code.as_bytes()).unwrap();
+        assert_eq!(result.stdout, Some("42\n".to_string()));
+        assert_eq!(verdict, ValidatorVerdict::Pass);
+
+        // Running normally is not affected.
+        assert!(runtime(&mut code.as_bytes()).unwrap().success());
+    }
+
+    #[test]
+    #[cfg(feature = "native")]
+    fn test_builder_cache_compilation() {
+        use crate::{common::runtime::InputData, runtimes::native_runtime::NativeConfig};
+
+        let code = r#"
+            fn main() {
+                let mut line = String::new();
+                std::io::stdin().read_line(&mut line).unwrap();
+                println!("{}", line.trim().parse::<i32>().unwrap() * 2);
+            }
+        "#;
+        let builder = RuntimeBuilder::new()
+            .compiler(RustCompiler, None)
+            .runtime(NativeRuntime, None);
+        let config = |input: &str| NativeConfig {
+            stdin: InputData::String(input.to_string()),
+            ..Default::default()
+        };
+        let executable = |compiled_code: super::CompiledCode<NativeRuntime>| {
+            compiled_code.executable_path().unwrap().to_path_buf()
+        };
+
+        // Same code is compiled once and run with different stdin.
+        let runtime = builder.clone().cache_compilation(true).build().unwrap();
+        let compiled_code = runtime.compile(&mut code.as_bytes()).unwrap();
+        for (input, output) in [("1", "2\n"), ("21", "42\n")] {
+            let result = runtime
+                .run_with_config(&mut code.as_bytes(), config(input))
+                .unwrap();
+            assert_eq!(result.stdout.as_deref(), Some(output));
+        }
+        assert_eq!(
+            executable(runtime.compile(&mut code.as_bytes()).unwrap()),
+            executable(compiled_code.clone())
+        );
+
+        // Other code is compiled again.
+        let other_code = "fn main() { println!(\"Hello, world!\"); }";
+        let result = runtime.run(&mut other_code.as_bytes()).unwrap();
+        assert_eq!(result.stdout, Some("Hello, world!\n".to_string()));
+
+        // Without cache, code is compiled every time.
+        let runtime = builder.build().unwrap();
+        assert_ne!(
+            executable(runtime.compile(&mut code.as_bytes()).unwrap()),
+            executable(runtime.compile(&mut code.as_bytes()).unwrap())
+        );
+        let result = runtime
+            .run_compiled(&compiled_code, Some(config("5")))
+            .unwrap();
+        assert_eq!(result.stdout, Some("10\n".to_string()));
+    }
 }
@@ -0,0 +1,329 @@
+//! Opt-in cache for compiled code.
+
+use std::{
+    any::Any,
+    collections::HashMap,
+    fs,
+    io::{self, Read},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use sha2::{Digest, Sha256};
+
+use crate::{
+    compilers::{CompiledCode, Compiler, Executable},
+    runtimes::CodeRuntime,
+};
+
+use super::compiler::CompilationResult;
+
+/// Storage for cached executables.
+/// Implement this trait to plug your own cache backend into [`CompilerCache`].
+pub trait CacheBackend: Send + Sync {
+    /// Returns path to the executable stored under given key (if any).
+    fn get(&self, key: &str) -> Option<PathBuf>;
+
+    /// Stores copy of the work directory containing the executable (at `executable`, relative to it)
+    /// under given key and returns path to the stored executable. <br/>
+    /// Whole directory is stored, as the executable may need other files from it (e.g. extra source files).
+    fn put(&self, key: &str, work_dir: &Path, executable: &Path) -> io::Result<PathBuf>;
+}
+
+/// Cache backend that keeps executables in a persistent directory.
+/// Each entry is stored as `<directory>/<key>/`, containing copy of the work directory in `files`
+/// and path of the executable inside it in `executable`.
+#[derive(Debug, Clone)]
+pub struct DirectoryCache {
+    /// Directory containing cached executables.
+    path: PathBuf,
+}
+
+impl DirectoryCache {
+    /// Creates new directory cache. Directory is created if it doesn't exist.
+    pub fn new(path: impl Into<PathBuf>) -> io::Result<Self> {
+        let path = path.into();
+        fs::create_dir_all(&path)?;
+        Ok(Self { path })
+    }
+}
+
+impl CacheBackend for DirectoryCache {
+    fn get(&self, key: &str) -> Option<PathBuf> {
+        let entry = self.path.join(key);
+        let executable = fs::read_to_string(entry.join("executable")).ok()?;
+        Some(entry.join("files").join(executable))
+    }
+
+    fn put(&self, key: &str, work_dir: &Path, executable: &Path) -> io::Result<PathBuf> {
+        let executable = executable.to_str().ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidInput, "invalid executable path")
+        })?;
+
+        // Copy into a temporary entry first, so other processes never see partial files.
+        let entry = tempfile::Builder::new()
+            .prefix(".entry-")
+            .tempdir_in(&self.path)?;
+        copy_dir(work_dir, &entry.path().join("files"))?;
+        fs::write(entry.path().join("executable"), executable)?;
+
+        // If entry already exists, someone else has stored it in the meantime.
+        let destination = self.path.join(key);
+        if fs::rename(entry.path(), &destination).is_err() && !destination.exists() {
+            return Err(io::Error::other("failed to store cache entry"));
+        }
+
+        Ok(destination.join("files").join(executable))
+    }
+}
+
+/// Copies directory with all its contents.
+fn copy_dir(from: &Path, to: &Path) -> io::Result<()> {
+    fs::create_dir_all(to)?;
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        if entry.file_type()?.is_dir() {
+            copy_dir(&entry.path(), &to.join(entry.file_name()))?;
+        } else {
+            fs::copy(entry.path(), to.join(entry.file_name()))?;
+        }
+    }
+    Ok(())
+}
+
+/// Cache for compiled code.
+/// Entries are keyed by hash of the source code, compiler configuration and compiler/runtime types,
+/// so compiling identical code again returns cached executable instead of running the compiler.
+///
+/// Only standalone code (with default additional data and executable inside its work directory)
+/// is stored in the backend, as additional data can't be persisted.
+/// Other code (like interpreted languages, which need the interpreter in additional data)
+/// is kept in memory along with its temporary directory, so it's only cached for the lifetime of the cache.
+///
+/// # Example
+/// ```ignore
+/// let cache = CompilerCache::new(DirectoryCache::new("/var/cache/exers")?);
+/// let compiled: CompiledCode<NativeRuntime> =
+///     cache.compile(&RustCompiler, &mut code.as_bytes(), Default::default())?;
+/// ```
+pub struct CompilerCache<B: CacheBackend = DirectoryCache> {
+    /// Backend storing executables.
+    backend: B,
+    /// Cached code which can't be stored in the backend.
+    in_memory: Mutex<HashMap<String, Box<dyn Any + Send + Sync>>>,
+}
+
+impl<B: CacheBackend> CompilerCache<B> {
+    /// Creates new compiler cache using given backend.
+    pub fn new(backend: B) -> Self {
+        Self {
+            backend,
+            in_memory: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Compiles the code using given compiler, or returns cached executable if the same code
+    /// has already been compiled with the same configuration. <br/>
+    /// Code returned from the backend is not owned by a temporary directory, so it's not deleted on drop.
+    pub fn compile<R, C>(
+        &self,
+        compiler: &C,
+        code: &mut impl Read,
+        config: C::Config,
+    ) -> CompilationResult<CompiledCode<R>>
+    where
+        R: CodeRuntime + 'static,
+        C: Compiler<R>,
+    {
+        let mut source = Vec::new();
+        code.read_to_end(&mut source)?;
+
+        let key = Self::key::<R, C>(&source, &config);
+
+        // Return cached code if present.
+        if let Some(compiled) = self
+            .in_memory
+            .lock()
+            .unwrap()
+            .get(&key)
+            .and_then(|compiled| compiled.downcast_ref::<CompiledCode<R>>())
+        {
+            return Ok(compiled.clone());
+        }
+        if let Some(executable) = self.backend.get(&key) {
+            return Ok(Self::cached_code(executable, String::new()));
+        }
+
+        // Compile the code and store it in the cache.
+        let compiled = compiler.compile(&mut source.as_slice(), config)?;
+        let standalone = format!("{:?}", compiled.additional_data)
+            == format!("{:?}", R::AdditionalData::default());
+        let stored = match (&compiled.executable, compiled.work_dir()) {
+            (Some(Executable::Path(executable)), Some(work_dir)) if standalone => {
+                match executable.strip_prefix(&work_dir) {
+                    Ok(relative) => Some(self.backend.put(&key, &work_dir, relative)?),
+                    Err(_) => None,
+                }
+            }
+            _ => None,
+        };
+
+        match stored {
+            Some(executable) => Ok(Self::cached_code(executable, compiled.warnings.clone())),
+            None => {
+                self.in_memory
+                    .lock()
+                    .unwrap()
+                    .insert(key, Box::new(compiled.clone()));
+                Ok(compiled)
+            }
+        }
+    }
+
+    /// Computes cache key for given source and configuration.
+    /// Configuration is hashed by its debug representation, as it's the only one every configuration has.
+    fn key<R: CodeRuntime, C: Compiler<R>>(source: &[u8], config: &C::Config) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(std::any::type_name::<C>());
+        hasher.update([0]);
+        hasher.update(std::any::type_name::<R>());
+        hasher.update([0]);
+        hasher.update(format!("{:?}", config));
+        hasher.update([0]);
+        hasher.update(source);
+        format!("{:x}", hasher.finalize())
+    }
+
+    /// Creates compiled code pointing to executable stored in the backend.
+    fn cached_code<R: CodeRuntime>(executable: PathBuf, warnings: String) -> CompiledCode<R> {
+        CompiledCode {
+            executable: Some(Executable::Path(executable)),
+            temp_dir_handle: Arc::new(Mutex::new(None)),
+            additional_data: Default::default(),
+            warnings,
+            command_line: None,
+            runtime_marker: std::marker::PhantomData,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "native")]
+    #[test]
+    fn test_compiler_cache_compiles_once() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        use super::*;
+        use crate::{
+            compilers::rust_compiler::{RustCompiler, RustCompilerConfig},
+            runtimes::native_runtime::NativeRuntime,
+        };
+
+        // Compiler that counts how many times it was invoked.
+        struct SpyCompiler(AtomicUsize);
+
+        impl Compiler<NativeRuntime> for SpyCompiler {
+            type Config = RustCompilerConfig;
+            const NAME: &'static str = "spy";
+
+            fn compile(
+                &self,
+                code: &mut impl Read,
+                config: Self::Config,
+            ) -> CompilationResult<CompiledCode<NativeRuntime>> {
+                self.0.fetch_add(1, Ordering::SeqCst);
+                RustCompiler.compile(code, config)
+            }
+        }
+
+        let code = r#"
+            fn main() {
+                println!("Hello, world!");
+            }
+        "#;
+
+        let cache_dir = tempfile::tempdir().unwrap();
+        let cache = CompilerCache::new(DirectoryCache::new(cache_dir.path()).unwrap());
+        let compiler = SpyCompiler(AtomicUsize::new(0));
+
+        let first: CompiledCode<NativeRuntime> = cache
+            .compile(&compiler, &mut code.as_bytes(), Default::default())
+            .unwrap();
+        let second: CompiledCode<NativeRuntime> = cache
+            .compile(&compiler, &mut code.as_bytes(), Default::default())
+            .unwrap();
+
+        assert_eq!(compiler.0.load(Ordering::SeqCst), 1);
+        assert_eq!(first.executable, second.executable);
+
+        // Cached executable must survive dropping the compiled code.
+        drop(first);
+        let result = NativeRuntime.run(&second, Default::default()).unwrap();
+        assert_eq!(result.stdout, Some("Hello, world!\n".to_string()));
+    }
+
+    #[test]
+    fn test_directory_cache_keeps_work_dir() {
+        use super::*;
+
+        let work_dir = tempfile::tempdir().unwrap();
+        fs::write(work_dir.path().join("executable"), "main").unwrap();
+        fs::create_dir(work_dir.path().join("lib")).unwrap();
+        fs::write(work_dir.path().join("lib/helper"), "helper").unwrap();
+
+        let cache_dir = tempfile::tempdir().unwrap();
+        let cache = DirectoryCache::new(cache_dir.path()).unwrap();
+        let stored = cache
+            .put("key", work_dir.path(), Path::new("executable"))
+            .unwrap();
+        drop(work_dir);
+
+        assert_eq!(cache.get("key"), Some(stored.clone()));
+        assert_eq!(fs::read_to_string(&stored).unwrap(), "main");
+        let helper = stored.parent().unwrap().join("lib/helper");
+        assert_eq!(fs::read_to_string(helper).unwrap(), "helper");
+        assert_eq!(cache.get("other"), None);
+    }
+
+    #[cfg(all(feature = "native", feature = "python"))]
+    #[test]
+    fn test_compiler_cache_interpreted_code() {
+        use super::*;
+        use crate::{
+            compilers::python_compiler::{PythonCompiler, PythonCompilerConfig},
+            runtimes::native_runtime::NativeRuntime,
+        };
+
+        let code = "import helper\nprint(helper.VALUE)\n";
+        let config = |value: &str| PythonCompilerConfig {
+            extra_files: [(
+                PathBuf::from("helper.py"),
+                format!("VALUE = {}\n", value).into_bytes(),
+            )]
+            .into(),
+            ..Default::default()
+        };
+        let run = |cache: &CompilerCache, value: &str| {
+            let compiled: CompiledCode<NativeRuntime> = cache
+                .compile(&PythonCompiler, &mut code.as_bytes(), config(value))
+                .unwrap();
+            NativeRuntime
+                .run(&compiled, Default::default())
+                .unwrap()
+                .stdout
+        };
+
+        let cache_dir = tempfile::tempdir().unwrap();
+        let cache = CompilerCache::new(DirectoryCache::new(cache_dir.path()).unwrap());
+
+        // Extra files are part of the key, and are kept along with the code.
+        assert_eq!(run(&cache, "1"), Some("1\n".to_string()));
+        assert_eq!(run(&cache, "2"), Some("2\n".to_string()));
+        assert_eq!(run(&cache, "1"), Some("1\n".to_string()));
+
+        // Code needing additional data is not served from the backend to other caches.
+        let other = CompilerCache::new(DirectoryCache::new(cache_dir.path()).unwrap());
+        assert_eq!(run(&other, "1"), Some("1\n".to_string()));
+    }
+}
@@ -0,0 +1,125 @@
+//! Comparing output of the code with the expected one. <br/>
+//! Comparators work on any text, e.g. [`ExecutionResult::stdout`](crate::runtimes::ExecutionResult::stdout).
+
+/// Way of checking whether output of the code matches the expected output.
+pub trait OutputComparator {
+    /// Returns true if `actual` output matches `expected` output.
+    fn matches(&self, expected: &str, actual: &str) -> bool;
+}
+
+/// Output has to be exactly the same (byte by byte).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ExactComparator;
+
+impl OutputComparator for ExactComparator {
+    fn matches(&self, expected: &str, actual: &str) -> bool {
+        expected.as_bytes() == actual.as_bytes()
+    }
+}
+
+/// Whitespace at the end of the output (e.g. missing or additional final newline) is ignored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct TrimmedComparator;
+
+impl OutputComparator for TrimmedComparator {
+    fn matches(&self, expected: &str, actual: &str) -> bool {
+        expected.trim_end() == actual.trim_end()
+    }
+}
+
+/// Output is compared line by line. <br/>
+/// Trailing whitespace of every line (including `\r` of windows line endings) and trailing empty lines are ignored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct LineComparator;
+
+impl LineComparator {
+    /// Returns lines without trailing whitespace, skipping trailing empty lines.
+    fn lines(text: &str) -> impl Iterator<Item = &str> {
+        text.trim_end().lines().map(str::trim_end)
+    }
+}
+
+impl OutputComparator for LineComparator {
+    fn matches(&self, expected: &str, actual: &str) -> bool {
+        Self::lines(expected).eq(Self::lines(actual))
+    }
+}
+
+/// Output is compared token by token (split on any whitespace). <br/>
+/// Tokens that are both numbers match if they are [within `epsilon`](within_epsilon),
+/// other tokens have to be the same.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TokenComparator {
+    /// Maximum absolute or relative difference between numbers.
+    pub epsilon: f64,
+}
+
+impl OutputComparator for TokenComparator {
+    fn matches(&self, expected: &str, actual: &str) -> bool {
+        let mut expected = expected.split_whitespace();
+        let mut actual = actual.split_whitespace();
+        loop {
+            match (expected.next(), actual.next()) {
+                (None, None) => return true,
+                (Some(expected), Some(actual)) => {
+                    let matches = match (expected.parse(), actual.parse()) {
+                        (Ok(expected), Ok(actual)) => {
+                            within_epsilon(expected, actual, self.epsilon)
+                        }
+                        _ => expected == actual,
+                    };
+                    if !matches {
+                        return false;
+                    }
+                }
+                _ => return false,
+            }
+        }
+    }
+}
+
+/// Returns true if `actual` differs from `expected` by at most `eps`,
+/// either absolutely or relatively to `expected` (so large numbers don't need all digits to be exact). <br/>
+/// E.g. `0.1` and `0.10000001` are within `1e-6`, infinities match only themselves and `NaN` matches nothing.
+pub fn within_epsilon(expected: f64, actual: f64, eps: f64) -> bool {
+    if expected == actual {
+        return true;
+    }
+
+    let difference = (expected - actual).abs();
+    difference.is_finite() && (difference <= eps || difference <= eps * expected.abs())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_within_epsilon() {
+        assert!(within_epsilon(0.1, 0.10000001, 1e-6));
+        assert!(within_epsilon(1e12, 1e12 + 10.0, 1e-6));
+        assert!(within_epsilon(f64::INFINITY, f64::INFINITY, 1e-6));
+        assert!(!within_epsilon(0.1, 0.1001, 1e-6));
+        assert!(!within_epsilon(f64::NAN, f64::NAN, 1e-6));
+        assert!(!within_epsilon(f64::INFINITY, 1e300, 1e-6));
+    }
+
+    #[test]
+    fn test_comparators() {
+        assert!(ExactComparator.matches("1 2\n", "1 2\n"));
+        assert!(!ExactComparator.matches("1 2\n", "1 2"));
+
+        assert!(TrimmedComparator.matches("1 2\n", "1 2  \n\n"));
+        assert!(!TrimmedComparator.matches("1\n2\n", "1 \n2\n"));
+
+        assert!(LineComparator.matches("1 2\n3", "1 2  \r\n3\n\n"));
+        assert!(!LineComparator.matches("1 2\n", "1  2\n"));
+        assert!(!LineComparator.matches("1\n2\n", "1\n\n2\n"));
+
+        let tokens = TokenComparator { epsilon: 1e-6 };
+        assert!(tokens.matches("0.333333333\nyes", "0.3333333 yes\n"));
+        assert!(!tokens.matches("0.333333333", "0.33"));
+        assert!(!tokens.matches("1 yes", "1 no"));
+        assert!(!tokens.matches("1", "1 2"));
+    }
+}
@@ -1,4 +1,13 @@
-use std::{error::Error, fmt::Display};
+use std::{
+    collections::BTreeMap,
+    error::Error,
+    fmt::Display,
+    io::{Read, Write},
+    path::{Component, Path, PathBuf},
+    process::{Child, Command, ExitStatus, Output, Stdio},
+    thread::JoinHandle,
+    time::{Duration, Instant},
+};
 
 use super::preprocessor::PreprocessorError;
 
@@ -23,6 +32,8 @@ pub enum OptLevel {
 }
 
 impl OptLevel {
+    /// Returns level as used in gcc-style `-O<level>` argument (e.g. `fast` for `Speed`). <br/>
+    /// Compilers translate levels on their own, as meaning of these differs between them.
     pub fn as_stanard_opt_char(&self) -> String {
         match self {
             OptLevel::None => "0",
@@ -39,10 +50,291 @@ impl OptLevel {
 
 /// Checks if program is installed and panic with nice message if it is not.
 pub fn check_program_installed(program: &str) -> Result<(), CompilationError> {
-    if which::which(program).is_err() {
-        Err(CompilationError::ProgramNotInstalled(program.to_string()))
+    resolve_program(program).map(|_| ())
+}
+
+/// Finds the program in `PATH` and returns path to it.
+/// Returns `ProgramNotInstalled` error if it can't be found.
+pub fn resolve_program(program: &str) -> CompilationResult<PathBuf> {
+    which::which(program).map_err(|_| CompilationError::ProgramNotInstalled(program.to_string()))
+}
+
+/// Returns program running interpreted code, which is `interpreter_path` if it's set
+/// and `default` (looked up in `PATH`) otherwise. <br/>
+/// Returns `ProgramNotInstalled` error if the program can't be found.
+pub(crate) fn interpreter_program(
+    interpreter_path: Option<&Path>,
+    default: &str,
+) -> CompilationResult<String> {
+    let program = match interpreter_path {
+        Some(path) => path.to_string_lossy().into_owned(),
+        None => default.to_string(),
+    };
+    check_program_installed(&program)?;
+    Ok(program)
+}
+
+/// Checks that the name is a plain file name (e.g. `main.py`), so the file is created directly in the code directory.
+/// Other names are rejected with [`InvalidInput`](std::io::ErrorKind::InvalidInput) error.
+pub fn check_file_name(name: &str) -> CompilationResult<()> {
+    let mut components = Path::new(name).components();
+    match (components.next(), components.next()) {
+        (Some(Component::Normal(_)), None) => Ok(()),
+        _ => Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("invalid file name: {}", name),
+        )
+        .into()),
+    }
+}
+
+/// Writes additional files (e.g. modules imported by the code) to the directory, creating parent directories. <br/>
+/// Paths are relative to the directory, paths escaping it (absolute or with `..`)
+/// are rejected with [`InvalidInput`](std::io::ErrorKind::InvalidInput) error.
+pub fn write_extra_files(dir: &Path, files: &BTreeMap<PathBuf, Vec<u8>>) -> CompilationResult<()> {
+    for (path, contents) in files {
+        let is_inside = path
+            .components()
+            .all(|component| matches!(component, Component::Normal(_) | Component::CurDir));
+        if !is_inside || path.as_os_str().is_empty() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("extra file escapes code directory: {}", path.display()),
+            )
+            .into());
+        }
+
+        let path = dir.join(path);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, contents)?;
+    }
+
+    Ok(())
+}
+
+/// Returns version of the program, which is the first line of `<program> --version` output. <br/>
+/// This is useful for reporting which toolchain was used to compile the code.
+pub fn program_version(program: &str) -> CompilationResult<String> {
+    let path = resolve_program(program)?;
+    let output = Command::new(path)
+        .arg("--version")
+        .stdin(Stdio::null())
+        .output()?;
+
+    // Some programs (e.g. older pythons) print version to stderr.
+    let output = if output.stdout.is_empty() {
+        output.stderr
     } else {
-        Ok(())
+        output.stdout
+    };
+
+    Ok(String::from_utf8_lossy(&output)
+        .lines()
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_string())
+}
+
+/// Runs the compiler command and waits for it to finish.
+/// Returns stderr of the compiler (warnings) if compilation was successful.
+/// If `timeout` is exceeded, the compiler is killed and `CompilationFailed` error is returned.
+/// Spawning is retried up to `spawn_retries` times, see [`spawn_with_retries`].
+pub(crate) fn run_compiler(
+    command: &mut Command,
+    timeout: Option<Duration>,
+    spawn_retries: u32,
+) -> CompilationResult<String> {
+    run_compiler_with_stdin(command, None, timeout, spawn_retries)
+}
+
+/// Like [`run_compiler`], but writes `stdin` (if any) to stdin of the compiler,
+/// for compilers reading the code from `-` instead of a file.
+pub(crate) fn run_compiler_with_stdin(
+    command: &mut Command,
+    stdin: Option<Vec<u8>>,
+    timeout: Option<Duration>,
+    spawn_retries: u32,
+) -> CompilationResult<String> {
+    command.stderr(Stdio::piped());
+    command.stdout(Stdio::null());
+    command.stdin(match stdin {
+        Some(_) => Stdio::piped(),
+        None => Stdio::null(),
+    });
+
+    let output = compiler_output_with_stdin(command, stdin, timeout, spawn_retries)?;
+    let stderr = String::from_utf8_lossy(&output.stderr).to_string();
+
+    // Check if compilation was successful.
+    if !output.status.success() {
+        return Err(CompilationError::failed(
+            command,
+            Some(output.status),
+            stderr,
+        ));
+    }
+
+    Ok(stderr)
+}
+
+/// Returns command line of the command (program followed by arguments), as it would be typed in shell.
+/// Arguments containing whitespace or quotes are quoted.
+pub(crate) fn command_line(command: &Command) -> String {
+    std::iter::once(command.get_program())
+        .chain(command.get_args())
+        .map(|arg| {
+            let arg = arg.to_string_lossy();
+            if arg.is_empty() || arg.contains(|c: char| c.is_whitespace() || c == '"' || c == '\'')
+            {
+                format!("{:?}", arg)
+            } else {
+                arg.into_owned()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Spawns the compiler command and collects its output, like [`Command::output`]. <br/>
+/// If `timeout` is exceeded, the compiler is killed and `CompilationFailed` error is returned.
+#[cfg(feature = "csharp")]
+pub(crate) fn compiler_output(
+    command: &mut Command,
+    timeout: Option<Duration>,
+    spawn_retries: u32,
+) -> CompilationResult<Output> {
+    compiler_output_with_stdin(command, None, timeout, spawn_retries)
+}
+
+/// Like [`compiler_output`], but writes `stdin` (if any) to piped stdin of the compiler.
+fn compiler_output_with_stdin(
+    command: &mut Command,
+    stdin: Option<Vec<u8>>,
+    timeout: Option<Duration>,
+    spawn_retries: u32,
+) -> CompilationResult<Output> {
+    #[cfg(feature = "tracing")]
+    let _span =
+        tracing::debug_span!("exers::compiler_process", command = %command_line(command)).entered();
+    #[cfg(feature = "tracing")]
+    let start = Instant::now();
+
+    let mut child = spawn_with_retries(command, spawn_retries)?;
+
+    // Read and write pipes in the background, so the compiler doesn't block on full pipe.
+    let stdin = write_pipe(child.stdin.take(), stdin.unwrap_or_default());
+    let stdout = read_pipe(child.stdout.take());
+    let stderr = read_pipe(child.stderr.take());
+
+    let status = match timeout {
+        Some(timeout) => match wait_timeout(&mut child, timeout)? {
+            Some(status) => status,
+            None => {
+                child.kill()?;
+                child.wait()?;
+                trace_event!(?timeout, "compiler timed out");
+                return Err(CompilationError::failed(
+                    command,
+                    None,
+                    "timed out".to_string(),
+                ));
+            }
+        },
+        None => child.wait()?,
+    };
+    trace_event!(%status, time_taken = ?start.elapsed(), "compiler finished");
+    stdin.join().unwrap()?;
+
+    Ok(Output {
+        status,
+        stdout: stdout.join().unwrap()?,
+        stderr: stderr.join().unwrap()?,
+    })
+}
+
+/// Default number of times spawning is retried when it fails with transient error.
+pub(crate) const DEFAULT_SPAWN_RETRIES: u32 = 3;
+
+/// Spawns the command, retrying with backoff up to `retries` times if it fails with transient error
+/// (`ETXTBSY` when executable was just written and is still open, or `EAGAIN` when out of processes).
+pub(crate) fn spawn_with_retries(command: &mut Command, retries: u32) -> std::io::Result<Child> {
+    retry_spawn(retries, || command.spawn())
+}
+
+/// Calls `spawn` until it succeeds, fails with non-transient error or runs out of retries.
+fn retry_spawn<T>(
+    retries: u32,
+    mut spawn: impl FnMut() -> std::io::Result<T>,
+) -> std::io::Result<T> {
+    let mut attempt = 0;
+    loop {
+        match spawn() {
+            Err(e) if attempt < retries && is_transient_spawn_error(&e) => {
+                trace_event!(error = %e, attempt, "retrying spawn");
+                std::thread::sleep(Duration::from_millis(10 << attempt));
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Returns true if spawning failed because of temporary condition, so it's worth retrying.
+fn is_transient_spawn_error(error: &std::io::Error) -> bool {
+    #[cfg(unix)]
+    return matches!(
+        error.raw_os_error(),
+        Some(libc::ETXTBSY) | Some(libc::EAGAIN)
+    );
+    #[cfg(not(unix))]
+    return error.kind() == std::io::ErrorKind::WouldBlock;
+}
+
+/// Writes the data to the pipe (if any) in a background thread and closes it afterwards.
+fn write_pipe(
+    pipe: Option<impl Write + Send + 'static>,
+    data: Vec<u8>,
+) -> JoinHandle<std::io::Result<()>> {
+    std::thread::spawn(move || {
+        let Some(mut pipe) = pipe else {
+            return Ok(());
+        };
+
+        // Compiler may exit (e.g. on error) before reading everything.
+        match pipe.write_all(&data) {
+            Err(e) if e.kind() == std::io::ErrorKind::BrokenPipe => Ok(()),
+            result => result,
+        }
+    })
+}
+
+/// Reads the whole pipe (if any) in a background thread.
+fn read_pipe(pipe: Option<impl Read + Send + 'static>) -> JoinHandle<std::io::Result<Vec<u8>>> {
+    std::thread::spawn(move || {
+        let mut buffer = Vec::new();
+        if let Some(mut pipe) = pipe {
+            pipe.read_to_end(&mut buffer)?;
+        }
+        Ok(buffer)
+    })
+}
+
+/// Waits for the child to exit, returns `None` if it didn't exit before timeout.
+fn wait_timeout(child: &mut Child, timeout: Duration) -> std::io::Result<Option<ExitStatus>> {
+    let start = Instant::now();
+    loop {
+        if let Some(status) = child.try_wait()? {
+            return Ok(Some(status));
+        }
+
+        let elapsed = start.elapsed();
+        if elapsed >= timeout {
+            return Ok(None);
+        }
+        std::thread::sleep((timeout - elapsed).min(Duration::from_millis(10)));
     }
 }
 
@@ -54,9 +346,17 @@ pub enum CompilationError {
     IoError(std::io::Error),
 
     /// Error while compiling.
-    /// This is returned when compiler returns non-zero exit code.
-    /// This contains stderr of compiler.
-    CompilationFailed(String),
+    /// This is returned when compiler returns non-zero exit code (or times out).
+    CompilationFailed {
+        /// Output of the compiler with errors (usually stderr).
+        stderr: String,
+        /// Exit code of the compiler. <br/>
+        /// This is `None` if the compiler was killed (e.g. crashed with a signal or timed out),
+        /// as opposed to a normal failure caused by errors in the code.
+        exit_code: Option<i32>,
+        /// Command line of the compiler (program followed by arguments).
+        command: String,
+    },
 
     /// Program is not installed.
     /// This is returned when compiler dependency is not installed.
@@ -77,6 +377,18 @@ impl From<std::io::Error> for CompilationError {
     }
 }
 
+impl CompilationError {
+    /// Creates [`CompilationError::CompilationFailed`] for the compiler command that exited with `status`
+    /// (or was killed if it's `None`).
+    pub(crate) fn failed(command: &Command, status: Option<ExitStatus>, stderr: String) -> Self {
+        Self::CompilationFailed {
+            stderr,
+            exit_code: status.and_then(|status| status.code()),
+            command: command_line(command),
+        }
+    }
+}
+
 /// Type for convinient result of compiler.
 pub type CompilationResult<T> = Result<T, CompilationError>;
 
@@ -84,10 +396,12 @@ impl Display for CompilationError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             CompilationError::IoError(e) => write!(f, "IO error: {}", e),
-            CompilationError::CompilationFailed(e) => write!(f, "Compilation failed: {}", e),
+            CompilationError::CompilationFailed { stderr, .. } => {
+                write!(f, "Compilation failed: {}", stderr)
+            }
             CompilationError::ProgramNotInstalled(e) => write!(f, "Program not installed: {}", e),
             CompilationError::FeatureNotSupported(e) => write!(f, "Feature not supported: {}", e),
-            CompilationError::PreprocessorError(e) => write!(f, "Preprocessor error: {:?}", e),
+            CompilationError::PreprocessorError(e) => write!(f, "Preprocessor error: {}", e),
         }
     }
 }
@@ -98,3 +412,75 @@ impl From<PreprocessorError> for CompilationError {
         Self::PreprocessorError(e)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_program() {
+        assert!(resolve_program("rustc").unwrap().is_absolute());
+        assert!(matches!(
+            resolve_program("exers-missing-program"),
+            Err(CompilationError::ProgramNotInstalled(_))
+        ));
+    }
+
+    #[test]
+    fn test_run_compiler_timeout() {
+        let mut command = Command::new("sleep");
+        command.arg("10");
+
+        let start = Instant::now();
+        let result = run_compiler(
+            &mut command,
+            Some(Duration::from_millis(100)),
+            DEFAULT_SPAWN_RETRIES,
+        );
+
+        assert!(matches!(
+            result,
+            Err(CompilationError::CompilationFailed { stderr, exit_code: None, command })
+                if stderr == "timed out" && command == "sleep 10"
+        ));
+        assert!(start.elapsed() < Duration::from_secs(5));
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_retry_spawn() {
+        // Fails twice with "text file busy" before succeeding.
+        let mut attempts = 0;
+        let result = retry_spawn(3, || {
+            attempts += 1;
+            match attempts {
+                1 | 2 => Err(std::io::Error::from_raw_os_error(libc::ETXTBSY)),
+                _ => Ok(attempts),
+            }
+        });
+        assert_eq!(result.unwrap(), 3);
+
+        // Gives up after running out of retries.
+        let mut attempts = 0;
+        let result: std::io::Result<()> = retry_spawn(2, || {
+            attempts += 1;
+            Err(std::io::Error::from_raw_os_error(libc::ETXTBSY))
+        });
+        assert!(result.is_err());
+        assert_eq!(attempts, 3);
+
+        // Other errors are not retried.
+        let mut attempts = 0;
+        let result: std::io::Result<()> = retry_spawn(3, || {
+            attempts += 1;
+            Err(std::io::Error::from(std::io::ErrorKind::NotFound))
+        });
+        assert!(result.is_err());
+        assert_eq!(attempts, 1);
+    }
+
+    #[test]
+    fn test_program_version() {
+        assert!(program_version("rustc").unwrap().starts_with("rustc "));
+    }
+}
@@ -0,0 +1,511 @@
+//! Object safe wrappers over compilers and runtimes.
+//!
+//! [`Compiler`] and [`CodeRuntime`] are generic over configuration and runtime types,
+//! so they can't be chosen at runtime (e.g. by language name from API request).
+//! This module erases these types, so compilers and runtimes can be stored in [`DynRegistry`].
+
+use std::{any::Any, collections::HashMap, io, marker::PhantomData};
+
+use crate::{
+    compilers::{CompiledCode, Compiler},
+    runtimes::{CodeRuntime, ExecutionResult},
+};
+
+use super::error::ExersError;
+
+/// Configuration with erased type. <br/>
+/// `None` means default configuration, otherwise it must contain config of the wrapped compiler or runtime.
+pub type DynConfig = Option<Box<dyn Any + Send>>;
+
+/// Compiled code with erased runtime type, produced by [`DynCompiler`].
+pub struct DynCompiledCode {
+    code: Box<dyn Any + Send + Sync>,
+    warnings: String,
+}
+
+impl DynCompiledCode {
+    /// Erases runtime type of the compiled code.
+    pub fn new<R: CodeRuntime + 'static>(code: CompiledCode<R>) -> Self {
+        Self {
+            warnings: code.warnings.clone(),
+            code: Box::new(code),
+        }
+    }
+
+    /// Returns warnings printed by the compiler.
+    pub fn warnings(&self) -> &str {
+        &self.warnings
+    }
+
+    /// Returns compiled code, if it was compiled for runtime `R`.
+    pub fn downcast_ref<R: CodeRuntime + 'static>(&self) -> Option<&CompiledCode<R>> {
+        self.code.downcast_ref()
+    }
+
+    /// Converts back to compiled code, if it was compiled for runtime `R`.
+    pub fn downcast<R: CodeRuntime + 'static>(self) -> Result<CompiledCode<R>, Self> {
+        match self.code.downcast() {
+            Ok(code) => Ok(*code),
+            Err(code) => Err(Self {
+                code,
+                warnings: self.warnings,
+            }),
+        }
+    }
+}
+
+/// Object safe version of [`Compiler`], compiling for a single runtime.
+/// Use [`DynCompilerWrapper`] to create one from any compiler.
+pub trait DynCompiler: Send + Sync {
+    /// Compile the given code, using configuration of the wrapped compiler (or default one).
+    fn compile_dyn(
+        &self,
+        code: &mut dyn io::Read,
+        config: DynConfig,
+    ) -> Result<DynCompiledCode, ExersError>;
+}
+
+/// Object safe version of [`CodeRuntime`].
+/// This is implemented for every runtime.
+pub trait DynRuntime: Send + Sync {
+    /// Run compiled code, using configuration of the runtime (or default one).
+    /// Code must be compiled for this runtime.
+    fn run_dyn(
+        &self,
+        code: &DynCompiledCode,
+        config: DynConfig,
+    ) -> Result<ExecutionResult, ExersError>;
+}
+
+/// Wrapper implementing [`DynCompiler`] for compiler `C` targeting runtime `R`.
+pub struct DynCompilerWrapper<C: Compiler<R>, R: CodeRuntime> {
+    compiler: C,
+    runtime_marker: PhantomData<R>,
+}
+
+impl<C: Compiler<R>, R: CodeRuntime> DynCompilerWrapper<C, R> {
+    /// Wraps the compiler.
+    pub fn new(compiler: C) -> Self {
+        Self {
+            compiler,
+            runtime_marker: PhantomData,
+        }
+    }
+}
+
+impl<C, R> DynCompiler for DynCompilerWrapper<C, R>
+where
+    C: Compiler<R>,
+    C::Config: 'static,
+    R: CodeRuntime + 'static,
+{
+    fn compile_dyn(
+        &self,
+        mut code: &mut dyn io::Read,
+        config: DynConfig,
+    ) -> Result<DynCompiledCode, ExersError> {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!("exers::compile").entered();
+
+        let config = downcast_config::<C::Config>(config)?;
+        let compiled = self.compiler.compile(&mut code, config)?;
+        Ok(DynCompiledCode::new(compiled))
+    }
+}
+
+impl<R: CodeRuntime + 'static> DynRuntime for R {
+    fn run_dyn(
+        &self,
+        code: &DynCompiledCode,
+        config: DynConfig,
+    ) -> Result<ExecutionResult, ExersError> {
+        let code = code.downcast_ref::<R>().ok_or_else(|| {
+            ExersError::DispatchError(format!(
+                "Code was not compiled for {}",
+                std::any::type_name::<R>()
+            ))
+        })?;
+        let config = downcast_config::<R::Config>(config)?;
+
+        self.run(code, config).map_err(ExersError::runtime)
+    }
+}
+
+/// Returns configuration from erased one, or default configuration if it's not set.
+fn downcast_config<T: Default + 'static>(config: DynConfig) -> Result<T, ExersError> {
+    match config {
+        Some(config) => config.downcast::<T>().map(|config| *config).map_err(|_| {
+            ExersError::DispatchError(format!(
+                "Invalid configuration, expected {}",
+                std::any::type_name::<T>()
+            ))
+        }),
+        None => Ok(T::default()),
+    }
+}
+
+/// Registry of compilers and runtimes, identified by names. <br/>
+/// Compilers are registered for pairs of language and runtime name (e.g. `rust` and `native`).
+/// For all compilers and runtimes enabled by crate features, see [`DynRegistry::with_defaults`].
+#[derive(Default)]
+pub struct DynRegistry {
+    /// Compilers by language and runtime name.
+    compilers: HashMap<(String, String), Box<dyn DynCompiler>>,
+    /// Runtimes by name.
+    runtimes: HashMap<String, Box<dyn DynRuntime>>,
+}
+
+impl DynRegistry {
+    /// Creates new empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates registry with all compilers and runtimes enabled by crate features. <br/>
+    /// Names are the same as in [`CompilerInfo`](crate::compilers::CompilerInfo) and [`RuntimeKind`](crate::runtimes::RuntimeKind).
+    pub fn with_defaults() -> Self {
+        #[allow(unused_mut)]
+        let mut registry = Self::new();
+
+        #[cfg(feature = "native")]
+        {
+            use crate::{
+                compilers::rust_compiler::{CargoRustCompiler, RustCompiler},
+                runtimes::native_runtime::NativeRuntime,
+            };
+
+            registry = registry
+                .with_runtime(NativeRuntime::NAME, NativeRuntime)
+                .with_native_compiler("rust", RustCompiler)
+                .with_native_compiler("cargo", CargoRustCompiler);
+
+            #[cfg(all(feature = "jailed", target_family = "unix"))]
+            {
+                use crate::runtimes::jailed_runtime::JailedRuntime;
+
+                registry = registry.with_runtime(JailedRuntime::NAME, JailedRuntime);
+            }
+
+            #[cfg(feature = "cpp")]
+            {
+                registry = registry
+                    .with_native_compiler("cpp", crate::compilers::cpp_compiler::CppCompiler);
+            }
+            #[cfg(feature = "python")]
+            {
+                registry = registry.with_native_compiler(
+                    "python",
+                    crate::compilers::python_compiler::PythonCompiler,
+                );
+            }
+            #[cfg(feature = "javascript")]
+            {
+                registry = registry
+                    .with_native_compiler("javascript", crate::compilers::js_compiler::JsCompiler);
+            }
+            #[cfg(feature = "ocaml")]
+            {
+                registry = registry
+                    .with_native_compiler("ocaml", crate::compilers::ocaml_compiler::OCamlCompiler);
+            }
+            #[cfg(feature = "csharp")]
+            {
+                registry = registry.with_native_compiler(
+                    "csharp",
+                    crate::compilers::csharp_compiler::CSharpCompiler,
+                );
+            }
+            #[cfg(feature = "php")]
+            {
+                registry = registry
+                    .with_native_compiler("php", crate::compilers::php_compiler::PhpCompiler);
+            }
+            #[cfg(feature = "kotlin")]
+            {
+                registry = registry.with_native_compiler(
+                    "kotlin",
+                    crate::compilers::kotlin_compiler::KotlinCompiler,
+                );
+            }
+            #[cfg(feature = "swift")]
+            {
+                registry = registry
+                    .with_native_compiler("swift", crate::compilers::swift_compiler::SwiftCompiler);
+            }
+            #[cfg(feature = "lua")]
+            {
+                registry = registry
+                    .with_native_compiler("lua", crate::compilers::lua_compiler::LuaCompiler);
+            }
+            #[cfg(feature = "perl")]
+            {
+                registry = registry
+                    .with_native_compiler("perl", crate::compilers::perl_compiler::PerlCompiler);
+            }
+            #[cfg(feature = "r")]
+            {
+                registry =
+                    registry.with_native_compiler("r", crate::compilers::r_compiler::RCompiler);
+            }
+            #[cfg(feature = "asm")]
+            {
+                registry = registry
+                    .with_native_compiler("asm", crate::compilers::asm_compiler::AsmCompiler);
+            }
+            #[cfg(feature = "haskell")]
+            {
+                registry = registry.with_native_compiler(
+                    "haskell",
+                    crate::compilers::haskell_compiler::HaskellCompiler,
+                );
+            }
+            #[cfg(feature = "zig")]
+            {
+                registry = registry
+                    .with_native_compiler("zig", crate::compilers::zig_compiler::ZigCompiler);
+            }
+        }
+
+        #[cfg(feature = "wasm")]
+        {
+            use crate::runtimes::wasm_runtime::WasmRuntime;
+
+            registry = registry
+                .with_runtime(WasmRuntime::NAME, WasmRuntime)
+                .with_compiler::<_, WasmRuntime>(
+                    "rust",
+                    WasmRuntime::NAME,
+                    crate::compilers::rust_compiler::RustCompiler,
+                );
+
+            #[cfg(feature = "cpp")]
+            {
+                registry = registry.with_compiler::<_, WasmRuntime>(
+                    "cpp",
+                    WasmRuntime::NAME,
+                    crate::compilers::cpp_compiler::CppCompiler,
+                );
+            }
+            #[cfg(feature = "javascript")]
+            {
+                registry = registry.with_compiler::<_, WasmRuntime>(
+                    "javascript",
+                    WasmRuntime::NAME,
+                    crate::compilers::js_compiler::JsCompiler,
+                );
+            }
+            #[cfg(feature = "zig")]
+            {
+                registry = registry.with_compiler::<_, WasmRuntime>(
+                    "zig",
+                    WasmRuntime::NAME,
+                    crate::compilers::zig_compiler::ZigCompiler,
+                );
+            }
+        }
+
+        #[cfg(feature = "wasmtime")]
+        {
+            use crate::runtimes::wasmtime_runtime::WasmtimeRuntime;
+
+            registry = registry
+                .with_runtime(WasmtimeRuntime::NAME, WasmtimeRuntime)
+                .with_compiler::<_, WasmtimeRuntime>(
+                    "rust",
+                    WasmtimeRuntime::NAME,
+                    crate::compilers::rust_compiler::RustCompiler,
+                );
+
+            #[cfg(feature = "cpp")]
+            {
+                registry = registry.with_compiler::<_, WasmtimeRuntime>(
+                    "cpp",
+                    WasmtimeRuntime::NAME,
+                    crate::compilers::cpp_compiler::CppCompiler,
+                );
+            }
+            #[cfg(feature = "zig")]
+            {
+                registry = registry.with_compiler::<_, WasmtimeRuntime>(
+                    "zig",
+                    WasmtimeRuntime::NAME,
+                    crate::compilers::zig_compiler::ZigCompiler,
+                );
+            }
+        }
+
+        registry
+    }
+
+    /// Adds runtime to the registry.
+    pub fn with_runtime<R: CodeRuntime + 'static>(mut self, name: &str, runtime: R) -> Self {
+        self.runtimes.insert(name.to_string(), Box::new(runtime));
+        self
+    }
+
+    /// Adds compiler of the language for given runtime to the registry.
+    pub fn with_compiler<C, R>(mut self, language: &str, runtime: &str, compiler: C) -> Self
+    where
+        C: Compiler<R> + 'static,
+        C::Config: 'static,
+        R: CodeRuntime + 'static,
+    {
+        self.compilers.insert(
+            (language.to_string(), runtime.to_string()),
+            Box::new(DynCompilerWrapper::<C, R>::new(compiler)),
+        );
+        self
+    }
+
+    /// Adds native compiler of the language, for native and jailed runtime.
+    #[cfg(feature = "native")]
+    #[allow(unused_mut)]
+    fn with_native_compiler<C>(mut self, language: &str, compiler: C) -> Self
+    where
+        C: Compiler<crate::runtimes::native_runtime::NativeRuntime> + Clone + 'static,
+        C::Config: 'static,
+    {
+        #[cfg(all(feature = "jailed", target_family = "unix"))]
+        {
+            self = self.with_compiler::<_, crate::runtimes::jailed_runtime::JailedRuntime>(
+                language,
+                crate::runtimes::jailed_runtime::JailedRuntime::NAME,
+                compiler.clone(),
+            );
+        }
+
+        self.with_compiler::<_, crate::runtimes::native_runtime::NativeRuntime>(
+            language,
+            crate::runtimes::native_runtime::NativeRuntime::NAME,
+            compiler,
+        )
+    }
+
+    /// Returns compiler of the language for given runtime (if registered).
+    pub fn compiler(&self, language: &str, runtime: &str) -> Option<&dyn DynCompiler> {
+        self.compilers
+            .get(&(language.to_string(), runtime.to_string()))
+            .map(|compiler| compiler.as_ref())
+    }
+
+    /// Returns runtime with given name (if registered).
+    pub fn runtime(&self, name: &str) -> Option<&dyn DynRuntime> {
+        self.runtimes.get(name).map(|runtime| runtime.as_ref())
+    }
+
+    /// Returns all registered pairs of language and runtime name.
+    pub fn languages(&self) -> Vec<(&str, &str)> {
+        let mut languages: Vec<_> = self
+            .compilers
+            .keys()
+            .map(|(language, runtime)| (language.as_str(), runtime.as_str()))
+            .collect();
+        languages.sort();
+        languages
+    }
+
+    /// Compiles the code in given language and runs it in given runtime.
+    /// Returns `DispatchError` if the language or runtime is not registered.
+    pub fn run(
+        &self,
+        language: &str,
+        runtime: &str,
+        code: &mut dyn io::Read,
+        compiler_config: DynConfig,
+        runtime_config: DynConfig,
+    ) -> Result<ExecutionResult, ExersError> {
+        let compiler = self.compiler(language, runtime).ok_or_else(|| {
+            ExersError::DispatchError(format!(
+                "Language {} is not available for runtime {}",
+                language, runtime
+            ))
+        })?;
+        let runtime = self.runtime(runtime).ok_or_else(|| {
+            ExersError::DispatchError(format!("Runtime {} is not available", runtime))
+        })?;
+
+        let compiled = compiler.compile_dyn(code, compiler_config)?;
+        runtime.run_dyn(&compiled, runtime_config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "native")]
+    #[test]
+    fn test_dyn_registry_run() {
+        use super::*;
+        use crate::compilers::rust_compiler::RustCompilerConfig;
+
+        let registry = DynRegistry::with_defaults();
+        assert!(registry.languages().contains(&("rust", "native")));
+
+        let code = "fn main() { println!(\"Hello, world!\"); }";
+        let result = registry
+            .run(
+                "rust",
+                "native",
+                &mut code.as_bytes(),
+                Some(Box::new(RustCompilerConfig::optimized())),
+                None,
+            )
+            .unwrap();
+        assert_eq!(result.stdout, Some("Hello, world!\n".to_string()));
+
+        // Wrong config type.
+        let result = registry.run(
+            "rust",
+            "native",
+            &mut code.as_bytes(),
+            Some(Box::new(())),
+            None,
+        );
+        assert!(matches!(result, Err(ExersError::DispatchError(_))));
+
+        // Unknown language.
+        let result = registry.run("brainfuck", "native", &mut code.as_bytes(), None, None);
+        assert!(matches!(result, Err(ExersError::DispatchError(_))));
+    }
+
+    #[cfg(feature = "native")]
+    #[test]
+    fn test_names() {
+        use super::*;
+        use crate::{
+            compilers::{rust_compiler::RustCompiler, CompilerInfo},
+            runtimes::{native_runtime::NativeRuntime, RuntimeKind},
+        };
+
+        fn names<C: Compiler<R> + CompilerInfo, R: CodeRuntime>(compiler: C, kind: RuntimeKind) {
+            assert_eq!(C::NAME, compiler.name());
+            assert_eq!(R::NAME, kind.name());
+            assert!(DynRegistry::with_defaults()
+                .languages()
+                .contains(&(C::NAME, R::NAME)));
+        }
+
+        names::<_, NativeRuntime>(RustCompiler, RuntimeKind::Native);
+        #[cfg(feature = "cpp")]
+        names::<_, NativeRuntime>(
+            crate::compilers::cpp_compiler::CppCompiler,
+            RuntimeKind::Native,
+        );
+        #[cfg(feature = "python")]
+        names::<_, NativeRuntime>(
+            crate::compilers::python_compiler::PythonCompiler,
+            RuntimeKind::Native,
+        );
+        #[cfg(all(feature = "jailed", target_family = "unix"))]
+        names::<_, crate::runtimes::jailed_runtime::JailedRuntime>(
+            RustCompiler,
+            RuntimeKind::Jailed,
+        );
+        #[cfg(feature = "wasm")]
+        names::<_, crate::runtimes::wasm_runtime::WasmRuntime>(RustCompiler, RuntimeKind::Wasm);
+        #[cfg(feature = "wasmtime")]
+        names::<_, crate::runtimes::wasmtime_runtime::WasmtimeRuntime>(
+            RustCompiler,
+            RuntimeKind::Wasmtime,
+        );
+    }
+}
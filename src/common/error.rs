@@ -0,0 +1,107 @@
+//! Error type covering both compilation and execution of code.
+
+use std::{error::Error, fmt::Display};
+
+use crate::runtimes::CodeRuntime;
+
+use super::{builder::CustomRuntimeError, compiler::CompilationError};
+
+/// Error that can occur anywhere between compiling and running the code. <br/>
+/// Runtime errors are boxed, so this type doesn't depend on the runtime and can be
+/// used as an error type of application functions (`-> Result<ExecutionResult, ExersError>`).
+#[derive(Debug)]
+pub enum ExersError {
+    /// Error from compiler.
+    CompilationError(CompilationError),
+    /// Error from runtime.
+    RuntimeError(Box<dyn Error + Send + Sync>),
+    /// Compiler or runtime couldn't be chosen at runtime (e.g. unknown language or wrong config type). <br/>
+    /// See [`DynRegistry`](super::dynamic::DynRegistry).
+    DispatchError(String),
+}
+
+impl ExersError {
+    /// Creates new error from runtime error.
+    pub fn runtime(error: impl Error + Send + Sync + 'static) -> Self {
+        Self::RuntimeError(Box::new(error))
+    }
+}
+
+impl Display for ExersError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ExersError::CompilationError(e) => write!(f, "{}", e),
+            ExersError::RuntimeError(e) => write!(f, "Runtime error: {}", e),
+            ExersError::DispatchError(e) => write!(f, "Dispatch error: {}", e),
+        }
+    }
+}
+
+impl Error for ExersError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ExersError::CompilationError(e) => Some(e),
+            ExersError::RuntimeError(e) => Some(e.as_ref()),
+            ExersError::DispatchError(_) => None,
+        }
+    }
+}
+
+impl From<CompilationError> for ExersError {
+    fn from(e: CompilationError) -> Self {
+        Self::CompilationError(e)
+    }
+}
+
+impl<R: CodeRuntime> From<CustomRuntimeError<R>> for ExersError {
+    fn from(e: CustomRuntimeError<R>) -> Self {
+        match e {
+            CustomRuntimeError::CompilationError(e) => Self::CompilationError(e),
+            CustomRuntimeError::RuntimeError(e) => Self::runtime(e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "native")]
+    #[test]
+    fn test_exers_error_from_pipeline() {
+        use super::*;
+        use crate::{
+            common::builder::RuntimeBuilder,
+            compilers::{rust_compiler::RustCompiler, Compiler},
+            runtimes::{native_runtime::NativeRuntime, ExecutionResult},
+        };
+
+        fn compile_and_run(code: &str) -> Result<ExecutionResult, ExersError> {
+            let compiled = RustCompiler.compile(&mut code.as_bytes(), Default::default())?;
+            NativeRuntime
+                .run(&compiled, Default::default())
+                .map_err(ExersError::runtime)
+        }
+
+        fn build_and_run(code: &str) -> Result<ExecutionResult, ExersError> {
+            let runtime = RuntimeBuilder::new()
+                .compiler(RustCompiler, None)
+                .runtime(NativeRuntime, None)
+                .build()
+                .unwrap();
+            Ok(runtime.run(&mut code.as_bytes())?)
+        }
+
+        let code = "fn main() { println!(\"Hello, world!\"); }";
+        assert!(compile_and_run(code).is_ok());
+        assert!(build_and_run(code).is_ok());
+
+        let code = "fn main() { this is not rust }";
+        assert!(matches!(
+            compile_and_run(code),
+            Err(ExersError::CompilationError(_))
+        ));
+        assert!(matches!(
+            build_and_run(code),
+            Err(ExersError::CompilationError(_))
+        ));
+    }
+}
@@ -0,0 +1,250 @@
+//! Judging compiled code against test cases.
+
+use crate::{
+    compilers::CompiledCode,
+    runtimes::{CodeRuntime, ExecutionOutcome, ExecutionResult},
+};
+
+use super::{
+    compare::{
+        ExactComparator, LineComparator, OutputComparator, TokenComparator, TrimmedComparator,
+    },
+    runtime::InputData,
+};
+
+/// Runtime configuration with stdin, which can be replaced for every test case.
+pub trait StdinConfig {
+    /// Sets stdin used by the code.
+    fn set_stdin(&mut self, stdin: InputData);
+}
+
+#[cfg(feature = "native")]
+impl StdinConfig for crate::runtimes::native_runtime::NativeConfig {
+    fn set_stdin(&mut self, stdin: InputData) {
+        self.stdin = stdin;
+    }
+}
+
+#[cfg(feature = "wasm")]
+impl StdinConfig for crate::runtimes::wasm_runtime::WasmConfig {
+    fn set_stdin(&mut self, stdin: InputData) {
+        self.stdin = stdin;
+    }
+}
+
+#[cfg(feature = "wasmtime")]
+impl StdinConfig for crate::runtimes::wasmtime_runtime::WasmtimeConfig {
+    fn set_stdin(&mut self, stdin: InputData) {
+        self.stdin = stdin;
+    }
+}
+
+/// Single test case, see [`grade`].
+#[derive(Debug, Clone)]
+pub struct TestCase {
+    /// Input given to the code.
+    pub stdin: InputData,
+    /// Output the code should print.
+    pub expected_stdout: String,
+    /// How the output is compared with the expected one.
+    pub comparator: Comparator,
+}
+
+impl TestCase {
+    /// Creates test case with string input, compared using [`Comparator::Lines`].
+    pub fn new(stdin: impl Into<String>, expected_stdout: impl Into<String>) -> Self {
+        Self {
+            stdin: InputData::String(stdin.into()),
+            expected_stdout: expected_stdout.into(),
+            comparator: Comparator::Lines,
+        }
+    }
+}
+
+/// Way of comparing output of the code with the expected one.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Comparator {
+    /// Output has to be exactly the same, see [`ExactComparator`].
+    Exact,
+    /// Whitespace at the end of the output is ignored, see [`TrimmedComparator`].
+    Trimmed,
+    /// Trailing whitespace of every line and trailing empty lines are ignored, see [`LineComparator`]. <br/>
+    /// This is the default comparator.
+    #[default]
+    Lines,
+    /// Output is compared token by token (split on whitespace),
+    /// tokens that are both numbers can differ by `tolerance` (absolute or relative). <br/>
+    /// See [`TokenComparator`].
+    Float {
+        /// Maximum difference between numbers.
+        tolerance: f64,
+    },
+}
+
+impl OutputComparator for Comparator {
+    fn matches(&self, expected: &str, actual: &str) -> bool {
+        match self {
+            Comparator::Exact => ExactComparator.matches(expected, actual),
+            Comparator::Trimmed => TrimmedComparator.matches(expected, actual),
+            Comparator::Lines => LineComparator.matches(expected, actual),
+            Comparator::Float { tolerance } => TokenComparator {
+                epsilon: *tolerance,
+            }
+            .matches(expected, actual),
+        }
+    }
+}
+
+/// Result of running a single test case.
+#[derive(Debug, Clone)]
+pub struct CaseResult {
+    /// Verdict of the test case.
+    pub verdict: CaseVerdict,
+    /// Result of running the code (stdout, stderr, time taken, ...).
+    pub execution: ExecutionResult,
+}
+
+impl CaseResult {
+    /// Returns true if the test case passed.
+    pub fn passed(&self) -> bool {
+        self.verdict == CaseVerdict::Passed
+    }
+}
+
+/// Verdict of a single test case.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CaseVerdict {
+    /// Code exited successfully and its output matches the expected one.
+    Passed,
+    /// Code exited successfully, but its output is different.
+    WrongAnswer,
+    /// Code didn't exit successfully (e.g. crashed or timed out), so output wasn't checked.
+    Failed(ExecutionOutcome),
+}
+
+/// Runs the code once for every test case (with stdin of the case) and checks its output. <br/>
+/// Cases are run in order with copies of `config`, errors of the runtime stop grading and are returned as `Err`.
+/// If `stop_on_first_failure` is set, remaining cases are skipped after the first case that didn't pass,
+/// so returned results end with that case.
+pub fn grade<R>(
+    runtime: &R,
+    code: &CompiledCode<R>,
+    config: R::Config,
+    cases: Vec<TestCase>,
+    stop_on_first_failure: bool,
+) -> Result<Vec<CaseResult>, R::Error>
+where
+    R: CodeRuntime,
+    R::Config: StdinConfig + Clone,
+{
+    let mut results = Vec::with_capacity(cases.len());
+    for case in cases {
+        let mut config = config.clone();
+        config.set_stdin(case.stdin);
+        let execution = runtime.run(code, config)?;
+
+        let verdict = match execution.outcome() {
+            ExecutionOutcome::Success => {
+                let stdout = execution.stdout.as_deref().unwrap_or_default();
+                match case.comparator.matches(&case.expected_stdout, stdout) {
+                    true => CaseVerdict::Passed,
+                    false => CaseVerdict::WrongAnswer,
+                }
+            }
+            outcome => CaseVerdict::Failed(outcome),
+        };
+
+        results.push(CaseResult { verdict, execution });
+        if stop_on_first_failure && verdict != CaseVerdict::Passed {
+            break;
+        }
+    }
+
+    Ok(results)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_comparators() {
+        assert!(Comparator::Exact.matches("1 2\n", "1 2\n"));
+        assert!(!Comparator::Exact.matches("1 2", "1 2\n"));
+
+        assert!(Comparator::Trimmed.matches("1 2\n", "1 2  \n\n"));
+        assert!(!Comparator::Trimmed.matches("1 2\n3", "1 2  \n3\n"));
+
+        assert!(Comparator::Lines.matches("1 2\n3", "1 2  \n3\n\n"));
+        assert!(!Comparator::Lines.matches("1 2\n", "1  2\n"));
+        assert!(!Comparator::Lines.matches("1\n2\n", "1\n\n2\n"));
+
+        let float = Comparator::Float { tolerance: 1e-6 };
+        assert!(float.matches("0.333333333\nyes", "0.3333333 yes\n"));
+        assert!(float.matches("1000000.0", "1000000.5"));
+        assert!(!float.matches("0.333333333", "0.33"));
+        assert!(!float.matches("1 yes", "1 no"));
+        assert!(!float.matches("1", "1 2"));
+    }
+
+    #[cfg(feature = "native")]
+    #[test]
+    fn test_grade() {
+        use crate::{
+            compilers::{rust_compiler::RustCompiler, Compiler},
+            runtimes::native_runtime::NativeRuntime,
+        };
+
+        let code = r#"
+        fn main() {
+            let mut line = String::new();
+            std::io::stdin().read_line(&mut line).unwrap();
+            let n: i32 = line.trim().parse().unwrap();
+            println!("{}", n * 2);
+        }
+        "#;
+        let compiled = Compiler::<NativeRuntime>::compile(
+            &RustCompiler,
+            &mut code.as_bytes(),
+            Default::default(),
+        )
+        .unwrap();
+
+        let results = grade(
+            &NativeRuntime,
+            &compiled,
+            Default::default(),
+            vec![
+                TestCase::new("2\n", "4"),
+                TestCase::new("3\n", "7"),
+                TestCase::new("not a number\n", "0"),
+            ],
+            false,
+        )
+        .unwrap();
+
+        assert!(results[0].passed());
+        assert_eq!(results[1].verdict, CaseVerdict::WrongAnswer);
+        assert_eq!(
+            results[2].verdict,
+            CaseVerdict::Failed(ExecutionOutcome::NonZeroExit(101))
+        );
+
+        // Case after the wrong answer would panic, but it's never run.
+        let results = grade(
+            &NativeRuntime,
+            &compiled,
+            Default::default(),
+            vec![
+                TestCase::new("2\n", "4"),
+                TestCase::new("3\n", "7"),
+                TestCase::new("not a number\n", "0"),
+            ],
+            true,
+        )
+        .unwrap();
+
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[1].verdict, CaseVerdict::WrongAnswer);
+    }
+}
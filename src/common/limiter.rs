@@ -0,0 +1,195 @@
+//! Limiting number of compilations and executions running at the same time.
+
+use std::{
+    io::Read,
+    sync::{Arc, Condvar, Mutex},
+};
+
+use crate::{
+    compilers::{CompiledCode, Compiler},
+    runtimes::{CodeRuntime, ExecutionResult},
+};
+
+use super::compiler::CompilationResult;
+
+/// Semaphore limiting how many compilers and programs can run at the same time. <br/>
+/// Clones share the same permits, so one limiter can be created and shared across all requests
+/// (e.g. in a server), which keeps many concurrent `rustc` invocations from thrashing the machine.
+/// Waiting for a permit blocks the current thread.
+#[derive(Debug, Clone)]
+pub struct ConcurrencyLimiter {
+    inner: Arc<LimiterState>,
+}
+
+#[derive(Debug)]
+struct LimiterState {
+    /// Maximum number of permits.
+    max: usize,
+    /// Number of permits currently held.
+    used: Mutex<usize>,
+    /// Notified when a permit is released.
+    released: Condvar,
+}
+
+impl ConcurrencyLimiter {
+    /// Creates limiter allowing at most `max` compilations or executions at once. <br/>
+    /// Panics if `max` is 0, as nothing could ever run.
+    pub fn new(max: usize) -> Self {
+        assert!(max > 0, "concurrency limit must be at least 1");
+        Self {
+            inner: Arc::new(LimiterState {
+                max,
+                used: Mutex::new(0),
+                released: Condvar::new(),
+            }),
+        }
+    }
+
+    /// Creates limiter allowing as many compilations or executions at once as there are CPU cores.
+    pub fn per_cpu() -> Self {
+        Self::new(std::thread::available_parallelism().map_or(1, |cores| cores.get()))
+    }
+
+    /// Returns maximum number of compilations or executions running at once.
+    pub fn max(&self) -> usize {
+        self.inner.max
+    }
+
+    /// Returns number of permits that are currently available.
+    pub fn available(&self) -> usize {
+        self.inner.max - *self.inner.used.lock().unwrap()
+    }
+
+    /// Waits until a permit is available and takes it. <br/>
+    /// Permit is released when returned value is dropped.
+    pub fn acquire(&self) -> ConcurrencyPermit {
+        let used = self.inner.used.lock().unwrap();
+        let mut used = self
+            .inner
+            .released
+            .wait_while(used, |used| *used >= self.inner.max)
+            .unwrap();
+        *used += 1;
+        ConcurrencyPermit {
+            limiter: self.clone(),
+        }
+    }
+
+    /// Takes a permit if one is available, without waiting.
+    pub fn try_acquire(&self) -> Option<ConcurrencyPermit> {
+        let mut used = self.inner.used.lock().unwrap();
+        if *used >= self.inner.max {
+            return None;
+        }
+        *used += 1;
+        Some(ConcurrencyPermit {
+            limiter: self.clone(),
+        })
+    }
+
+    /// Compiles the code while holding a permit.
+    pub fn compile<C, R>(
+        &self,
+        compiler: &C,
+        code: &mut impl Read,
+        config: C::Config,
+    ) -> CompilationResult<CompiledCode<R>>
+    where
+        C: Compiler<R>,
+        R: CodeRuntime,
+    {
+        let _permit = self.acquire();
+        compiler.compile(code, config)
+    }
+
+    /// Runs the code while holding a permit.
+    pub fn run<R: CodeRuntime>(
+        &self,
+        runtime: &R,
+        code: &CompiledCode<R>,
+        config: R::Config,
+    ) -> Result<ExecutionResult, R::Error> {
+        let _permit = self.acquire();
+        runtime.run(code, config)
+    }
+}
+
+/// Permit of [`ConcurrencyLimiter`], which is released when dropped.
+#[derive(Debug)]
+pub struct ConcurrencyPermit {
+    limiter: ConcurrencyLimiter,
+}
+
+impl Drop for ConcurrencyPermit {
+    fn drop(&mut self) {
+        let state = &self.limiter.inner;
+        *state.used.lock().unwrap() -= 1;
+        state.released.notify_one();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::atomic::{AtomicUsize, Ordering},
+        time::Duration,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_limiter() {
+        let limiter = ConcurrencyLimiter::new(2);
+
+        let first = limiter.try_acquire().unwrap();
+        let _second = limiter.acquire();
+        assert_eq!(limiter.available(), 0);
+        assert!(limiter.try_acquire().is_none());
+
+        drop(first);
+        assert_eq!(limiter.available(), 1);
+        assert!(limiter.try_acquire().is_some());
+    }
+
+    #[test]
+    fn test_limiter_threads() {
+        let limiter = ConcurrencyLimiter::new(3);
+        let running = AtomicUsize::new(0);
+        let max_running = AtomicUsize::new(0);
+
+        std::thread::scope(|scope| {
+            for _ in 0..12 {
+                scope.spawn(|| {
+                    let _permit = limiter.acquire();
+                    let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+                    max_running.fetch_max(now, Ordering::SeqCst);
+                    std::thread::sleep(Duration::from_millis(20));
+                    running.fetch_sub(1, Ordering::SeqCst);
+                });
+            }
+        });
+
+        assert_eq!(max_running.load(Ordering::SeqCst), 3);
+        assert_eq!(limiter.available(), 3);
+    }
+
+    #[cfg(feature = "native")]
+    #[test]
+    fn test_limiter_compile_and_run() {
+        use crate::{
+            compilers::rust_compiler::RustCompiler, runtimes::native_runtime::NativeRuntime,
+        };
+
+        let limiter = ConcurrencyLimiter::new(1);
+        let code = "fn main() { println!(\"Hello, world!\"); }";
+        let compiled = limiter
+            .compile::<_, NativeRuntime>(&RustCompiler, &mut code.as_bytes(), Default::default())
+            .unwrap();
+        let result = limiter
+            .run(&NativeRuntime, &compiled, Default::default())
+            .unwrap();
+
+        assert_eq!(result.stdout, Some("Hello, world!\n".to_string()));
+        assert_eq!(limiter.available(), 1);
+    }
+}
@@ -1,6 +1,15 @@
 //! This module contains common code for all compilers / runtimes.
 
+#[cfg(feature = "archive")]
+pub mod archive;
 pub mod builder;
+pub mod cache;
+pub mod compare;
 pub mod compiler;
+pub mod dynamic;
+pub mod error;
+pub mod grade;
+pub mod limiter;
 pub mod preprocessor;
+pub mod report;
 pub mod runtime;
@@ -1,4 +1,4 @@
-use std::{fmt::Debug, io::Read, sync::Arc};
+use std::{fmt::Debug, io::Read, sync::Arc, time::Duration};
 
 /// Errors that can occur while preprocessing code.
 #[derive(Debug, Clone)]
@@ -11,6 +11,17 @@ pub enum PreprocessorError {
     // TODO: Add more errors.
 }
 
+impl std::fmt::Display for PreprocessorError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PreprocessorError::ParserError(e) => write!(f, "Parser error: {}", e),
+            PreprocessorError::Other(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for PreprocessorError {}
+
 pub type PreprocessorResult<T> = Result<T, PreprocessorError>;
 
 /// Preprocessor trait. Preprocessors are used to change the code before compilation.
@@ -43,7 +54,7 @@ impl Default for PreprocessorBundle {
 
 impl PreprocessorBundle {
     /// Creates new preprocessor bundle.
-    pub fn new() -> Self {
+    pub const fn new() -> Self {
         Self {
             preprocessors: Vec::new(),
         }
@@ -56,17 +67,137 @@ impl PreprocessorBundle {
     }
 
     /// Preprocesses code using all preprocessors in the bundle.
-    pub fn preprocess(&self, code: &mut impl Read) -> String {
-        let mut code = std::io::read_to_string(code).unwrap();
+    /// Returns the first error reported by any preprocessor.
+    pub fn preprocess(&self, code: &mut impl Read) -> PreprocessorResult<String> {
+        let mut code = std::io::read_to_string(code)
+            .map_err(|e| PreprocessorError::Other(format!("Failed to read code: {}", e)))?;
 
         for preprocessor in &self.preprocessors {
-            code = match preprocessor.preprocess(&code) {
-                Ok(code) => code,
-                Err(err) => panic!("Preprocessor error: {:?}", err),
-            };
+            code = preprocessor.preprocess(&code)?;
+        }
+
+        Ok(code)
+    }
+}
+
+/// Language of the code instrumented by [`InstrumentationPreprocessor`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InstrumentedLanguage {
+    /// Rust code with `fn main`.
+    Rust,
+    /// Python script.
+    Python,
+}
+
+/// Preprocessor that inserts a watchdog into the code, so runaway programs terminate themselves
+/// even without runtime limits (useful in teaching environments). <br/>
+/// When the time runs out, program prints a message to stderr and exits with given exit code.
+/// Note that the watchdog is a part of the program, so it's not a security measure.
+///
+/// # Example
+/// ```ignore
+/// let runtime = RuntimeBuilder::new()
+///     .preprocessor(InstrumentationPreprocessor::new(InstrumentedLanguage::Rust, Duration::from_secs(1)))
+///     .compiler(RustCompiler, None)
+///     .runtime(NativeRuntime, None)
+///     .build()?;
+/// ```
+#[derive(Debug, Clone)]
+pub struct InstrumentationPreprocessor {
+    /// Language of the code.
+    language: InstrumentedLanguage,
+    /// Time after which the program terminates itself.
+    timeout: Duration,
+    /// Exit code used when the program is terminated.
+    exit_code: i32,
+}
+
+impl InstrumentationPreprocessor {
+    /// Creates new instrumentation preprocessor. Default exit code is `124` (like `timeout` command).
+    pub fn new(language: InstrumentedLanguage, timeout: Duration) -> Self {
+        Self {
+            language,
+            timeout,
+            exit_code: 124,
         }
+    }
+
+    /// Sets exit code used when the program is terminated.
+    pub fn exit_code(mut self, exit_code: i32) -> Self {
+        self.exit_code = exit_code;
+        self
+    }
+
+    /// Renames user's `main` and adds new `main` that starts watchdog thread before calling it.
+    fn instrument_rust(&self, code: &str) -> PreprocessorResult<String> {
+        let main = find_rust_main(code).ok_or_else(|| {
+            PreprocessorError::ParserError("Could not find `fn main` in the code".to_string())
+        })?;
 
-        code
+        let mut code = code.to_string();
+        code.replace_range(main..main + "fn main".len(), "fn __exers_user_main");
+        code.push_str(&format!(
+            r#"
+fn main() -> impl std::process::Termination {{
+    std::thread::spawn(|| {{
+        std::thread::sleep(std::time::Duration::from_millis({}));
+        eprintln!("Time limit exceeded");
+        std::process::exit({});
+    }});
+    __exers_user_main()
+}}
+"#,
+            self.timeout.as_millis(),
+            self.exit_code
+        ));
+
+        Ok(code)
+    }
+
+    /// Inserts daemon timer thread at the beginning of the script.
+    fn instrument_python(&self, code: &str) -> String {
+        let watchdog = format!(
+            r#"import os as __exers_os, sys as __exers_sys, threading as __exers_threading
+def __exers_watchdog():
+    __exers_sys.stdout.flush()
+    __exers_sys.stderr.write("Time limit exceeded\n")
+    __exers_sys.stderr.flush()
+    __exers_os._exit({})
+__exers_timer = __exers_threading.Timer({}, __exers_watchdog)
+__exers_timer.daemon = True
+__exers_timer.start()
+"#,
+            self.exit_code,
+            self.timeout.as_secs_f64()
+        );
+
+        // `from __future__` imports must stay at the beginning of the file.
+        let mut lines: Vec<&str> = code.lines().collect();
+        let position = lines
+            .iter()
+            .rposition(|line| line.trim_start().starts_with("from __future__"))
+            .map_or(0, |i| i + 1);
+        lines.insert(position, &watchdog);
+
+        lines.join("\n")
+    }
+}
+
+/// Returns position of `fn main` in rust code (if any).
+fn find_rust_main(code: &str) -> Option<usize> {
+    code.match_indices("fn main").map(|(i, _)| i).find(|&i| {
+        let before = code[..i].chars().next_back();
+        let after = code[i + "fn main".len()..].trim_start().chars().next();
+        !before.is_some_and(|c| c.is_alphanumeric() || c == '_') && after == Some('(')
+    })
+}
+
+impl Preprocessor for InstrumentationPreprocessor {
+    fn preprocess(&self, code: &str) -> PreprocessorResult<String> {
+        match self.language {
+            InstrumentedLanguage::Rust => self.instrument_rust(code),
+            InstrumentedLanguage::Python => Ok(self.instrument_python(code)),
+        }
     }
 }
 
@@ -81,7 +212,98 @@ mod tests {
             .add_preprocessor(|code: &str| Ok(code.replace("b", "c")));
 
         let code = "a";
-        let code = bundle.preprocess(&mut code.as_bytes());
+        let code = bundle.preprocess(&mut code.as_bytes()).unwrap();
         assert_eq!(code, "c");
     }
+
+    #[test]
+    fn test_preprocessor_bundle_error() {
+        use super::*;
+
+        let bundle = PreprocessorBundle::new()
+            .add_preprocessor(|_: &str| {
+                Err(PreprocessorError::ParserError("unexpected token".into()))
+            })
+            .add_preprocessor(|_: &str| -> PreprocessorResult<String> {
+                unreachable!("preprocessors after failing one must not run")
+            });
+
+        let result = bundle.preprocess(&mut "a".as_bytes());
+        assert!(matches!(result, Err(PreprocessorError::ParserError(_))));
+    }
+
+    #[test]
+    #[cfg(feature = "native")]
+    fn test_instrumentation_rust() {
+        use super::*;
+        use crate::{
+            compilers::{rust_compiler::RustCompiler, Compiler},
+            runtimes::{native_runtime::NativeRuntime, CodeRuntime},
+        };
+
+        let code = r#"
+            fn main() {
+                println!("started");
+                loop {}
+            }
+        "#;
+
+        let code = InstrumentationPreprocessor::new(
+            InstrumentedLanguage::Rust,
+            Duration::from_millis(200),
+        )
+        .preprocess(code)
+        .unwrap();
+        let compiled_code = RustCompiler
+            .compile(&mut code.as_bytes(), Default::default())
+            .unwrap();
+        let result = NativeRuntime
+            .run(&compiled_code, Default::default())
+            .unwrap();
+
+        assert_eq!(result.stdout, Some("started\n".to_string()));
+        assert_eq!(result.exit_code, 124);
+    }
+
+    #[test]
+    #[cfg(all(feature = "native", feature = "python"))]
+    fn test_instrumentation_python() {
+        use super::*;
+        use crate::{
+            compilers::{python_compiler::PythonCompiler, Compiler},
+            runtimes::{native_runtime::NativeRuntime, CodeRuntime},
+        };
+
+        let code =
+            "from __future__ import annotations\nprint(\"started\")\nwhile True:\n    pass\n";
+
+        let code = InstrumentationPreprocessor::new(
+            InstrumentedLanguage::Python,
+            Duration::from_millis(200),
+        )
+        .exit_code(3)
+        .preprocess(code)
+        .unwrap();
+        let compiled_code = PythonCompiler
+            .compile(&mut code.as_bytes(), Default::default())
+            .unwrap();
+        let result = NativeRuntime
+            .run(&compiled_code, Default::default())
+            .unwrap();
+
+        assert_eq!(result.stdout, Some("started\n".to_string()));
+        assert_eq!(result.exit_code, 3);
+    }
+
+    #[test]
+    fn test_instrumentation_rust_without_main() {
+        use super::*;
+
+        let preprocessor =
+            InstrumentationPreprocessor::new(InstrumentedLanguage::Rust, Duration::from_secs(1));
+        assert!(matches!(
+            preprocessor.preprocess("fn mainly() {}"),
+            Err(PreprocessorError::ParserError(_))
+        ));
+    }
 }
@@ -0,0 +1,137 @@
+//! Combined report of compiling and running the code.
+
+use std::{
+    io::Read,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    compilers::Compiler,
+    runtimes::{CodeRuntime, ExecutionOutcome, ExecutionResult},
+};
+
+use super::{compiler::CompilationError, error::ExersError};
+
+/// Report of compiling and running the code, see [`compile_and_run`]. <br/>
+/// This captures everything that is usually needed for judging a submission in one place.
+#[derive(Debug, Clone)]
+pub struct RunReport {
+    /// Time taken by the compiler.
+    pub compile_time: Duration,
+    /// Output of the compiler, which is warnings if compilation succeeded or errors if it failed.
+    pub compiler_output: String,
+    /// Result of running the code (stdout, stderr, time taken, ...). <br/>
+    /// This is `None` if compilation failed.
+    pub execution: Option<ExecutionResult>,
+    /// Final verdict.
+    pub verdict: Verdict,
+}
+
+impl RunReport {
+    /// Returns true if the code compiled and ran successfully.
+    pub fn success(&self) -> bool {
+        self.verdict == Verdict::Executed(ExecutionOutcome::Success)
+    }
+
+    /// Returns stdout of the code (if it was run and produced any).
+    pub fn stdout(&self) -> Option<&str> {
+        self.execution.as_ref()?.stdout.as_deref()
+    }
+}
+
+/// Verdict of compiling and running the code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verdict {
+    /// Code didn't compile (or compiler timed out), errors are in [`RunReport::compiler_output`].
+    CompilationFailed,
+    /// Code was compiled and run, with given outcome.
+    Executed(ExecutionOutcome),
+}
+
+/// Compiles the code and runs it, returning report of both steps. <br/>
+/// Code failing to compile is reported as [`Verdict::CompilationFailed`],
+/// other errors (e.g. compiler not installed or runtime error) are returned as `Err`.
+pub fn compile_and_run<C, R>(
+    compiler: &C,
+    runtime: &R,
+    code: &mut impl Read,
+    compiler_config: C::Config,
+    runtime_config: R::Config,
+) -> Result<RunReport, ExersError>
+where
+    C: Compiler<R>,
+    R: CodeRuntime,
+{
+    // Compile the code.
+    let start_time = Instant::now();
+    let compiled = compiler.compile(code, compiler_config);
+    let compile_time = start_time.elapsed();
+
+    let compiled = match compiled {
+        Ok(compiled) => compiled,
+        Err(CompilationError::CompilationFailed { stderr, .. }) => {
+            return Ok(RunReport {
+                compile_time,
+                compiler_output: stderr,
+                execution: None,
+                verdict: Verdict::CompilationFailed,
+            })
+        }
+        Err(e) => return Err(e.into()),
+    };
+
+    // Run the code.
+    let execution = runtime
+        .run(&compiled, runtime_config)
+        .map_err(ExersError::runtime)?;
+
+    Ok(RunReport {
+        compile_time,
+        compiler_output: compiled.warnings.clone(),
+        verdict: Verdict::Executed(execution.outcome()),
+        execution: Some(execution),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "native")]
+    #[test]
+    fn test_compile_and_run() {
+        use super::*;
+        use crate::{
+            compilers::rust_compiler::RustCompiler, runtimes::native_runtime::NativeRuntime,
+        };
+
+        let code = "fn main() { println!(\"Hello, world!\"); std::process::exit(2); }";
+        let report = compile_and_run(
+            &RustCompiler,
+            &NativeRuntime,
+            &mut code.as_bytes(),
+            Default::default(),
+            Default::default(),
+        )
+        .unwrap();
+
+        assert_eq!(report.stdout(), Some("Hello, world!\n"));
+        assert_eq!(
+            report.verdict,
+            Verdict::Executed(ExecutionOutcome::NonZeroExit(2))
+        );
+        assert!(!report.success());
+
+        let code = "fn main() { this is not rust }";
+        let report = compile_and_run(
+            &RustCompiler,
+            &NativeRuntime,
+            &mut code.as_bytes(),
+            Default::default(),
+            Default::default(),
+        )
+        .unwrap();
+
+        assert_eq!(report.verdict, Verdict::CompilationFailed);
+        assert!(report.compiler_output.contains("error"));
+        assert!(report.execution.is_none());
+    }
+}
@@ -1,17 +1,132 @@
-use std::path::PathBuf;
+use std::{
+    fmt::Debug,
+    io::{self, Read},
+    path::PathBuf,
+    process::Stdio,
+    sync::{Arc, Mutex},
+};
+
+#[cfg(feature = "native")]
+use std::{io::Write, process::ChildStdin};
+
+#[cfg(unix)]
+use std::os::fd::OwnedFd;
 
 #[cfg(feature = "wasm")]
 use wasmer::{MemoryError, MemoryType, Pages, Tunables};
 
 /// Represents input data for the code.
-#[derive(Debug, Clone)]
+#[derive(Clone)]
 pub enum InputData {
     /// Stdin will be read from the given file.
     File(PathBuf),
-    /// Stdin will be read from the given string.
+    /// Stdin will be read from the given string. <br/>
+    /// The string is passed as is by all runtimes, no newline is appended (see [`InputData::line`]).
     String(String),
     /// Stdin will be ignored.
     Ignore,
+    /// Stdin will be read directly from the given file descriptor (e.g. read end of OS pipe). <br/>
+    /// This allows chaining programs through kernel pipes, without copying data through this process.
+    #[cfg(unix)]
+    Fd(Arc<OwnedFd>),
+    /// Stdin will be copied from the given reader (e.g. network socket or decompressor) as the code reads it. <br/>
+    /// The reader is shared between clones and consumed by the first run, see [`InputData::reader`].
+    Reader(Arc<Mutex<dyn Read + Send>>),
+}
+
+impl Debug for InputData {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::File(path) => f.debug_tuple("File").field(path).finish(),
+            Self::String(input) => f.debug_tuple("String").field(input).finish(),
+            Self::Ignore => write!(f, "Ignore"),
+            #[cfg(unix)]
+            Self::Fd(fd) => f.debug_tuple("Fd").field(fd).finish(),
+            Self::Reader(_) => write!(f, "Reader"),
+        }
+    }
+}
+
+impl InputData {
+    /// Creates string input ending with a newline (appended if missing). <br/>
+    /// Useful for programs that read a line and expect it to be terminated.
+    pub fn line(input: impl Into<String>) -> Self {
+        let mut input = input.into();
+        if !input.ends_with('\n') {
+            input.push('\n');
+        }
+        Self::String(input)
+    }
+
+    /// Creates input streamed from the given reader.
+    pub fn reader(reader: impl Read + Send + 'static) -> Self {
+        Self::Reader(Arc::new(Mutex::new(reader)))
+    }
+
+    /// Reads the whole input into memory, for runtimes that can't pass it as a stream.
+    #[cfg(any(feature = "wasm", feature = "wasmtime"))]
+    pub(crate) fn read_to_vec(&self) -> io::Result<Vec<u8>> {
+        let mut data = Vec::new();
+        match self {
+            Self::String(input) => data.extend_from_slice(input.as_bytes()),
+            Self::File(path) => {
+                std::fs::File::open(path)?.read_to_end(&mut data)?;
+            }
+            #[cfg(unix)]
+            Self::Fd(fd) => {
+                std::fs::File::from(fd.try_clone()?).read_to_end(&mut data)?;
+            }
+            Self::Reader(reader) => {
+                reader.lock().unwrap().read_to_end(&mut data)?;
+            }
+            Self::Ignore => {}
+        }
+        Ok(data)
+    }
+
+    /// Returns stdio that should be used as stdin of the process.
+    /// Data other than file descriptors has to be written to piped stdin after spawning.
+    pub(crate) fn stdio(&self) -> io::Result<Stdio> {
+        match self {
+            Self::Ignore => Ok(Stdio::null()),
+            #[cfg(unix)]
+            Self::Fd(fd) => Ok(Stdio::from(fd.try_clone()?)),
+            _ => Ok(Stdio::piped()),
+        }
+    }
+
+    /// Writes the data to piped stdin of the process on a separate thread and closes it afterwards. <br/>
+    /// Writing concurrently with reading the output prevents deadlocks when the process
+    /// fills its stdout pipe before consuming all input. Returned handle should be joined after the process exits.
+    #[cfg(feature = "native")]
+    pub(crate) fn write_to(
+        self,
+        stdin: Option<ChildStdin>,
+    ) -> std::thread::JoinHandle<io::Result<()>> {
+        std::thread::spawn(move || {
+            // Nothing to write if stdin is not piped.
+            let Some(mut stdin) = stdin else {
+                return Ok(());
+            };
+
+            let result = match self {
+                Self::String(data) => stdin.write_all(data.as_bytes()),
+                Self::File(path) => std::fs::File::open(path)
+                    .and_then(|mut file| io::copy(&mut file, &mut stdin))
+                    .map(|_| ()),
+                Self::Reader(reader) => {
+                    io::copy(&mut *reader.lock().unwrap(), &mut stdin).map(|_| ())
+                }
+                _ => Ok(()),
+            };
+
+            // Process doesn't have to read all of its input.
+            match result {
+                Err(e) if e.kind() == io::ErrorKind::BrokenPipe => Ok(()),
+                result => result,
+            }
+        })
+    }
 }
 
 /// Limiting tunables for wasm runtime.
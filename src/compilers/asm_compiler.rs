@@ -0,0 +1,249 @@
+use std::{
+    fs::File,
+    io,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use crate::{
+    common::compiler::{
+        check_program_installed, program_version, run_compiler, CompilationResult,
+        DEFAULT_SPAWN_RETRIES,
+    },
+    runtimes::{native_runtime::NativeRuntime, RuntimeKind},
+};
+
+use super::{native_runtimes, CompiledCode, Compiler, CompilerInfo, Executable, IntoArgs};
+
+/// Assembly compiler.
+/// Assembles code using `nasm` (or GNU `as`) and links it to native executable using `ld`. <br/>
+/// Code is linked without libc, so it must define `_start` symbol. <br/>
+/// For configuration options see [`AsmCompilerConfig`].
+#[derive(Debug, Clone)]
+pub struct AsmCompiler;
+
+/// Syntax of assembly code, which decides the assembler.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AsmSyntax {
+    /// Intel syntax, assembled by `nasm`. <br/>
+    /// This is the default syntax.
+    #[default]
+    Nasm,
+    /// GNU assembler syntax (AT&T by default), assembled by `as`.
+    Gas,
+}
+
+/// Configuration for assembly compiler.
+#[derive(Debug, Clone)]
+pub struct AsmCompilerConfig {
+    /// Syntax of the code.
+    pub syntax: AsmSyntax,
+
+    /// Output format of `nasm` (e.g. `elf64` or `elf32`). <br/>
+    /// This is passed to `nasm` command using `-f <format>` argument, and is ignored for GAS.
+    /// Default is `elf64`.
+    pub format: String,
+
+    /// Additional flags for the assembler.
+    pub assembler_flags: Vec<String>,
+
+    /// Additional flags for `ld` (e.g. `-m elf_i386` for 32-bit code).
+    pub linker_flags: Vec<String>,
+
+    /// Prefix of temporary directory used for compilation, useful for telling apart concurrent runs. <br/>
+    /// Default is `exers-asm-`.
+    pub temp_prefix: String,
+
+    /// Maximum time the compilation can take, compiler is killed when it's exceeded. <br/>
+    /// Default is `None` (no limit).
+    pub compile_timeout: Option<Duration>,
+    /// Number of times spawning the compiler is retried when it fails with transient error
+    /// (e.g. `ETXTBSY` or `EAGAIN`). <br/>
+    /// Default is 3.
+    pub spawn_retries: u32,
+}
+
+// Default configuration for assembly compiler.
+impl Default for AsmCompilerConfig {
+    fn default() -> Self {
+        Self {
+            syntax: AsmSyntax::default(),
+            format: "elf64".to_string(),
+            assembler_flags: Vec::new(),
+            linker_flags: Vec::new(),
+            temp_prefix: "exers-asm-".to_string(),
+            compile_timeout: None,
+            spawn_retries: DEFAULT_SPAWN_RETRIES,
+        }
+    }
+}
+
+impl IntoArgs for AsmCompilerConfig {
+    /// Convert this configuration to arguments for the assembler.
+    fn into_args(self) -> Vec<String> {
+        let mut args = Vec::new();
+
+        // Add output format.
+        if self.syntax == AsmSyntax::Nasm {
+            args.push("-f".to_string());
+            args.push(self.format);
+        }
+
+        // Add additional flags.
+        args.extend(self.assembler_flags);
+
+        args
+    }
+}
+
+/// Compiler for native runtime.
+impl Compiler<NativeRuntime> for AsmCompiler {
+    type Config = AsmCompilerConfig;
+    const NAME: &'static str = "asm";
+
+    fn compile(
+        &self,
+        code: &mut impl io::Read,
+        config: Self::Config,
+    ) -> CompilationResult<CompiledCode<NativeRuntime>> {
+        let (assembler, code_name) = match config.syntax {
+            AsmSyntax::Nasm => ("nasm", "code.asm"),
+            AsmSyntax::Gas => ("as", "code.s"),
+        };
+        check_program_installed(assembler)?;
+        check_program_installed("ld")?;
+
+        // Create temporary directory for code and executable.
+        let temp_dir = tempfile::Builder::new()
+            .prefix(&config.temp_prefix)
+            .tempdir()?;
+
+        let compile_timeout = config.compile_timeout;
+
+        let spawn_retries = config.spawn_retries;
+        let linker_flags = config.linker_flags.clone();
+
+        // Create file with assembly code.
+        let mut code_file = File::create(temp_dir.path().join(code_name))?;
+        io::copy(code, &mut code_file)?;
+
+        // Assemble the code to object file.
+        let mut command = std::process::Command::new(assembler);
+        command.current_dir(temp_dir.path());
+        command.args(config.into_args());
+        command.arg(code_name);
+        command.args(["-o", "code.o"]);
+
+        let mut warnings = run_compiler(&mut command, compile_timeout, spawn_retries)?;
+
+        // Link object file to executable.
+        let mut command = std::process::Command::new("ld");
+        command.current_dir(temp_dir.path());
+        command.arg("code.o");
+        command.args(linker_flags);
+        command.arg("-o");
+        command.arg(temp_dir.path().join("executable"));
+
+        warnings.push_str(&run_compiler(&mut command, compile_timeout, spawn_retries)?);
+
+        // Return compiled code.
+        Ok(CompiledCode {
+            executable: Some(Executable::Path(temp_dir.path().join("executable"))),
+            temp_dir_handle: Arc::new(Mutex::new(Some(temp_dir))),
+            additional_data: Default::default(),
+            warnings,
+            command_line: None,
+            runtime_marker: std::marker::PhantomData,
+        })
+    }
+}
+
+impl CompilerInfo for AsmCompiler {
+    fn name(&self) -> &'static str {
+        "asm"
+    }
+
+    fn supported_runtimes(&self) -> Vec<RuntimeKind> {
+        native_runtimes()
+    }
+
+    fn toolchain_version(&self) -> CompilationResult<String> {
+        program_version("nasm")
+    }
+}
+
+#[cfg(test)]
+#[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+mod tests {
+    use super::*;
+    use crate::runtimes::CodeRuntime;
+
+    #[test]
+    fn test_asm_native_runtime() {
+        let code = r#"
+            section .data
+                msg db "Hi", 10
+
+            section .text
+                global _start
+
+            _start:
+                mov rax, 1
+                mov rdi, 1
+                mov rsi, msg
+                mov rdx, 3
+                syscall
+
+                mov rax, 60
+                xor rdi, rdi
+                syscall
+        "#;
+
+        let compiled_code = AsmCompiler
+            .compile(&mut code.as_bytes(), Default::default())
+            .unwrap();
+        let result = NativeRuntime
+            .run(&compiled_code, Default::default())
+            .unwrap();
+
+        assert_eq!(result.stdout, Some("Hi\n".to_string()));
+        assert_eq!(result.exit_code, 0);
+    }
+
+    #[test]
+    fn test_asm_gas_syntax() {
+        let code = r#"
+            .data
+            msg: .ascii "Hi\n"
+
+            .text
+            .globl _start
+            _start:
+                mov $1, %rax
+                mov $1, %rdi
+                lea msg(%rip), %rsi
+                mov $3, %rdx
+                syscall
+
+                mov $60, %rax
+                mov $7, %rdi
+                syscall
+        "#;
+
+        let compiled_code = AsmCompiler
+            .compile(
+                &mut code.as_bytes(),
+                AsmCompilerConfig {
+                    syntax: AsmSyntax::Gas,
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        let result = NativeRuntime
+            .run(&compiled_code, Default::default())
+            .unwrap();
+
+        assert_eq!(result.stdout, Some("Hi\n".to_string()));
+        assert_eq!(result.exit_code, 7);
+    }
+}
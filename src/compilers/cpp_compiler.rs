@@ -1,14 +1,19 @@
 use std::{
     io,
+    path::{Path, PathBuf},
     sync::{Arc, Mutex},
+    time::Duration,
 };
 
 use crate::{
-    common::compiler::{check_program_installed, CompilationError, CompilationResult, OptLevel},
-    runtimes::CodeRuntime,
+    common::compiler::{
+        check_program_installed, command_line, program_version, run_compiler,
+        run_compiler_with_stdin, CompilationResult, OptLevel, DEFAULT_SPAWN_RETRIES,
+    },
+    runtimes::{CodeRuntime, RuntimeKind},
 };
 
-use super::{CompiledCode, Compiler, IntoArgs};
+use super::{native_runtimes, CompiledCode, Compiler, CompilerInfo, Executable, IntoArgs};
 
 /// C++ compiler.
 /// Compiles code using `clang++` for native code and `em++` for wasm code.
@@ -33,23 +38,36 @@ impl CppCompiler {
         Self: Compiler<R>,
     {
         // Create temporary directory for code and executable.
-        let temp_dir = tempfile::Builder::new().prefix("exerscpp-").tempdir()?;
+        let temp_dir = tempfile::Builder::new()
+            .prefix(&config.temp_prefix)
+            .tempdir()?;
 
-        // Create temporary file for code.
-        let mut code_file = tempfile::Builder::new()
-            .prefix("code-")
-            .suffix(".cpp")
-            .tempfile_in(temp_dir.path())?;
-        io::copy(code, &mut code_file)?;
+        let compile_timeout = config.compile_timeout;
+
+        let spawn_retries = config.spawn_retries;
+
+        // Create temporary file for code, unless it's passed through stdin.
+        let (code_file, stdin) = if config.use_stdin {
+            let mut stdin = Vec::new();
+            code.read_to_end(&mut stdin)?;
+            (None, Some(stdin))
+        } else {
+            let mut code_file = tempfile::Builder::new()
+                .prefix("code-")
+                .suffix(".cpp")
+                .tempfile_in(temp_dir.path())?;
+            io::copy(code, &mut code_file)?;
+            (Some(code_file), None)
+        };
 
         // Compile the code using `rustc` command with given arguments.
         let mut command = std::process::Command::new(command);
-        command.stderr(std::process::Stdio::piped());
-        command.stdout(std::process::Stdio::null());
-        command.stdin(std::process::Stdio::null());
         command.current_dir(temp_dir.path());
         command.args(args);
-        command.arg(code_file.path());
+        match &code_file {
+            Some(code_file) => command.arg(code_file.path()),
+            None => command.args(["-x", "c++", "-"]),
+        };
 
         // Add compiler arguments.
         for arg in config.into_args() {
@@ -59,24 +77,128 @@ impl CppCompiler {
         command.arg("-o");
         command.arg(temp_dir.path().join(output_name));
 
-        println!("{:?}", command);
-        let output = command.spawn()?.wait_with_output()?;
-
-        // Check if compilation was successful.
-        if !output.status.success() {
-            return Err(CompilationError::CompilationFailed(
-                String::from_utf8_lossy(&output.stderr).to_string(),
-            ));
-        }
+        let command_line = command_line(&command);
+        let warnings =
+            run_compiler_with_stdin(&mut command, stdin, compile_timeout, spawn_retries)?;
 
         // Return compiled code.
         Ok(CompiledCode {
-            executable: Some(temp_dir.path().join(output_name)),
+            executable: Some(Executable::Path(temp_dir.path().join(output_name))),
             temp_dir_handle: Arc::new(Mutex::new(Some(temp_dir))),
             additional_data: R::AdditionalData::default(),
+            warnings,
+            command_line: Some(command_line),
             runtime_marker: std::marker::PhantomData,
         })
     }
+
+    /// Compile the given code for wasi using `clang++` from wasi sdk.
+    /// Path to the sdk is taken from `WASI_SDK` environment variable.
+    #[cfg(any(feature = "wasm", feature = "wasmtime"))]
+    fn compile_wasi<R: CodeRuntime>(
+        &self,
+        code: &mut impl io::Read,
+        config: CppCompilerConfig,
+    ) -> CompilationResult<CompiledCode<R>>
+    where
+        Self: Compiler<R>,
+    {
+        let sdk_path = std::env::var("WASI_SDK").expect(
+            "WASI_SDK environment variable not set. Consider installing wasi-sdk or wasi-libc.",
+        );
+
+        // Target and sysroot of the sdk are used, unless they are set in the config.
+        let config = CppCompilerConfig {
+            target: config.target.or(Some("wasm32-wasi".to_string())),
+            sysroot: config
+                .sysroot
+                .or(Some(PathBuf::from(&sdk_path).join("share/wasi-sysroot"))),
+            ..config
+        };
+
+        self.compile_with_args(
+            code,
+            format!("{}/bin/clang++", sdk_path).as_str(),
+            config,
+            &[],
+            "executable.wasm",
+        )
+    }
+
+    /// Check if the given code compiles, without producing an executable.
+    /// This runs `clang++ -fsyntax-only`, which skips code generation and linking.
+    pub fn check(
+        &self,
+        code: &mut impl io::Read,
+        config: CppCompilerConfig,
+    ) -> CompilationResult<()> {
+        check_program_installed("clang++")?;
+
+        // Create temporary directory for code.
+        let temp_dir = tempfile::Builder::new()
+            .prefix(&config.temp_prefix)
+            .tempdir()?;
+
+        let compile_timeout = config.compile_timeout;
+
+        let spawn_retries = config.spawn_retries;
+
+        // Create temporary file for code.
+        let mut code_file = tempfile::Builder::new()
+            .prefix("code-")
+            .suffix(".cpp")
+            .tempfile_in(temp_dir.path())?;
+        io::copy(code, &mut code_file)?;
+
+        // Check the code using `clang++` command.
+        let mut command = std::process::Command::new("clang++");
+        command.current_dir(temp_dir.path());
+        command.arg("-fsyntax-only");
+        command.arg(code_file.path());
+        command.args(config.into_args());
+
+        run_compiler(&mut command, compile_timeout, spawn_retries)?;
+        Ok(())
+    }
+
+    /// Precompile the given header for native `clang++` and save it to `output`. <br/>
+    /// Use it by setting [`CppCompilerConfig::pch`], which makes every compilation include the header
+    /// without parsing it again (e.g. `#include <bits/stdc++.h>` for competitive programming).
+    /// Header must be compiled with the same flags as the code it is used with.
+    pub fn precompile_header(
+        &self,
+        header: &mut impl io::Read,
+        config: CppCompilerConfig,
+        output: &Path,
+    ) -> CompilationResult<()> {
+        check_program_installed("clang++")?;
+
+        // Create temporary directory for header.
+        let temp_dir = tempfile::Builder::new()
+            .prefix(&config.temp_prefix)
+            .tempdir()?;
+
+        let compile_timeout = config.compile_timeout;
+
+        let spawn_retries = config.spawn_retries;
+
+        // Create file for header.
+        let header_path = temp_dir.path().join("header.hpp");
+        let mut header_file = std::fs::File::create(&header_path)?;
+        io::copy(header, &mut header_file)?;
+
+        // Compile the header using `clang++` command.
+        let mut command = std::process::Command::new("clang++");
+        command.current_dir(temp_dir.path());
+        command.args(["-x", "c++-header"]);
+        command.arg(&header_path);
+        command.args(config.into_args());
+        command.arg("-o");
+        command.arg(output);
+
+        run_compiler(&mut command, compile_timeout, spawn_retries)?;
+        Ok(())
+    }
 }
 
 /// Comfiguration for C++ compiler.
@@ -88,6 +210,60 @@ pub struct CppCompilerConfig {
 
     /// Additional flags for C++ compiler.
     pub additional_flags: Vec<String>,
+
+    /// Precompiled header that should be included in the code. <br/>
+    /// This is passed to `clang++` command using `-include-pch` argument.
+    /// See [`CppCompiler::precompile_header`] for creating one.
+    pub pch: Option<PathBuf>,
+
+    /// Maximum number of parallel backend jobs used for link time optimization. <br/>
+    /// This is passed to `clang++` command using `-flto-jobs=<jobs>` argument,
+    /// only when LTO is enabled with `-flto` in additional flags (single file is otherwise compiled on one thread).
+    /// Default is `None` (all CPU cores for ThinLTO).
+    pub jobs: Option<usize>,
+
+    /// Target triple to compile for (e.g. `aarch64-linux-gnu`). <br/>
+    /// This is passed to `clang++` command using `--target=<triple>` argument.
+    /// Executables built for other targets may not run natively, which is fine for compile-only checks
+    /// or running under emulator (e.g. qemu).
+    /// Default is `None` (host for native code, `wasm32-wasi` for wasm code).
+    pub target: Option<String>,
+
+    /// Sysroot with headers and libraries for the target. <br/>
+    /// This is passed to `clang++` command using `--sysroot=<path>` argument.
+    /// Default is `None` (system sysroot for native code, wasi sdk sysroot for wasm code).
+    pub sysroot: Option<PathBuf>,
+
+    /// Toolchain used for native code. <br/>
+    /// Default is [`CppToolchain::Clang`].
+    pub toolchain: CppToolchain,
+
+    /// Passes the code to the compiler through stdin (as `-x c++ -`) instead of temporary file. <br/>
+    /// Default is `false`.
+    pub use_stdin: bool,
+
+    /// Prefix of temporary directory used for compilation, useful for telling apart concurrent runs. <br/>
+    /// Default is `exers-cpp-`.
+    pub temp_prefix: String,
+
+    /// Maximum time the compilation can take, compiler is killed when it's exceeded. <br/>
+    /// Default is `None` (no limit).
+    pub compile_timeout: Option<Duration>,
+    /// Number of times spawning the compiler is retried when it fails with transient error
+    /// (e.g. `ETXTBSY` or `EAGAIN`). <br/>
+    /// Default is 3.
+    pub spawn_retries: u32,
+}
+
+/// Toolchain used to compile native C++ code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CppToolchain {
+    /// `clang++` from the system. <br/>
+    /// This is the default toolchain.
+    #[default]
+    Clang,
+    /// `zig c++`, which bundles clang and libc++, so it works without system C++ toolchain.
+    Zig,
 }
 
 impl CppCompilerConfig {
@@ -106,6 +282,15 @@ impl Default for CppCompilerConfig {
         Self {
             opt_level: OptLevel::None,
             additional_flags: Vec::new(),
+            pch: None,
+            jobs: None,
+            target: None,
+            sysroot: None,
+            toolchain: CppToolchain::default(),
+            use_stdin: false,
+            temp_prefix: "exers-cpp-".to_string(),
+            compile_timeout: None,
+            spawn_retries: DEFAULT_SPAWN_RETRIES,
         }
     }
 }
@@ -114,14 +299,44 @@ impl IntoArgs for CppCompilerConfig {
     fn into_args(self) -> Vec<String> {
         let mut args = Vec::new();
 
-        // Add opt level.
-        if !matches!(self.opt_level, OptLevel::None) {
-            args.push(format!("-O{}", self.opt_level.as_stanard_opt_char()));
+        // Add target and sysroot.
+        if let Some(target) = self.target {
+            args.push(format!("--target={}", target));
+        }
+        if let Some(sysroot) = self.sysroot {
+            args.push(format!("--sysroot={}", sysroot.display()));
+        }
+
+        // Add opt level, `Speed` doesn't use `-Ofast`, as it breaks floating point semantics.
+        match self.opt_level {
+            OptLevel::None => {}
+            OptLevel::O1 => args.push("-O1".to_string()),
+            OptLevel::O2 => args.push("-O2".to_string()),
+            OptLevel::O3 | OptLevel::Speed => args.push("-O3".to_string()),
+            OptLevel::Size => args.push("-Os".to_string()),
+            OptLevel::Custom(level) => args.push(format!("-O{}", level)),
+        }
+
+        // Add jobs limit, it only affects LTO backend.
+        if let Some(jobs) = self.jobs {
+            if self
+                .additional_flags
+                .iter()
+                .any(|flag| flag.starts_with("-flto"))
+            {
+                args.push(format!("-flto-jobs={}", jobs));
+            }
         }
 
         // Add additional flags.
         args.extend(self.additional_flags);
 
+        // Add precompiled header.
+        if let Some(pch) = self.pch {
+            args.push("-include-pch".to_string());
+            args.push(pch.to_string_lossy().into_owned());
+        }
+
         args
     }
 }
@@ -132,26 +347,31 @@ use crate::runtimes::wasm_runtime::WasmRuntime;
 #[cfg(feature = "wasm")]
 impl Compiler<WasmRuntime> for CppCompiler {
     type Config = CppCompilerConfig;
+    const NAME: &'static str = "cpp";
 
     fn compile(
         &self,
         code: &mut impl io::Read,
         config: Self::Config,
     ) -> CompilationResult<CompiledCode<WasmRuntime>> {
-        let sdk_path = std::env::var("WASI_SDK").expect(
-            "WASI_SDK environment variable not set. Consider installing wasi-sdk or wasi-libc.",
-        );
+        self.compile_wasi(code, config)
+    }
+}
 
-        self.compile_with_args(
-            code,
-            format!("{}/bin/clang++", sdk_path).as_str(),
-            config,
-            &[
-                "--target=wasm32-wasi",
-                format!("--sysroot={}/share/wasi-sysroot", sdk_path).as_str(),
-            ],
-            "executable.wasm",
-        )
+/// Compiler for wasmtime runtime.
+#[cfg(feature = "wasmtime")]
+use crate::runtimes::wasmtime_runtime::WasmtimeRuntime;
+#[cfg(feature = "wasmtime")]
+impl Compiler<WasmtimeRuntime> for CppCompiler {
+    type Config = CppCompilerConfig;
+    const NAME: &'static str = "cpp";
+
+    fn compile(
+        &self,
+        code: &mut impl io::Read,
+        config: Self::Config,
+    ) -> CompilationResult<CompiledCode<WasmtimeRuntime>> {
+        self.compile_wasi(code, config)
     }
 }
 
@@ -161,14 +381,43 @@ use crate::runtimes::native_runtime::NativeRuntime;
 #[cfg(feature = "native")]
 impl Compiler<NativeRuntime> for CppCompiler {
     type Config = CppCompilerConfig;
+    const NAME: &'static str = "cpp";
 
     fn compile(
         &self,
         code: &mut impl io::Read,
         config: Self::Config,
     ) -> CompilationResult<CompiledCode<NativeRuntime>> {
-        check_program_installed("clang++")?;
-        self.compile_with_args(code, "clang++", config, &[], "executable")
+        match config.toolchain {
+            CppToolchain::Clang => {
+                check_program_installed("clang++")?;
+                self.compile_with_args(code, "clang++", config, &[], "executable")
+            }
+            CppToolchain::Zig => {
+                check_program_installed("zig")?;
+                self.compile_with_args(code, "zig", config, &["c++"], "executable")
+            }
+        }
+    }
+}
+
+impl CompilerInfo for CppCompiler {
+    fn name(&self) -> &'static str {
+        "cpp"
+    }
+
+    fn supported_runtimes(&self) -> Vec<RuntimeKind> {
+        #[allow(unused_mut)]
+        let mut runtimes = native_runtimes();
+        #[cfg(feature = "wasm")]
+        runtimes.push(RuntimeKind::Wasm);
+        #[cfg(feature = "wasmtime")]
+        runtimes.push(RuntimeKind::Wasmtime);
+        runtimes
+    }
+
+    fn toolchain_version(&self) -> CompilationResult<String> {
+        program_version("clang++")
     }
 }
 
@@ -176,6 +425,41 @@ impl Compiler<NativeRuntime> for CppCompiler {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_opt_level_args() {
+        let args = |opt_level| {
+            CppCompilerConfig {
+                opt_level,
+                ..Default::default()
+            }
+            .into_args()
+        };
+
+        assert_eq!(args(OptLevel::None), Vec::<String>::new());
+        assert_eq!(args(OptLevel::Speed), ["-O3"]);
+        assert_eq!(args(OptLevel::Size), ["-Os"]);
+        assert_eq!(args(OptLevel::Custom("fast".to_string())), ["-Ofast"]);
+    }
+
+    #[test]
+    fn test_target_args() {
+        let config = CppCompilerConfig {
+            target: Some("aarch64-linux-gnu".to_string()),
+            sysroot: Some(PathBuf::from("/usr/aarch64-linux-gnu")),
+            opt_level: OptLevel::O2,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            config.into_args(),
+            [
+                "--target=aarch64-linux-gnu",
+                "--sysroot=/usr/aarch64-linux-gnu",
+                "-O2"
+            ]
+        );
+    }
+
     #[cfg(feature = "native")]
     #[test]
     fn test_cpp_native_runtime() {
@@ -198,6 +482,57 @@ mod tests {
         assert_eq!(result.exit_code, 0);
     }
 
+    #[cfg(feature = "native")]
+    #[test]
+    fn test_cpp_precompiled_header() {
+        let pch_dir = tempfile::tempdir().unwrap();
+        let pch = pch_dir.path().join("iostream.pch");
+        CppCompiler
+            .precompile_header(
+                &mut "#include <iostream>".as_bytes(),
+                Default::default(),
+                &pch,
+            )
+            .unwrap();
+
+        // Code doesn't include iostream itself, so it compiles only with the header.
+        let code = r#"
+            int main() {
+                std::cout << "Hello, World!";
+                return 0;
+            }
+        "#;
+
+        let compiled_code = CppCompiler
+            .compile(
+                &mut code.as_bytes(),
+                CppCompilerConfig {
+                    pch: Some(pch),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        let result = NativeRuntime
+            .run(&compiled_code, Default::default())
+            .unwrap();
+
+        assert_eq!(result.stdout.unwrap(), "Hello, World!");
+    }
+
+    #[test]
+    fn test_cpp_check() {
+        let code = "int main() { return 0; }";
+        assert!(CppCompiler
+            .check(&mut code.as_bytes(), Default::default())
+            .is_ok());
+
+        let code = "int main() { return undefined_variable; }";
+        assert!(matches!(
+            CppCompiler.check(&mut code.as_bytes(), Default::default()),
+            Err(crate::common::compiler::CompilationError::CompilationFailed { .. })
+        ));
+    }
+
     #[cfg(feature = "wasm")]
     #[test]
     fn test_cpp_compiler_wasm() {
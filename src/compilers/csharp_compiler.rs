@@ -0,0 +1,248 @@
+use std::{
+    fs::File,
+    io,
+    process::Stdio,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use crate::{
+    common::compiler::{
+        check_program_installed, compiler_output, program_version, CompilationError,
+        CompilationResult, OptLevel, DEFAULT_SPAWN_RETRIES,
+    },
+    runtimes::{
+        native_runtime::{NativeAdditionalData, NativeRuntime},
+        RuntimeKind,
+    },
+};
+
+use super::{native_runtimes, CompiledCode, Compiler, CompilerInfo, Executable, IntoArgs};
+
+/// C# compiler.
+/// Builds the code as a console project using `dotnet build`
+/// and runs produced assembly using `dotnet` command. <br/>
+/// For configuration options see [`CSharpCompilerConfig`].
+#[derive(Debug, Clone)]
+pub struct CSharpCompiler;
+
+/// Configuration for C# compiler.
+#[derive(Debug, Clone)]
+pub struct CSharpCompilerConfig {
+    /// Opt level for C# compiler. <br/>
+    /// `OptLevel::None` builds the project in `Debug` configuration, every other level uses `Release`.
+    pub opt_level: OptLevel,
+
+    /// Target framework of the project (e.g. `net8.0`). <br/>
+    /// It must be supported by installed .NET SDK.
+    pub framework: String,
+
+    /// Additional flags for `dotnet build` command.
+    pub additional_flags: Vec<String>,
+
+    /// Maximum number of processes used by MSBuild. <br/>
+    /// This is passed to `dotnet build` command using `-maxcpucount:<jobs>` argument.
+    /// Default is `None` (MSBuild uses all CPU cores).
+    pub jobs: Option<usize>,
+
+    /// Prefix of temporary directory used for compilation, useful for telling apart concurrent runs. <br/>
+    /// Default is `exers-csharp-`.
+    pub temp_prefix: String,
+
+    /// Maximum time the compilation can take, compiler is killed when it's exceeded. <br/>
+    /// Default is `None` (no limit).
+    pub compile_timeout: Option<Duration>,
+    /// Number of times spawning the compiler is retried when it fails with transient error
+    /// (e.g. `ETXTBSY` or `EAGAIN`). <br/>
+    /// Default is 3.
+    pub spawn_retries: u32,
+}
+
+impl CSharpCompilerConfig {
+    /// Creates new configuration building the project in `Release` configuration.
+    pub fn optimized() -> Self {
+        Self {
+            opt_level: OptLevel::O3,
+            ..Default::default()
+        }
+    }
+}
+
+// Default configuration for C# compiler.
+impl Default for CSharpCompilerConfig {
+    fn default() -> Self {
+        Self {
+            opt_level: OptLevel::None,
+            framework: "net8.0".to_string(),
+            additional_flags: Vec::new(),
+            jobs: None,
+            temp_prefix: "exers-csharp-".to_string(),
+            compile_timeout: None,
+            spawn_retries: DEFAULT_SPAWN_RETRIES,
+        }
+    }
+}
+
+impl IntoArgs for CSharpCompilerConfig {
+    /// Convert this configuration to arguments for `dotnet build` command.
+    fn into_args(self) -> Vec<String> {
+        let mut args = Vec::new();
+
+        // Add build configuration.
+        args.push("--configuration".to_string());
+        match self.opt_level {
+            OptLevel::None => args.push("Debug".to_string()),
+            _ => args.push("Release".to_string()),
+        }
+
+        // Add target framework.
+        args.push("--framework".to_string());
+        args.push(self.framework);
+
+        // Add jobs limit.
+        if let Some(jobs) = self.jobs {
+            args.push(format!("-maxcpucount:{}", jobs));
+        }
+
+        // Add additional flags.
+        args.extend(self.additional_flags);
+
+        args
+    }
+}
+
+/// Creates minimal console project file for given target framework.
+fn project_file(framework: &str) -> String {
+    format!(
+        r#"<Project Sdk="Microsoft.NET.Sdk">
+  <PropertyGroup>
+    <OutputType>Exe</OutputType>
+    <TargetFramework>{}</TargetFramework>
+    <ImplicitUsings>enable</ImplicitUsings>
+    <Nullable>enable</Nullable>
+    <AssemblyName>code</AssemblyName>
+  </PropertyGroup>
+</Project>
+"#,
+        framework
+    )
+}
+
+/// Compiler for native runtime.
+impl Compiler<NativeRuntime> for CSharpCompiler {
+    type Config = CSharpCompilerConfig;
+    const NAME: &'static str = "csharp";
+
+    fn compile(
+        &self,
+        code: &mut impl io::Read,
+        config: Self::Config,
+    ) -> CompilationResult<CompiledCode<NativeRuntime>> {
+        check_program_installed("dotnet")?;
+
+        // Create temporary directory for the project.
+        let temp_dir = tempfile::Builder::new()
+            .prefix(&config.temp_prefix)
+            .tempdir()?;
+
+        // Create project scaffold with the code.
+        std::fs::write(
+            temp_dir.path().join("code.csproj"),
+            project_file(&config.framework),
+        )?;
+        let mut code_file = File::create(temp_dir.path().join("Program.cs"))?;
+        io::copy(code, &mut code_file)?;
+
+        // Build the project.
+        let compile_timeout = config.compile_timeout;
+        let spawn_retries = config.spawn_retries;
+        let mut command = std::process::Command::new("dotnet");
+        command.current_dir(temp_dir.path());
+        command.arg("build");
+        command.args(["--nologo", "--output", "out"]);
+        command.args(config.into_args());
+        command.env("DOTNET_CLI_TELEMETRY_OPTOUT", "1");
+        command.env("DOTNET_NOLOGO", "1");
+        command.stdin(Stdio::null());
+        command.stdout(Stdio::piped());
+        command.stderr(Stdio::piped());
+
+        // Dotnet prints diagnostics to stdout.
+        let output = compiler_output(&mut command, compile_timeout, spawn_retries)?;
+        let stdout = String::from_utf8_lossy(&output.stdout).to_string();
+        if !output.status.success() {
+            return Err(CompilationError::failed(
+                &command,
+                Some(output.status),
+                stdout,
+            ));
+        }
+
+        // Collect warnings, each of them is printed twice (during build and in summary).
+        let mut warnings: Vec<&str> = Vec::new();
+        for line in stdout.lines().map(str::trim) {
+            if line.contains(": warning ") && !warnings.contains(&line) {
+                warnings.push(line);
+            }
+        }
+        let warnings = warnings.join("\n");
+
+        // Return compiled code.
+        Ok(CompiledCode {
+            executable: Some(Executable::Path(
+                temp_dir.path().join("out").join("code.dll"),
+            )),
+            temp_dir_handle: Arc::new(Mutex::new(Some(temp_dir))),
+            additional_data: NativeAdditionalData {
+                program: Some("dotnet".to_string()),
+                program_args: Vec::new(),
+            },
+            warnings,
+            command_line: None,
+            runtime_marker: std::marker::PhantomData,
+        })
+    }
+}
+
+impl CompilerInfo for CSharpCompiler {
+    fn name(&self) -> &'static str {
+        "csharp"
+    }
+
+    fn supported_runtimes(&self) -> Vec<RuntimeKind> {
+        native_runtimes()
+    }
+
+    fn toolchain_version(&self) -> CompilationResult<String> {
+        program_version("dotnet")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runtimes::CodeRuntime;
+
+    #[test]
+    fn test_csharp_native_runtime() {
+        let code = r#"
+            using System;
+
+            class Program {
+                static void Main() {
+                    Console.WriteLine("Hello, world!");
+                }
+            }
+        "#;
+
+        let compiled_code = CSharpCompiler
+            .compile(&mut code.as_bytes(), Default::default())
+            .unwrap();
+        let result = NativeRuntime
+            .run(&compiled_code, Default::default())
+            .unwrap();
+
+        assert_eq!(result.stdout, Some("Hello, world!\n".to_string()));
+        assert_eq!(result.exit_code, 0);
+    }
+}
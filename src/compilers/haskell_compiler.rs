@@ -0,0 +1,182 @@
+use std::{
+    fs::File,
+    io,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use crate::{
+    common::compiler::{
+        check_program_installed, program_version, run_compiler, CompilationResult, OptLevel,
+        DEFAULT_SPAWN_RETRIES,
+    },
+    runtimes::{native_runtime::NativeRuntime, RuntimeKind},
+};
+
+use super::{native_runtimes, CompiledCode, Compiler, CompilerInfo, Executable, IntoArgs};
+
+/// Haskell compiler.
+/// Compiles code to native executable using `ghc`. <br/>
+/// For configuration options see [`HaskellCompilerConfig`].
+#[derive(Debug, Clone)]
+pub struct HaskellCompiler;
+
+/// Configuration for Haskell compiler.
+#[derive(Debug, Clone)]
+pub struct HaskellCompilerConfig {
+    /// Opt level for Haskell compiler. <br/>
+    /// This is passed to `ghc` command using `-O0`, `-O1` or `-O2` argument.
+    pub opt_level: OptLevel,
+
+    /// Additional flags for Haskell compiler.
+    pub additional_flags: Vec<String>,
+
+    /// Maximum number of modules compiled in parallel. <br/>
+    /// This is passed to `ghc` command using `-j<jobs>` argument.
+    /// Default is `None` (ghc compiles modules sequentially).
+    pub jobs: Option<usize>,
+
+    /// Prefix of temporary directory used for compilation, useful for telling apart concurrent runs. <br/>
+    /// Default is `exers-haskell-`.
+    pub temp_prefix: String,
+
+    /// Maximum time the compilation can take, compiler is killed when it's exceeded. <br/>
+    /// Default is `None` (no limit).
+    pub compile_timeout: Option<Duration>,
+    /// Number of times spawning the compiler is retried when it fails with transient error
+    /// (e.g. `ETXTBSY` or `EAGAIN`). <br/>
+    /// Default is 3.
+    pub spawn_retries: u32,
+}
+
+impl HaskellCompilerConfig {
+    /// Creates new fully optimized configuration.
+    pub fn optimized() -> Self {
+        Self {
+            opt_level: OptLevel::O2,
+            ..Default::default()
+        }
+    }
+}
+
+// Default configuration for Haskell compiler.
+impl Default for HaskellCompilerConfig {
+    fn default() -> Self {
+        Self {
+            opt_level: OptLevel::None,
+            additional_flags: Vec::new(),
+            jobs: None,
+            temp_prefix: "exers-haskell-".to_string(),
+            compile_timeout: None,
+            spawn_retries: DEFAULT_SPAWN_RETRIES,
+        }
+    }
+}
+
+impl IntoArgs for HaskellCompilerConfig {
+    /// Convert this configuration to arguments for `ghc` command.
+    fn into_args(self) -> Vec<String> {
+        let mut args = Vec::new();
+
+        // Add opt level, ghc doesn't go beyond `-O2`.
+        match self.opt_level {
+            OptLevel::None => args.push("-O0".to_string()),
+            OptLevel::O1 | OptLevel::Size => args.push("-O1".to_string()),
+            OptLevel::O2 | OptLevel::O3 | OptLevel::Speed => args.push("-O2".to_string()),
+            OptLevel::Custom(level) => args.push(format!("-O{}", level)),
+        }
+
+        // Add jobs limit.
+        if let Some(jobs) = self.jobs {
+            args.push(format!("-j{}", jobs));
+        }
+
+        // Add additional flags.
+        args.extend(self.additional_flags);
+
+        args
+    }
+}
+
+/// Compiler for native runtime.
+impl Compiler<NativeRuntime> for HaskellCompiler {
+    type Config = HaskellCompilerConfig;
+    const NAME: &'static str = "haskell";
+
+    fn compile(
+        &self,
+        code: &mut impl io::Read,
+        config: Self::Config,
+    ) -> CompilationResult<CompiledCode<NativeRuntime>> {
+        check_program_installed("ghc")?;
+
+        // Create temporary directory for code and executable.
+        let temp_dir = tempfile::Builder::new()
+            .prefix(&config.temp_prefix)
+            .tempdir()?;
+
+        let compile_timeout = config.compile_timeout;
+
+        let spawn_retries = config.spawn_retries;
+
+        // Create file with Haskell code.
+        let mut code_file = File::create(temp_dir.path().join("Main.hs"))?;
+        io::copy(code, &mut code_file)?;
+
+        // Compile the code, intermediate files (`.hi`, `.o`) are kept in `build` directory.
+        let mut command = std::process::Command::new("ghc");
+        command.current_dir(temp_dir.path());
+        command.arg("Main.hs");
+        command.args(["-outputdir", "build"]);
+        command.args(config.into_args());
+        command.arg("-o");
+        command.arg(temp_dir.path().join("executable"));
+
+        let warnings = run_compiler(&mut command, compile_timeout, spawn_retries)?;
+
+        // Return compiled code.
+        Ok(CompiledCode {
+            executable: Some(Executable::Path(temp_dir.path().join("executable"))),
+            temp_dir_handle: Arc::new(Mutex::new(Some(temp_dir))),
+            additional_data: Default::default(),
+            warnings,
+            command_line: None,
+            runtime_marker: std::marker::PhantomData,
+        })
+    }
+}
+
+impl CompilerInfo for HaskellCompiler {
+    fn name(&self) -> &'static str {
+        "haskell"
+    }
+
+    fn supported_runtimes(&self) -> Vec<RuntimeKind> {
+        native_runtimes()
+    }
+
+    fn toolchain_version(&self) -> CompilationResult<String> {
+        program_version("ghc")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runtimes::CodeRuntime;
+
+    #[test]
+    fn test_haskell_native_runtime() {
+        let code = r#"main = putStrLn "Hello""#;
+
+        let compiled_code = HaskellCompiler
+            .compile(&mut code.as_bytes(), Default::default())
+            .unwrap();
+        let result = NativeRuntime
+            .run(&compiled_code, Default::default())
+            .unwrap();
+
+        assert_eq!(result.stdout, Some("Hello\n".to_string()));
+        assert_eq!(result.exit_code, 0);
+    }
+}
@@ -1,45 +1,126 @@
-use std::sync::{Arc, Mutex};
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
 
 use crate::{
-    common::compiler::check_program_installed,
+    common::compiler::{
+        check_file_name, command_line, interpreter_program, program_version, run_compiler,
+        write_extra_files, CompilationResult, DEFAULT_SPAWN_RETRIES,
+    },
     runtimes::{
         native_runtime::{NativeAdditionalData, NativeRuntime},
         wasm_runtime::WasmRuntime,
+        RuntimeKind,
     },
 };
 
-use super::Compiler;
+use super::{native_runtimes, Compiler, CompilerInfo, Executable, IntoArgs};
 
 /// Javascript compiler.
 /// This uses javy to compile the code to a wasm module. And runs the code in a nodejs environment for native modules.
 /// Javy is bundled with this crate and will be downloaded and installed automatically.
+#[derive(Debug, Clone)]
 pub struct JsCompiler;
 
+/// Configuration for Javascript compiler in native runtime.
+#[derive(Debug, Clone)]
+pub struct JsCompilerConfig {
+    /// Path to nodejs interpreter, for systems where it has non-standard name or location
+    /// (e.g. `nodejs` on older Debian). <br/>
+    /// Default is `None` (`node` from `PATH`).
+    pub interpreter_path: Option<PathBuf>,
+
+    /// Name of the file containing the code, visible to it as `__filename` or `import.meta.url`
+    /// (e.g. `main.mjs` for ES module). It must be a plain file name.
+    /// Files run in place by [`compile_file`](Compiler::compile_file) keep their own name. <br/>
+    /// Default is `code.js`.
+    pub main_file_name: String,
+
+    /// Additional files written next to the code, keyed by path relative to it (e.g. `helper.js`),
+    /// so that the code can `require` them. <br/>
+    /// Default is empty (code is a single file).
+    pub extra_files: BTreeMap<PathBuf, Vec<u8>>,
+}
+
+// Default configuration for Javascript compiler.
+impl Default for JsCompilerConfig {
+    fn default() -> Self {
+        Self {
+            interpreter_path: None,
+            main_file_name: "code.js".to_string(),
+            extra_files: BTreeMap::new(),
+        }
+    }
+}
+
+impl IntoArgs for JsCompilerConfig {
+    fn into_args(self) -> Vec<String> {
+        Vec::new()
+    }
+}
+
 impl Compiler<NativeRuntime> for JsCompiler {
-    type Config = ();
+    type Config = JsCompilerConfig;
+    const NAME: &'static str = "javascript";
 
     fn compile(
         &self,
         code: &mut impl std::io::Read,
-        _config: Self::Config,
+        config: Self::Config,
     ) -> crate::common::compiler::CompilationResult<super::CompiledCode<NativeRuntime>> {
+        check_file_name(&config.main_file_name)?;
+
         // Get temporary directory
-        let temp_dir = tempfile::tempdir().unwrap();
+        let temp_dir = tempfile::Builder::new().prefix("exers-js-").tempdir()?;
 
         // Create code file in temporary directory
-        let mut code_file = std::fs::File::create(temp_dir.path().join("code.js")).unwrap();
+        let code_path = temp_dir.path().join(&config.main_file_name);
+        let mut code_file = std::fs::File::create(&code_path).unwrap();
 
         // Copy code to code file
         std::io::copy(code, &mut code_file).unwrap();
+        write_extra_files(temp_dir.path(), &config.extra_files)?;
 
         // Return compiled code that uses nodejs to run the code (first ensure that nodejs is installed)
-        check_program_installed("node").unwrap();
+        let program = interpreter_program(config.interpreter_path.as_deref(), "node")?;
         Ok(super::CompiledCode {
-            executable: Some(temp_dir.path().join("code.js")),
+            executable: Some(Executable::Path(code_path)),
             temp_dir_handle: Arc::new(Mutex::new(Some(temp_dir))),
             additional_data: NativeAdditionalData {
-                program: Some("node".to_string()),
+                program: Some(program),
+                program_args: Vec::new(),
+            },
+            warnings: String::new(),
+            command_line: None,
+            runtime_marker: std::marker::PhantomData,
+        })
+    }
+
+    /// Runs `.js` (also `.mjs` and `.cjs`) files in place, unless they have extra files.
+    fn compile_file(
+        &self,
+        path: &Path,
+        config: Self::Config,
+    ) -> CompilationResult<super::CompiledCode<NativeRuntime>> {
+        let is_js = path
+            .extension()
+            .is_some_and(|extension| ["js", "mjs", "cjs"].iter().any(|js| extension == *js));
+        if !is_js || !config.extra_files.is_empty() {
+            return self.compile(&mut std::fs::File::open(path)?, config);
+        }
+
+        let program = interpreter_program(config.interpreter_path.as_deref(), "node")?;
+        Ok(super::CompiledCode {
+            executable: Some(Executable::Path(std::fs::canonicalize(path)?)),
+            temp_dir_handle: Arc::new(Mutex::new(None)),
+            additional_data: NativeAdditionalData {
+                program: Some(program),
+                program_args: Vec::new(),
             },
+            warnings: String::new(),
+            command_line: None,
             runtime_marker: std::marker::PhantomData,
         })
     }
@@ -47,10 +128,13 @@ impl Compiler<NativeRuntime> for JsCompiler {
 
 impl Compiler<WasmRuntime> for JsCompiler {
     type Config = ();
+    const NAME: &'static str = "javascript";
 
     /// Compile javascript code to wasm using javy.
     ///
-    /// **WARNING**: Output from console.log will be written to stderr instead of stdout. (This will be fixed in the future)
+    /// **WARNING**: Output from console.log will be written to stderr instead of stdout. (This will be fixed in the future) <br/>
+    /// Set [`WasmConfig::merge_stderr`](crate::runtimes::wasm_runtime::WasmConfig::merge_stderr)
+    /// to get all output (in the order it was written) as stdout.
     ///
     /// ### How to print to stdout? (temporary workaround)
     /// ```js,ignore
@@ -68,7 +152,7 @@ impl Compiler<WasmRuntime> for JsCompiler {
         _config: Self::Config,
     ) -> crate::common::compiler::CompilationResult<super::CompiledCode<WasmRuntime>> {
         // Get temporary directory
-        let temp_dir = tempfile::tempdir().unwrap();
+        let temp_dir = tempfile::Builder::new().prefix("exers-js-").tempdir()?;
 
         // Create code file in temporary directory
         let mut code_file = std::fs::File::create(temp_dir.path().join("code.js")).unwrap();
@@ -78,25 +162,47 @@ impl Compiler<WasmRuntime> for JsCompiler {
 
         // Compile code to wasm using javy
         let javy_path = std::env::var("JAVY_PATH").expect("JAVY_PATH environment variable not set");
-        std::process::Command::new(format!("{}/javy", javy_path))
-            .args([
-                "compile",
-                "-o",
-                temp_dir.path().join("code.wasm").to_str().unwrap(),
-                temp_dir.path().join("code.js").to_str().unwrap(),
-            ])
-            .output()?;
+        let mut command = std::process::Command::new(format!("{}/javy", javy_path));
+        command.args([
+            "compile",
+            "-o",
+            temp_dir.path().join("code.wasm").to_str().unwrap(),
+            temp_dir.path().join("code.js").to_str().unwrap(),
+        ]);
+
+        // Fail with javy's errors, instead of returning missing module.
+        let command_line = command_line(&command);
+        let warnings = run_compiler(&mut command, None, DEFAULT_SPAWN_RETRIES)?;
 
         // Return compiled code for wasm runtime
         Ok(super::CompiledCode {
-            executable: Some(temp_dir.path().join("code.wasm")),
+            executable: Some(Executable::Path(temp_dir.path().join("code.wasm"))),
             temp_dir_handle: Arc::new(Mutex::new(Some(temp_dir))),
             additional_data: Default::default(),
+            warnings,
+            command_line: Some(command_line),
             runtime_marker: std::marker::PhantomData,
         })
     }
 }
 
+impl CompilerInfo for JsCompiler {
+    fn name(&self) -> &'static str {
+        "javascript"
+    }
+
+    fn supported_runtimes(&self) -> Vec<RuntimeKind> {
+        #[allow(unused_mut)]
+        let mut runtimes = native_runtimes();
+        runtimes.push(RuntimeKind::Wasm);
+        runtimes
+    }
+
+    fn toolchain_version(&self) -> CompilationResult<String> {
+        program_version("node")
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::runtimes::CodeRuntime;
@@ -114,6 +220,49 @@ mod tests {
         assert_eq!(result.stdout, Some("Hello World!\n".to_string()));
     }
 
+    #[test]
+    fn test_compile_native_extra_files() {
+        let mut code = std::io::Cursor::new("console.log(require('./helper').greet());".as_bytes());
+        let config = JsCompilerConfig {
+            extra_files: [(
+                "helper.js".into(),
+                b"exports.greet = () => 'Hello World!';".to_vec(),
+            )]
+            .into(),
+            ..Default::default()
+        };
+        let compiled_code = JsCompiler.compile(&mut code, config).unwrap();
+        let result = NativeRuntime
+            .run(&compiled_code, Default::default())
+            .unwrap();
+
+        assert_eq!(result.stdout, Some("Hello World!\n".to_string()));
+    }
+
+    #[test]
+    fn test_compile_native_interpreter_path() {
+        let mut code = std::io::Cursor::new("console.log('Hello World!');".as_bytes());
+        let config = JsCompilerConfig {
+            interpreter_path: Some(which::which("node").unwrap()),
+            ..Default::default()
+        };
+        let compiled_code = JsCompiler.compile(&mut code, config).unwrap();
+        let result = NativeRuntime
+            .run(&compiled_code, Default::default())
+            .unwrap();
+
+        assert_eq!(result.stdout, Some("Hello World!\n".to_string()));
+
+        let mut code = std::io::Cursor::new("".as_bytes());
+        let config = JsCompilerConfig {
+            interpreter_path: Some("/nonexistent/node".into()),
+            ..Default::default()
+        };
+        let compiled_code: CompilationResult<crate::compilers::CompiledCode<NativeRuntime>> =
+            JsCompiler.compile(&mut code, config);
+        assert!(compiled_code.is_err());
+    }
+
     #[cfg(feature = "wasm")]
     #[test]
     fn test_compile_wasm() {
@@ -123,4 +272,20 @@ mod tests {
 
         assert_eq!(result.stderr, Some("Hello World!\n".to_string()));
     }
+
+    #[cfg(feature = "wasm")]
+    #[test]
+    fn test_compile_wasm_syntax_error() {
+        use crate::common::compiler::CompilationError;
+
+        let mut code = std::io::Cursor::new("console.log('Hello World!';".as_bytes());
+        let compiled_code: CompilationResult<crate::compilers::CompiledCode<WasmRuntime>> =
+            JsCompiler.compile(&mut code, ());
+
+        assert!(matches!(
+            compiled_code,
+            Err(CompilationError::CompilationFailed { exit_code: Some(_), stderr, .. })
+                if !stderr.is_empty()
+        ));
+    }
 }
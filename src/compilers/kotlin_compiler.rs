@@ -0,0 +1,268 @@
+use std::{
+    fs::File,
+    io,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use crate::{
+    common::compiler::{
+        check_program_installed, program_version, run_compiler, CompilationResult,
+        DEFAULT_SPAWN_RETRIES,
+    },
+    runtimes::{
+        native_runtime::{NativeAdditionalData, NativeRuntime},
+        RuntimeKind,
+    },
+};
+
+use super::{native_runtimes, CompiledCode, Compiler, CompilerInfo, Executable, IntoArgs};
+
+/// Kotlin compiler.
+/// Compiles code to a jar using `kotlinc` and runs it using `java -jar`,
+/// or to native executable using `kotlinc-native`. <br/>
+/// For configuration options see [`KotlinCompilerConfig`].
+#[derive(Debug, Clone)]
+pub struct KotlinCompiler;
+
+/// Target of Kotlin compiler.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum KotlinTarget {
+    /// Jar with Kotlin runtime included, run on JVM. <br/>
+    /// This is the default target.
+    #[default]
+    Jvm,
+    /// Native executable built by `kotlinc-native`.
+    Native,
+}
+
+/// Configuration for Kotlin compiler.
+#[derive(Debug, Clone)]
+pub struct KotlinCompilerConfig {
+    /// Target the code is compiled for.
+    pub target: KotlinTarget,
+
+    /// Class containing `main` function, used as jar entry point. <br/>
+    /// Code is saved as `main.kt`, so top-level `main` ends up in `MainKt` class (default).
+    /// This is only used for JVM target.
+    pub main_class: String,
+
+    /// Additional flags for `kotlinc` (or `kotlinc-native`) command.
+    pub additional_flags: Vec<String>,
+
+    /// Prefix of temporary directory used for compilation, useful for telling apart concurrent runs. <br/>
+    /// Default is `exers-kotlin-`.
+    pub temp_prefix: String,
+
+    /// Maximum time the compilation can take, compiler is killed when it's exceeded. <br/>
+    /// Default is `None` (no limit).
+    pub compile_timeout: Option<Duration>,
+    /// Number of times spawning the compiler is retried when it fails with transient error
+    /// (e.g. `ETXTBSY` or `EAGAIN`). <br/>
+    /// Default is 3.
+    pub spawn_retries: u32,
+}
+
+// Default configuration for Kotlin compiler.
+impl Default for KotlinCompilerConfig {
+    fn default() -> Self {
+        Self {
+            target: KotlinTarget::default(),
+            main_class: "MainKt".to_string(),
+            additional_flags: Vec::new(),
+            temp_prefix: "exers-kotlin-".to_string(),
+            compile_timeout: None,
+            spawn_retries: DEFAULT_SPAWN_RETRIES,
+        }
+    }
+}
+
+impl IntoArgs for KotlinCompilerConfig {
+    /// Convert this configuration to arguments for `kotlinc` command.
+    fn into_args(self) -> Vec<String> {
+        self.additional_flags
+    }
+}
+
+impl KotlinCompiler {
+    /// Compiles the code to a jar and sets its entry point.
+    fn compile_jvm(
+        &self,
+        code: &mut impl io::Read,
+        config: KotlinCompilerConfig,
+    ) -> CompilationResult<CompiledCode<NativeRuntime>> {
+        check_program_installed("kotlinc")?;
+        check_program_installed("jar")?;
+        check_program_installed("java")?;
+
+        // Create temporary directory for code and jar.
+        let temp_dir = tempfile::Builder::new()
+            .prefix(&config.temp_prefix)
+            .tempdir()?;
+
+        let compile_timeout = config.compile_timeout;
+
+        let spawn_retries = config.spawn_retries;
+
+        // Create file with Kotlin code.
+        let mut code_file = File::create(temp_dir.path().join("main.kt"))?;
+        io::copy(code, &mut code_file)?;
+
+        // Compile the code to jar with Kotlin runtime.
+        let main_class = config.main_class.clone();
+        let mut command = std::process::Command::new("kotlinc");
+        command.current_dir(temp_dir.path());
+        command.arg("main.kt");
+        command.arg("-include-runtime");
+        command.args(config.into_args());
+        command.args(["-d", "code.jar"]);
+
+        let warnings = run_compiler(&mut command, compile_timeout, spawn_retries)?;
+
+        // Set entry point of the jar.
+        let mut command = std::process::Command::new("jar");
+        command.current_dir(temp_dir.path());
+        command.args([
+            "--update",
+            "--file",
+            "code.jar",
+            "--main-class",
+            &main_class,
+        ]);
+
+        run_compiler(&mut command, compile_timeout, spawn_retries)?;
+
+        // Return compiled code.
+        Ok(CompiledCode {
+            executable: Some(Executable::Path(temp_dir.path().join("code.jar"))),
+            temp_dir_handle: Arc::new(Mutex::new(Some(temp_dir))),
+            additional_data: NativeAdditionalData {
+                program: Some("java".to_string()),
+                program_args: vec!["-jar".to_string()],
+            },
+            warnings,
+            command_line: None,
+            runtime_marker: std::marker::PhantomData,
+        })
+    }
+
+    /// Compiles the code to native executable.
+    fn compile_native(
+        &self,
+        code: &mut impl io::Read,
+        config: KotlinCompilerConfig,
+    ) -> CompilationResult<CompiledCode<NativeRuntime>> {
+        check_program_installed("kotlinc-native")?;
+
+        // Create temporary directory for code and executable.
+        let temp_dir = tempfile::Builder::new()
+            .prefix(&config.temp_prefix)
+            .tempdir()?;
+
+        let compile_timeout = config.compile_timeout;
+
+        let spawn_retries = config.spawn_retries;
+
+        // Create file with Kotlin code.
+        let mut code_file = File::create(temp_dir.path().join("main.kt"))?;
+        io::copy(code, &mut code_file)?;
+
+        // Compile the code, `.kexe` extension is added by the compiler.
+        let mut command = std::process::Command::new("kotlinc-native");
+        command.current_dir(temp_dir.path());
+        command.arg("main.kt");
+        command.args(config.into_args());
+        command.args(["-o", "executable"]);
+
+        let warnings = run_compiler(&mut command, compile_timeout, spawn_retries)?;
+
+        // Return compiled code.
+        Ok(CompiledCode {
+            executable: Some(Executable::Path(temp_dir.path().join("executable.kexe"))),
+            temp_dir_handle: Arc::new(Mutex::new(Some(temp_dir))),
+            additional_data: Default::default(),
+            warnings,
+            command_line: None,
+            runtime_marker: std::marker::PhantomData,
+        })
+    }
+}
+
+/// Compiler for native runtime.
+impl Compiler<NativeRuntime> for KotlinCompiler {
+    type Config = KotlinCompilerConfig;
+    const NAME: &'static str = "kotlin";
+
+    fn compile(
+        &self,
+        code: &mut impl io::Read,
+        config: Self::Config,
+    ) -> CompilationResult<CompiledCode<NativeRuntime>> {
+        match config.target {
+            KotlinTarget::Jvm => self.compile_jvm(code, config),
+            KotlinTarget::Native => self.compile_native(code, config),
+        }
+    }
+}
+
+impl CompilerInfo for KotlinCompiler {
+    fn name(&self) -> &'static str {
+        "kotlin"
+    }
+
+    fn supported_runtimes(&self) -> Vec<RuntimeKind> {
+        native_runtimes()
+    }
+
+    fn toolchain_version(&self) -> CompilationResult<String> {
+        program_version("kotlinc")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runtimes::CodeRuntime;
+
+    #[test]
+    fn test_kotlin_native_runtime() {
+        let code = r#"fun main() { println("Hello, world!") }"#;
+
+        let compiled_code = KotlinCompiler
+            .compile(&mut code.as_bytes(), Default::default())
+            .unwrap();
+        let result = NativeRuntime
+            .run(&compiled_code, Default::default())
+            .unwrap();
+
+        assert_eq!(result.stdout, Some("Hello, world!\n".to_string()));
+        assert_eq!(result.exit_code, 0);
+    }
+
+    #[test]
+    fn test_kotlin_main_class() {
+        let code = r#"
+            object Entry {
+                @JvmStatic
+                fun main(args: Array<String>) { println("Entry") }
+            }
+
+            fun main() { println("MainKt") }
+        "#;
+
+        let compiled_code = KotlinCompiler
+            .compile(
+                &mut code.as_bytes(),
+                KotlinCompilerConfig {
+                    main_class: "Entry".to_string(),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        let result = NativeRuntime
+            .run(&compiled_code, Default::default())
+            .unwrap();
+
+        assert_eq!(result.stdout, Some("Entry\n".to_string()));
+    }
+}
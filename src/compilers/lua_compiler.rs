@@ -0,0 +1,148 @@
+use std::{
+    fs::File,
+    io,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use crate::{
+    common::compiler::{interpreter_program, program_version, CompilationResult},
+    runtimes::{
+        native_runtime::{NativeAdditionalData, NativeRuntime},
+        RuntimeKind,
+    },
+};
+
+use super::{native_runtimes, CompiledCode, Compiler, CompilerInfo, Executable, IntoArgs};
+
+/// Lua compiler. <br/>
+/// Lua is not compiled, this only creates a temporary file containing the code,
+/// which is then run by the interpreter. <br/>
+/// For configuration options see [`LuaCompilerConfig`].
+#[derive(Debug, Clone)]
+pub struct LuaCompiler;
+
+/// Configuration for Lua compiler.
+#[derive(Debug, Clone)]
+pub struct LuaCompilerConfig {
+    /// Path to Lua interpreter, for systems where it has non-standard name or location.
+    /// Name looked up in `PATH` works too (e.g. `lua5.4` or `luajit`). <br/>
+    /// Default is `None` (`lua` from `PATH`).
+    pub interpreter_path: Option<PathBuf>,
+
+    /// Prefix of temporary directory used for compilation, useful for telling apart concurrent runs. <br/>
+    /// Default is `exers-lua-`.
+    pub temp_prefix: String,
+}
+
+// Default configuration for Lua compiler.
+impl Default for LuaCompilerConfig {
+    fn default() -> Self {
+        Self {
+            interpreter_path: None,
+            temp_prefix: "exers-lua-".to_string(),
+        }
+    }
+}
+
+impl IntoArgs for LuaCompilerConfig {
+    /// Convert this configuration to arguments for the interpreter.
+    fn into_args(self) -> Vec<String> {
+        Vec::new()
+    }
+}
+
+/// Compiler for native runtime.
+impl Compiler<NativeRuntime> for LuaCompiler {
+    type Config = LuaCompilerConfig;
+    const NAME: &'static str = "lua";
+
+    fn compile(
+        &self,
+        code: &mut impl io::Read,
+        config: Self::Config,
+    ) -> CompilationResult<CompiledCode<NativeRuntime>> {
+        let program = interpreter_program(config.interpreter_path.as_deref(), "lua")?;
+
+        // Create temporary directory.
+        let temp_dir = tempfile::Builder::new()
+            .prefix(&config.temp_prefix)
+            .tempdir()?;
+
+        // Create file with lua code.
+        let mut code_file = File::create(temp_dir.path().join("code.lua"))?;
+        io::copy(code, &mut code_file)?;
+
+        // Return the path to the lua file.
+        Ok(CompiledCode {
+            executable: Some(Executable::Path(temp_dir.path().join("code.lua"))),
+            temp_dir_handle: Arc::new(Mutex::new(Some(temp_dir))),
+            additional_data: NativeAdditionalData {
+                program: Some(program),
+                program_args: config.into_args(),
+            },
+            warnings: String::new(),
+            command_line: None,
+            runtime_marker: std::marker::PhantomData,
+        })
+    }
+
+    /// Runs `.lua` files in place.
+    fn compile_file(
+        &self,
+        path: &Path,
+        config: Self::Config,
+    ) -> CompilationResult<CompiledCode<NativeRuntime>> {
+        if path.extension() != Some("lua".as_ref()) {
+            return self.compile(&mut File::open(path)?, config);
+        }
+
+        let program = interpreter_program(config.interpreter_path.as_deref(), "lua")?;
+        Ok(CompiledCode {
+            executable: Some(Executable::Path(std::fs::canonicalize(path)?)),
+            temp_dir_handle: Arc::new(Mutex::new(None)),
+            additional_data: NativeAdditionalData {
+                program: Some(program),
+                program_args: config.into_args(),
+            },
+            warnings: String::new(),
+            command_line: None,
+            runtime_marker: std::marker::PhantomData,
+        })
+    }
+}
+
+impl CompilerInfo for LuaCompiler {
+    fn name(&self) -> &'static str {
+        "lua"
+    }
+
+    fn supported_runtimes(&self) -> Vec<RuntimeKind> {
+        native_runtimes()
+    }
+
+    fn toolchain_version(&self) -> CompilationResult<String> {
+        program_version("lua")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runtimes::CodeRuntime;
+
+    #[test]
+    fn test_lua_native_runtime() {
+        let code = r#"print("Hello")"#;
+
+        let compiled_code = LuaCompiler
+            .compile(&mut code.as_bytes(), Default::default())
+            .unwrap();
+        let result = NativeRuntime
+            .run(&compiled_code, Default::default())
+            .unwrap();
+
+        assert_eq!(result.stdout, Some("Hello\n".to_string()));
+        assert_eq!(result.exit_code, 0);
+    }
+}
@@ -6,17 +6,32 @@
 //! | [Rust](rust_compiler) | [WASM](crate::runtimes::wasm_runtime), [Native](crate::runtimes::native_runtime) |
 //! | [C++](cpp_compiler) | [WASM](crate::runtimes::wasm_runtime), [Native](crate::runtimes::native_runtime) |
 //! | [Python](python_compiler) | [Native](crate::runtimes::native_runtime) |
+//! | [OCaml](ocaml_compiler) | [Native](crate::runtimes::native_runtime) |
+//! | [C#](csharp_compiler) | [Native](crate::runtimes::native_runtime) |
+//! | [PHP](php_compiler) | [Native](crate::runtimes::native_runtime) |
+//! | [Kotlin](kotlin_compiler) | [Native](crate::runtimes::native_runtime) |
+//! | [Swift](swift_compiler) | [Native](crate::runtimes::native_runtime) |
+//! | [Lua](lua_compiler) | [Native](crate::runtimes::native_runtime) |
+//! | [Perl](perl_compiler) | [Native](crate::runtimes::native_runtime) |
+//! | [R](r_compiler) | [Native](crate::runtimes::native_runtime) |
+//! | [Assembly](asm_compiler) | [Native](crate::runtimes::native_runtime) |
+//! | [Haskell](haskell_compiler) | [Native](crate::runtimes::native_runtime) |
+//! | [Zig](zig_compiler) | [WASM](crate::runtimes::wasm_runtime), [Native](crate::runtimes::native_runtime) |
 
 use std::{
     fmt::Debug,
     io,
-    path::PathBuf,
+    path::{Path, PathBuf},
     sync::{Arc, Mutex},
 };
 
+use sha2::{Digest, Sha256};
 use tempfile::TempDir;
 
-use crate::{common::compiler::CompilationResult, runtimes::CodeRuntime};
+use crate::{
+    common::compiler::CompilationResult,
+    runtimes::{CodeRuntime, RuntimeKind},
+};
 
 #[cfg(feature = "cpp")]
 pub mod cpp_compiler;
@@ -28,10 +43,46 @@ pub mod rust_compiler;
 #[cfg(feature = "javascript")]
 pub mod js_compiler;
 
+#[cfg(feature = "ocaml")]
+pub mod ocaml_compiler;
+
+#[cfg(feature = "csharp")]
+pub mod csharp_compiler;
+
+#[cfg(feature = "php")]
+pub mod php_compiler;
+
+#[cfg(feature = "kotlin")]
+pub mod kotlin_compiler;
+
+#[cfg(feature = "swift")]
+pub mod swift_compiler;
+
+#[cfg(feature = "lua")]
+pub mod lua_compiler;
+
+#[cfg(feature = "perl")]
+pub mod perl_compiler;
+
+#[cfg(feature = "r")]
+pub mod r_compiler;
+
+#[cfg(feature = "asm")]
+pub mod asm_compiler;
+
+#[cfg(feature = "haskell")]
+pub mod haskell_compiler;
+
+#[cfg(feature = "zig")]
+pub mod zig_compiler;
+
 /// Trait for every compiler that can be used to compile some code.
 pub trait Compiler<R: CodeRuntime>: Send + Sync + Sized {
     /// Configuration for the compiler.
     type Config: Send + Sync + Sized + Debug + Clone + Default + IntoArgs;
+    /// Name of the compiler (e.g. `rust`), same as [`CompilerInfo::name`]. <br/>
+    /// This is the same for all runtimes, useful for logging and dispatch in generic code.
+    const NAME: &'static str;
 
     /// Compile the given code (as stream of bytes) and return the executable (in temporary file).
     fn compile(
@@ -39,30 +90,239 @@ pub trait Compiler<R: CodeRuntime>: Send + Sync + Sized {
         code: &mut impl io::Read,
         config: Self::Config,
     ) -> CompilationResult<CompiledCode<R>>;
+
+    /// Compile code from the file at `path`. <br/>
+    /// By default the file is copied like any other code, interpreted languages override this
+    /// to run the file in place (if it has the right extension), so large sources are not copied.
+    /// Such code doesn't own the file, so it's not deleted when the code is dropped.
+    fn compile_file(
+        &self,
+        path: &Path,
+        config: Self::Config,
+    ) -> CompilationResult<CompiledCode<R>> {
+        let mut file = std::fs::File::open(path)?;
+        self.compile(&mut file, config)
+    }
+
+    /// Compile the given code and move the executable to `dest`. <br/>
+    /// Temporary directory is cleaned up afterwards, so only the file at `dest` is left.
+    /// Note that some compilers produce files that need additional data to run (e.g. python scripts).
+    fn compile_to(
+        &self,
+        code: &mut impl io::Read,
+        config: Self::Config,
+        dest: &Path,
+    ) -> CompilationResult<()> {
+        let mut compiled = self.compile(code, config)?;
+
+        match compiled.executable()? {
+            Executable::Path(path) => {
+                // Renaming fails across file systems, so fall back to copying.
+                if std::fs::rename(path, dest).is_err() {
+                    std::fs::copy(path, dest)?;
+                }
+            }
+            Executable::Bytes(bytes) => {
+                std::fs::write(dest, bytes)?;
+            }
+        }
+
+        compiled.clean_up()?;
+        Ok(())
+    }
+}
+
+/// Executable produced by the compiler.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Executable {
+    /// Executable stored in a file (usually in temporary directory).
+    Path(PathBuf),
+    /// Executable held in memory. <br/>
+    /// This is useful for lightweight artifacts that don't need to touch the filesystem.
+    /// Runtimes that need a file write it to a temporary file before running.
+    Bytes(Vec<u8>),
 }
 
 /// Compiled code (executable).
 /// Represents compiled code with additional information.
-#[derive(Debug, Clone)]
+#[derive(Debug)]
 pub struct CompiledCode<R: CodeRuntime> {
-    /// Executable file (in temporary file).
-    pub executable: Option<PathBuf>,
+    /// Executable (in temporary file or in memory). <br/>
+    /// This is `None` if compiler didn't produce anything runnable (e.g. metadata-only artifacts).
+    /// Runtimes return [`NotFound`](io::ErrorKind::NotFound) error when asked to run such code.
+    pub executable: Option<Executable>,
 
     /// Handle to the temporary directory.
-    /// This is used to clean up the temporary directory when this object is dropped.
+    /// This is used to clean up the temporary directory when this object is dropped. <br/>
+    /// If this is `None`, executable is not owned by this object (e.g. it comes from a cache) and nothing is deleted.
     pub temp_dir_handle: Arc<Mutex<Option<TempDir>>>,
 
     /// Additional data for the runtime.
     /// This can differ for different runtimes.
     pub additional_data: R::AdditionalData,
 
+    /// Warnings printed by the compiler during successful compilation.
+    /// This is empty if compiler didn't print anything.
+    pub warnings: String,
+
+    /// Command line of the compiler invocation, useful for reproducing the compilation. <br/>
+    /// This is only set by compilers that run a single command (e.g. `rustc` or `clang++`).
+    pub command_line: Option<String>,
+
     /// Runtime marker.
     pub runtime_marker: std::marker::PhantomData<R>,
 }
 
+// Clones share the temporary directory, runtime itself doesn't have to be `Clone`.
+impl<R: CodeRuntime> Clone for CompiledCode<R> {
+    fn clone(&self) -> Self {
+        Self {
+            executable: self.executable.clone(),
+            temp_dir_handle: self.temp_dir_handle.clone(),
+            additional_data: self.additional_data.clone(),
+            warnings: self.warnings.clone(),
+            command_line: self.command_line.clone(),
+            runtime_marker: std::marker::PhantomData,
+        }
+    }
+}
+
 impl<R: CodeRuntime> CompiledCode<R> {
+    /// Creates compiled code from executable bytes (e.g. received from another machine),
+    /// writing them to a fresh temporary directory. <br/>
+    /// Additional data must be transferred along with the executable,
+    /// note that paths in it (e.g. preopen dirs) must exist on this machine.
+    pub fn from_bytes(bytes: &[u8], additional_data: R::AdditionalData) -> io::Result<Self> {
+        let temp_dir = tempfile::Builder::new().prefix("exers-").tempdir()?;
+        let path = write_executable(temp_dir.path(), bytes)?;
+
+        Ok(Self {
+            executable: Some(Executable::Path(path)),
+            temp_dir_handle: Arc::new(Mutex::new(Some(temp_dir))),
+            additional_data,
+            warnings: String::new(),
+            command_line: None,
+            runtime_marker: std::marker::PhantomData,
+        })
+    }
+
+    /// Reads the executable into memory, so it can be sent to another machine.
+    /// See [`CompiledCode::from_bytes`] for the other side.
+    pub fn read_executable(&self) -> io::Result<Vec<u8>> {
+        match self.executable()? {
+            Executable::Path(path) => std::fs::read(path),
+            Executable::Bytes(bytes) => Ok(bytes.clone()),
+        }
+    }
+
+    /// Returns hex encoded SHA-256 of the executable bytes. <br/>
+    /// Identical compiler outputs have the same hash, so it can be used for deduplicating them
+    /// or verifying that persisted executable matches the one that was produced.
+    pub fn executable_bytes_hash(&self) -> io::Result<String> {
+        let mut hasher = Sha256::new();
+        match self.executable()? {
+            Executable::Path(path) => {
+                io::copy(&mut std::fs::File::open(path)?, &mut hasher)?;
+            }
+            Executable::Bytes(bytes) => hasher.update(bytes),
+        }
+        Ok(format!("{:x}", hasher.finalize()))
+    }
+
+    /// Returns the executable.
+    /// Fails with [`NotFound`](io::ErrorKind::NotFound) error if there is no executable.
+    pub fn executable(&self) -> io::Result<&Executable> {
+        self.executable.as_ref().ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                "compiled code does not contain an executable",
+            )
+        })
+    }
+
+    /// Returns path to the executable.
+    /// Fails with [`NotFound`](io::ErrorKind::NotFound) error if there is no executable
+    /// and with [`InvalidInput`](io::ErrorKind::InvalidInput) error if executable is held in memory.
+    pub fn executable_path(&self) -> io::Result<&Path> {
+        match self.executable()? {
+            Executable::Path(path) => Ok(path),
+            Executable::Bytes(_) => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "executable is held in memory",
+            )),
+        }
+    }
+
+    /// Returns path to the temporary directory with compiled files (e.g. for mounting it into a runtime). <br/>
+    /// Directory is still owned by this object, so it is deleted when the last clone is dropped.
+    /// This is `None` if there is no temporary directory (e.g. code comes from a cache or was persisted).
+    pub fn work_dir(&self) -> Option<PathBuf> {
+        self.temp_dir_handle
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map(|temp_dir| temp_dir.path().to_path_buf())
+    }
+
+    /// Returns path to the executable, writing in-memory executable to a temporary file if needed. <br/>
+    /// Returned temporary directory (if any) must be kept alive as long as the file is used.
+    pub(crate) fn materialize_executable(&self) -> io::Result<(PathBuf, Option<TempDir>)> {
+        match self.executable()? {
+            Executable::Path(path) => Ok((path.clone(), None)),
+            Executable::Bytes(bytes) => {
+                let temp_dir = tempfile::Builder::new().prefix("exers-").tempdir()?;
+                let path = write_executable(temp_dir.path(), bytes)?;
+                Ok((path, Some(temp_dir)))
+            }
+        }
+    }
+
+    /// Moves compiled files out of the temporary directory to `dest` directory (created if needed)
+    /// and returns new path to the executable. <br/>
+    /// All files from the temporary directory are moved, so files the executable needs
+    /// (e.g. python scripts or libraries) are kept along with it. In-memory executable is written to `dest`.
+    /// Files are owned by the caller afterwards, so they are not deleted when this object is dropped. <br/>
+    /// Note that clones of this object (sharing the temporary directory) still point to the old location,
+    /// and paths in additional data (e.g. preopen dirs) are not updated.
+    pub fn persist(&mut self, dest: &Path) -> io::Result<PathBuf> {
+        let executable = self.executable()?.clone();
+        std::fs::create_dir_all(dest)?;
+
+        // Move contents of the temporary directory, the directory itself is deleted afterwards.
+        let temp_dir = self.temp_dir_handle.lock().unwrap().take();
+        if let Some(temp_dir) = &temp_dir {
+            for entry in std::fs::read_dir(temp_dir.path())? {
+                let entry = entry?;
+                move_path(&entry.path(), &dest.join(entry.file_name()))?;
+            }
+        }
+
+        let path = match executable {
+            Executable::Bytes(bytes) => write_executable(dest, &bytes)?,
+            Executable::Path(path) => match temp_dir
+                .as_ref()
+                .and_then(|temp_dir| path.strip_prefix(temp_dir.path()).ok())
+            {
+                Some(relative) => dest.join(relative),
+                // Executable is not owned by this object, so it's copied.
+                None => {
+                    let new_path = dest.join(path.file_name().unwrap_or_default());
+                    std::fs::copy(&path, &new_path)?;
+                    new_path
+                }
+            },
+        };
+
+        if let Some(temp_dir) = temp_dir {
+            temp_dir.close()?;
+        }
+
+        self.executable = Some(Executable::Path(path.clone()));
+        Ok(path)
+    }
+
     /// Clean up the compiled code.
-    /// This deletes the temporary directory containing the executable.
+    /// This deletes the temporary directory containing the executable (for all clones of this object).
     pub fn clean_up(&mut self) -> io::Result<()> {
         // Delete the temporary directory.
         if let Some(temp_dir) = self.temp_dir_handle.lock().unwrap().take() {
@@ -71,14 +331,70 @@ impl<R: CodeRuntime> CompiledCode<R> {
 
         Ok(())
     }
+
+    /// Converts this code to code for another runtime, mapping additional data using given function.
+    /// Ownership of the temporary directory is moved to the returned code.
+    pub(crate) fn map_runtime<T: CodeRuntime>(
+        mut self,
+        map: impl FnOnce(R::AdditionalData) -> T::AdditionalData,
+    ) -> CompiledCode<T> {
+        CompiledCode {
+            executable: self.executable.take(),
+            temp_dir_handle: std::mem::replace(
+                &mut self.temp_dir_handle,
+                Arc::new(Mutex::new(None)),
+            ),
+            additional_data: map(std::mem::take(&mut self.additional_data)),
+            warnings: std::mem::take(&mut self.warnings),
+            command_line: self.command_line.take(),
+            runtime_marker: std::marker::PhantomData,
+        }
+    }
 }
 
 impl<R: CodeRuntime> Drop for CompiledCode<R> {
     fn drop(&mut self) {
-        self.clean_up().unwrap();
+        // Clones share the directory, so it's deleted together with the last one.
+        if Arc::get_mut(&mut self.temp_dir_handle).is_some() {
+            self.clean_up().unwrap();
+        }
     }
 }
 
+/// Moves file or directory, copying it if renaming fails (e.g. across file systems).
+fn move_path(from: &Path, to: &Path) -> io::Result<()> {
+    if std::fs::rename(from, to).is_ok() {
+        return Ok(());
+    }
+
+    if from.is_dir() {
+        std::fs::create_dir_all(to)?;
+        for entry in std::fs::read_dir(from)? {
+            let entry = entry?;
+            move_path(&entry.path(), &to.join(entry.file_name()))?;
+        }
+        std::fs::remove_dir(from)
+    } else {
+        std::fs::copy(from, to)?;
+        std::fs::remove_file(from)
+    }
+}
+
+/// Writes executable bytes to `executable` file in given directory and returns path to it.
+fn write_executable(dir: &Path, bytes: &[u8]) -> io::Result<PathBuf> {
+    let path = dir.join("executable");
+    std::fs::write(&path, bytes)?;
+
+    // Make the file executable.
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755))?;
+    }
+
+    Ok(path)
+}
+
 // Converts Config to args.
 pub trait IntoArgs {
     fn into_args(self) -> Vec<String>;
@@ -89,3 +405,67 @@ impl IntoArgs for () {
         vec![]
     }
 }
+
+/// Information about the compiler, available without knowing the runtime at compile time. <br/>
+/// Unlike [`Compiler`], this is object safe, so it can be used to list languages and runtimes
+/// (e.g. in language selection UI). See [`available_compilers`].
+pub trait CompilerInfo: Send + Sync {
+    /// Name of the compiler (e.g. `rust`).
+    fn name(&self) -> &'static str;
+
+    /// Runtimes this compiler can compile code for. <br/>
+    /// Only runtimes enabled by crate features are listed.
+    fn supported_runtimes(&self) -> Vec<RuntimeKind>;
+
+    /// Version of the underlying toolchain (e.g. `rustc 1.79.0 (129f3b996 2024-06-10)`). <br/>
+    /// This is the first line of `--version` output of the default compiler program,
+    /// useful for recording which toolchain produced the results.
+    fn toolchain_version(&self) -> CompilationResult<String>;
+}
+
+/// Returns all compilers enabled by crate features.
+pub fn available_compilers() -> Vec<Box<dyn CompilerInfo>> {
+    vec![
+        Box::new(rust_compiler::RustCompiler),
+        Box::new(rust_compiler::CargoRustCompiler),
+        #[cfg(feature = "cpp")]
+        Box::new(cpp_compiler::CppCompiler),
+        #[cfg(feature = "python")]
+        Box::new(python_compiler::PythonCompiler),
+        #[cfg(feature = "javascript")]
+        Box::new(js_compiler::JsCompiler),
+        #[cfg(feature = "ocaml")]
+        Box::new(ocaml_compiler::OCamlCompiler),
+        #[cfg(feature = "csharp")]
+        Box::new(csharp_compiler::CSharpCompiler),
+        #[cfg(feature = "php")]
+        Box::new(php_compiler::PhpCompiler),
+        #[cfg(feature = "kotlin")]
+        Box::new(kotlin_compiler::KotlinCompiler),
+        #[cfg(feature = "swift")]
+        Box::new(swift_compiler::SwiftCompiler),
+        #[cfg(feature = "lua")]
+        Box::new(lua_compiler::LuaCompiler),
+        #[cfg(feature = "perl")]
+        Box::new(perl_compiler::PerlCompiler),
+        #[cfg(feature = "r")]
+        Box::new(r_compiler::RCompiler),
+        #[cfg(feature = "asm")]
+        Box::new(asm_compiler::AsmCompiler),
+        #[cfg(feature = "haskell")]
+        Box::new(haskell_compiler::HaskellCompiler),
+        #[cfg(feature = "zig")]
+        Box::new(zig_compiler::ZigCompiler),
+    ]
+}
+
+/// Returns runtimes available for compilers producing native code.
+/// Jailed runtime is included, as it can run any native code.
+pub(crate) fn native_runtimes() -> Vec<RuntimeKind> {
+    vec![
+        #[cfg(feature = "native")]
+        RuntimeKind::Native,
+        #[cfg(all(feature = "jailed", feature = "native", target_family = "unix"))]
+        RuntimeKind::Jailed,
+    ]
+}
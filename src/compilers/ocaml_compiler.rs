@@ -0,0 +1,193 @@
+use std::{
+    fs::File,
+    io,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use crate::{
+    common::compiler::{
+        check_program_installed, program_version, run_compiler, CompilationResult, OptLevel,
+        DEFAULT_SPAWN_RETRIES,
+    },
+    runtimes::{native_runtime::NativeRuntime, RuntimeKind},
+};
+
+use super::{native_runtimes, CompiledCode, Compiler, CompilerInfo, Executable, IntoArgs};
+
+/// OCaml compiler.
+/// Compiles code to native executable using `ocamlopt`.
+/// If any packages are requested, `ocamlfind ocamlopt` is used instead. <br/>
+/// For configuration options see [`OCamlCompilerConfig`].
+#[derive(Debug, Clone)]
+pub struct OCamlCompiler;
+
+/// Configuration for OCaml compiler.
+#[derive(Debug, Clone)]
+pub struct OCamlCompilerConfig {
+    /// Opt level for OCaml compiler. <br/>
+    /// This is passed to `ocamlopt` command using `-O2`, `-O3` or `-Oclassic` argument.
+    /// Note that these only have effect on flambda-enabled compilers.
+    pub opt_level: OptLevel,
+
+    /// Packages that should be linked with the code (e.g. `str`, `unix`). <br/>
+    /// These are passed to `ocamlfind` using `-package` argument.
+    pub packages: Vec<String>,
+
+    /// Additional flags for OCaml compiler.
+    pub additional_flags: Vec<String>,
+
+    /// Prefix of temporary directory used for compilation, useful for telling apart concurrent runs. <br/>
+    /// Default is `exers-ocaml-`.
+    pub temp_prefix: String,
+
+    /// Maximum time the compilation can take, compiler is killed when it's exceeded. <br/>
+    /// Default is `None` (no limit).
+    pub compile_timeout: Option<Duration>,
+    /// Number of times spawning the compiler is retried when it fails with transient error
+    /// (e.g. `ETXTBSY` or `EAGAIN`). <br/>
+    /// Default is 3.
+    pub spawn_retries: u32,
+}
+
+impl OCamlCompilerConfig {
+    /// Creates new fully optimized configuration.
+    pub fn optimized() -> Self {
+        Self {
+            opt_level: OptLevel::O3,
+            ..Default::default()
+        }
+    }
+}
+
+// Default configuration for OCaml compiler.
+impl Default for OCamlCompilerConfig {
+    fn default() -> Self {
+        Self {
+            opt_level: OptLevel::None,
+            packages: Vec::new(),
+            additional_flags: Vec::new(),
+            temp_prefix: "exers-ocaml-".to_string(),
+            compile_timeout: None,
+            spawn_retries: DEFAULT_SPAWN_RETRIES,
+        }
+    }
+}
+
+impl IntoArgs for OCamlCompilerConfig {
+    /// Convert this configuration to arguments for `ocamlopt` command.
+    fn into_args(self) -> Vec<String> {
+        let mut args = Vec::new();
+
+        // Add opt level.
+        match self.opt_level {
+            OptLevel::None => {}
+            OptLevel::Size => args.push("-Oclassic".to_string()),
+            OptLevel::O1 | OptLevel::O2 => args.push("-O2".to_string()),
+            OptLevel::O3 | OptLevel::Speed => args.push("-O3".to_string()),
+            OptLevel::Custom(level) => args.push(format!("-O{}", level)),
+        }
+
+        // Add packages.
+        if !self.packages.is_empty() {
+            args.push("-package".to_string());
+            args.push(self.packages.join(","));
+            args.push("-linkpkg".to_string());
+        }
+
+        // Add additional flags.
+        args.extend(self.additional_flags);
+
+        args
+    }
+}
+
+/// Compiler for native runtime.
+impl Compiler<NativeRuntime> for OCamlCompiler {
+    type Config = OCamlCompilerConfig;
+    const NAME: &'static str = "ocaml";
+
+    fn compile(
+        &self,
+        code: &mut impl io::Read,
+        config: Self::Config,
+    ) -> CompilationResult<CompiledCode<NativeRuntime>> {
+        // Packages can only be resolved by ocamlfind.
+        let mut command = if config.packages.is_empty() {
+            check_program_installed("ocamlopt")?;
+            std::process::Command::new("ocamlopt")
+        } else {
+            check_program_installed("ocamlfind")?;
+            let mut command = std::process::Command::new("ocamlfind");
+            command.arg("ocamlopt");
+            command
+        };
+
+        // Create temporary directory for code, intermediate files and executable.
+        let temp_dir = tempfile::Builder::new()
+            .prefix(&config.temp_prefix)
+            .tempdir()?;
+
+        let compile_timeout = config.compile_timeout;
+
+        let spawn_retries = config.spawn_retries;
+
+        // Create file with OCaml code.
+        let mut code_file = File::create(temp_dir.path().join("code.ml"))?;
+        io::copy(code, &mut code_file)?;
+
+        // Compile the code.
+        command.current_dir(temp_dir.path());
+        command.args(config.into_args());
+        command.arg("code.ml");
+        command.arg("-o");
+        command.arg(temp_dir.path().join("executable"));
+
+        let warnings = run_compiler(&mut command, compile_timeout, spawn_retries)?;
+
+        // Return compiled code.
+        Ok(CompiledCode {
+            executable: Some(Executable::Path(temp_dir.path().join("executable"))),
+            temp_dir_handle: Arc::new(Mutex::new(Some(temp_dir))),
+            additional_data: Default::default(),
+            warnings,
+            command_line: None,
+            runtime_marker: std::marker::PhantomData,
+        })
+    }
+}
+
+impl CompilerInfo for OCamlCompiler {
+    fn name(&self) -> &'static str {
+        "ocaml"
+    }
+
+    fn supported_runtimes(&self) -> Vec<RuntimeKind> {
+        native_runtimes()
+    }
+
+    fn toolchain_version(&self) -> CompilationResult<String> {
+        program_version("ocamlopt")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runtimes::CodeRuntime;
+
+    #[test]
+    fn test_ocaml_native_runtime() {
+        let code = r#"let () = print_string "hi""#;
+
+        let compiled_code = OCamlCompiler
+            .compile(&mut code.as_bytes(), Default::default())
+            .unwrap();
+        let result = NativeRuntime
+            .run(&compiled_code, Default::default())
+            .unwrap();
+
+        assert_eq!(result.stdout, Some("hi".to_string()));
+        assert_eq!(result.exit_code, 0);
+    }
+}
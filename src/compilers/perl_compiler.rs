@@ -0,0 +1,223 @@
+use std::{
+    fs::File,
+    io,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use crate::{
+    common::compiler::{interpreter_program, resolve_program, CompilationResult},
+    runtimes::{
+        native_runtime::{NativeAdditionalData, NativeRuntime},
+        RuntimeKind,
+    },
+};
+
+use super::{native_runtimes, CompiledCode, Compiler, CompilerInfo, Executable, IntoArgs};
+
+/// Perl compiler. <br/>
+/// Perl is not compiled, this only creates a temporary file containing the code,
+/// which is then run by `perl` command. <br/>
+/// For configuration options see [`PerlCompilerConfig`].
+#[derive(Debug, Clone)]
+pub struct PerlCompiler;
+
+/// Configuration for Perl compiler.
+#[derive(Debug, Clone)]
+pub struct PerlCompilerConfig {
+    /// Path to Perl interpreter, for systems where it has non-standard name or location
+    /// (e.g. perlbrew installation). <br/>
+    /// Default is `None` (`perl` from `PATH`).
+    pub interpreter_path: Option<PathBuf>,
+
+    /// Directories added to module search path. <br/>
+    /// These are passed to `perl` command using `-I<dir>` arguments.
+    pub include_dirs: Vec<String>,
+
+    /// Modules loaded before the code is run (e.g. `strict` or `List::Util=sum`). <br/>
+    /// These are passed to `perl` command using `-M<module>` arguments.
+    pub modules: Vec<String>,
+
+    /// Prefix of temporary directory used for compilation, useful for telling apart concurrent runs. <br/>
+    /// Default is `exers-perl-`.
+    pub temp_prefix: String,
+}
+
+// Default configuration for Perl compiler.
+impl Default for PerlCompilerConfig {
+    fn default() -> Self {
+        Self {
+            interpreter_path: None,
+            include_dirs: Vec::new(),
+            modules: Vec::new(),
+            temp_prefix: "exers-perl-".to_string(),
+        }
+    }
+}
+
+impl IntoArgs for PerlCompilerConfig {
+    /// Convert this configuration to arguments for `perl` command.
+    fn into_args(self) -> Vec<String> {
+        let mut args = Vec::new();
+
+        // Add include directories.
+        for dir in self.include_dirs {
+            args.push(format!("-I{}", dir));
+        }
+
+        // Add modules.
+        for module in self.modules {
+            args.push(format!("-M{}", module));
+        }
+
+        args
+    }
+}
+
+/// Compiler for native runtime.
+impl Compiler<NativeRuntime> for PerlCompiler {
+    type Config = PerlCompilerConfig;
+    const NAME: &'static str = "perl";
+
+    fn compile(
+        &self,
+        code: &mut impl io::Read,
+        config: Self::Config,
+    ) -> CompilationResult<CompiledCode<NativeRuntime>> {
+        let program = interpreter_program(config.interpreter_path.as_deref(), "perl")?;
+
+        // Create temporary directory.
+        let temp_dir = tempfile::Builder::new()
+            .prefix(&config.temp_prefix)
+            .tempdir()?;
+
+        // Create file with perl code.
+        let mut code_file = File::create(temp_dir.path().join("code.pl"))?;
+        io::copy(code, &mut code_file)?;
+
+        // Return the path to the perl file.
+        Ok(CompiledCode {
+            executable: Some(Executable::Path(temp_dir.path().join("code.pl"))),
+            temp_dir_handle: Arc::new(Mutex::new(Some(temp_dir))),
+            additional_data: NativeAdditionalData {
+                program: Some(program),
+                program_args: config.into_args(),
+            },
+            warnings: String::new(),
+            command_line: None,
+            runtime_marker: std::marker::PhantomData,
+        })
+    }
+
+    /// Runs `.pl` files in place.
+    fn compile_file(
+        &self,
+        path: &Path,
+        config: Self::Config,
+    ) -> CompilationResult<CompiledCode<NativeRuntime>> {
+        if path.extension() != Some("pl".as_ref()) {
+            return self.compile(&mut File::open(path)?, config);
+        }
+
+        let program = interpreter_program(config.interpreter_path.as_deref(), "perl")?;
+        Ok(CompiledCode {
+            executable: Some(Executable::Path(std::fs::canonicalize(path)?)),
+            temp_dir_handle: Arc::new(Mutex::new(None)),
+            additional_data: NativeAdditionalData {
+                program: Some(program),
+                program_args: config.into_args(),
+            },
+            warnings: String::new(),
+            command_line: None,
+            runtime_marker: std::marker::PhantomData,
+        })
+    }
+}
+
+impl CompilerInfo for PerlCompiler {
+    fn name(&self) -> &'static str {
+        "perl"
+    }
+
+    fn supported_runtimes(&self) -> Vec<RuntimeKind> {
+        native_runtimes()
+    }
+
+    fn toolchain_version(&self) -> CompilationResult<String> {
+        // `perl --version` starts with empty line, so the version is printed directly.
+        let output = std::process::Command::new(resolve_program("perl")?)
+            .args(["-e", "print $^V"])
+            .output()?;
+        Ok(format!(
+            "perl {}",
+            String::from_utf8_lossy(&output.stdout).trim()
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runtimes::CodeRuntime;
+
+    #[test]
+    fn test_perl_native_runtime() {
+        let code = r#"print "Hello";"#;
+
+        let compiled_code = PerlCompiler
+            .compile(&mut code.as_bytes(), Default::default())
+            .unwrap();
+        let result = NativeRuntime
+            .run(&compiled_code, Default::default())
+            .unwrap();
+
+        assert_eq!(result.stdout, Some("Hello".to_string()));
+        assert_eq!(result.exit_code, 0);
+    }
+
+    #[test]
+    fn test_perl_modules() {
+        let code = r#"print sum(1, 2, 3);"#;
+
+        let compiled_code = PerlCompiler
+            .compile(
+                &mut code.as_bytes(),
+                PerlCompilerConfig {
+                    modules: vec!["List::Util=sum".to_string()],
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        let result = NativeRuntime
+            .run(&compiled_code, Default::default())
+            .unwrap();
+
+        assert_eq!(result.stdout, Some("6".to_string()));
+    }
+
+    #[test]
+    fn test_perl_interpreter_path() {
+        let config = |interpreter_path| PerlCompilerConfig {
+            interpreter_path: Some(interpreter_path),
+            ..Default::default()
+        };
+
+        let perl = which::which("perl").unwrap();
+        let compiled_code: CompiledCode<NativeRuntime> = PerlCompiler
+            .compile(&mut r#"print "Hello";"#.as_bytes(), config(perl.clone()))
+            .unwrap();
+        assert_eq!(
+            compiled_code.additional_data.program,
+            Some(perl.to_string_lossy().into_owned())
+        );
+
+        assert!(matches!(
+            Compiler::<NativeRuntime>::compile(
+                &PerlCompiler,
+                &mut "".as_bytes(),
+                config("/nonexistent/perl".into())
+            ),
+            Err(crate::common::compiler::CompilationError::ProgramNotInstalled(_))
+        ));
+    }
+}
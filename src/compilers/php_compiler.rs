@@ -0,0 +1,181 @@
+use std::{
+    fs::File,
+    io,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use crate::{
+    common::compiler::{interpreter_program, program_version, CompilationResult},
+    runtimes::{
+        native_runtime::{NativeAdditionalData, NativeRuntime},
+        RuntimeKind,
+    },
+};
+
+use super::{native_runtimes, CompiledCode, Compiler, CompilerInfo, Executable, IntoArgs};
+
+/// PHP compiler. <br/>
+/// PHP is not compiled, this only creates a temporary file containing the code,
+/// which is then run by `php` command. <br/>
+/// For configuration options see [`PhpCompilerConfig`].
+#[derive(Debug, Clone)]
+pub struct PhpCompiler;
+
+/// Configuration for PHP compiler.
+#[derive(Debug, Clone)]
+pub struct PhpCompilerConfig {
+    /// Path to PHP interpreter, for systems where it has non-standard name or location
+    /// (e.g. `php8.2` from `PATH` or `/opt/php/bin/php`). <br/>
+    /// Default is `None` (`php` from `PATH`).
+    pub interpreter_path: Option<PathBuf>,
+
+    /// Ini settings overridden for the code (e.g. `("memory_limit", "128M")`). <br/>
+    /// These are passed to `php` command using `-d <key>=<value>` arguments.
+    pub ini: Vec<(String, String)>,
+
+    /// Prefix of temporary directory used for compilation, useful for telling apart concurrent runs. <br/>
+    /// Default is `exers-php-`.
+    pub temp_prefix: String,
+}
+
+// Default configuration for PHP compiler.
+impl Default for PhpCompilerConfig {
+    fn default() -> Self {
+        Self {
+            interpreter_path: None,
+            ini: Vec::new(),
+            temp_prefix: "exers-php-".to_string(),
+        }
+    }
+}
+
+impl IntoArgs for PhpCompilerConfig {
+    /// Convert this configuration to arguments for `php` command.
+    fn into_args(self) -> Vec<String> {
+        let mut args = Vec::new();
+
+        // Add ini overrides.
+        for (key, value) in self.ini {
+            args.push("-d".to_string());
+            args.push(format!("{}={}", key, value));
+        }
+
+        args
+    }
+}
+
+/// Compiler for native runtime.
+impl Compiler<NativeRuntime> for PhpCompiler {
+    type Config = PhpCompilerConfig;
+    const NAME: &'static str = "php";
+
+    fn compile(
+        &self,
+        code: &mut impl io::Read,
+        config: Self::Config,
+    ) -> CompilationResult<CompiledCode<NativeRuntime>> {
+        let program = interpreter_program(config.interpreter_path.as_deref(), "php")?;
+
+        // Create temporary directory.
+        let temp_dir = tempfile::Builder::new()
+            .prefix(&config.temp_prefix)
+            .tempdir()?;
+
+        // Create file with php code.
+        let mut code_file = File::create(temp_dir.path().join("code.php"))?;
+        io::copy(code, &mut code_file)?;
+
+        // Return the path to the php file.
+        Ok(CompiledCode {
+            executable: Some(Executable::Path(temp_dir.path().join("code.php"))),
+            temp_dir_handle: Arc::new(Mutex::new(Some(temp_dir))),
+            additional_data: NativeAdditionalData {
+                program: Some(program),
+                program_args: config.into_args(),
+            },
+            warnings: String::new(),
+            command_line: None,
+            runtime_marker: std::marker::PhantomData,
+        })
+    }
+
+    /// Runs `.php` files in place.
+    fn compile_file(
+        &self,
+        path: &Path,
+        config: Self::Config,
+    ) -> CompilationResult<CompiledCode<NativeRuntime>> {
+        if path.extension() != Some("php".as_ref()) {
+            return self.compile(&mut File::open(path)?, config);
+        }
+
+        let program = interpreter_program(config.interpreter_path.as_deref(), "php")?;
+        Ok(CompiledCode {
+            executable: Some(Executable::Path(std::fs::canonicalize(path)?)),
+            temp_dir_handle: Arc::new(Mutex::new(None)),
+            additional_data: NativeAdditionalData {
+                program: Some(program),
+                program_args: config.into_args(),
+            },
+            warnings: String::new(),
+            command_line: None,
+            runtime_marker: std::marker::PhantomData,
+        })
+    }
+}
+
+impl CompilerInfo for PhpCompiler {
+    fn name(&self) -> &'static str {
+        "php"
+    }
+
+    fn supported_runtimes(&self) -> Vec<RuntimeKind> {
+        native_runtimes()
+    }
+
+    fn toolchain_version(&self) -> CompilationResult<String> {
+        program_version("php")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runtimes::CodeRuntime;
+
+    #[test]
+    fn test_php_native_runtime() {
+        let code = r#"<?php echo "Hi";"#;
+
+        let compiled_code = PhpCompiler
+            .compile(&mut code.as_bytes(), Default::default())
+            .unwrap();
+        let result = NativeRuntime
+            .run(&compiled_code, Default::default())
+            .unwrap();
+
+        assert_eq!(result.stdout, Some("Hi".to_string()));
+        assert_eq!(result.exit_code, 0);
+    }
+
+    #[test]
+    fn test_php_ini() {
+        let code = r#"<?php echo ini_get("precision");"#;
+
+        let compiled_code = PhpCompiler
+            .compile(
+                &mut code.as_bytes(),
+                PhpCompilerConfig {
+                    ini: vec![("precision".to_string(), "5".to_string())],
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        let result = NativeRuntime
+            .run(&compiled_code, Default::default())
+            .unwrap();
+
+        assert_eq!(result.stdout, Some("5".to_string()));
+    }
+}
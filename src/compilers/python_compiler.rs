@@ -1,13 +1,22 @@
 use std::{
+    collections::BTreeMap,
     fmt::Debug,
     fs::File,
+    path::{Path, PathBuf},
     sync::{Arc, Mutex},
+    time::Duration,
 };
 
 #[allow(unused_imports)]
 use crate::{
-    common::compiler::{CompilationError, CompilationResult},
-    runtimes::native_runtime::{NativeAdditionalData, NativeRuntime},
+    common::compiler::{
+        check_file_name, interpreter_program, program_version, run_compiler, write_extra_files,
+        CompilationError, CompilationResult, DEFAULT_SPAWN_RETRIES,
+    },
+    runtimes::{
+        native_runtime::{NativeAdditionalData, NativeRuntime},
+        RuntimeKind,
+    },
 };
 
 /// Include python wasm file if wasm feature is enabled. <br/>
@@ -20,7 +29,7 @@ use crate::common::compiler::check_program_installed;
 
 #[cfg(feature = "cython")]
 use super::cpp_compiler::CppCompiler;
-use super::{Compiler, IntoArgs};
+use super::{native_runtimes, CompiledCode, Compiler, CompilerInfo, Executable, IntoArgs};
 
 /// Python compiler. <br/>
 /// Actually, python is not compiled, but this is used to create a temporary file containing the code. <br/>
@@ -30,10 +39,41 @@ pub struct PythonCompiler;
 
 /// Configuration for Python compiler.
 pub struct PythonCompilerConfig {
-    /// Python version to use. <br/>
+    /// Python version to use (name of the interpreter looked up in `PATH`). <br/>
+    /// This is used only if neither `interpreter_path` nor `minor_version` is set. <br/>
     /// Default is `python3`.
     pub python_version: String,
 
+    /// Minor version of Python 3 that should be used (e.g. `10` for `python3.10`). <br/>
+    /// If set, matching interpreter is looked up in `PATH` (see [`installed_python_versions`])
+    /// and compilation fails if it is not installed. This takes precedence over `python_version`. <br/>
+    /// Default is `None`.
+    pub minor_version: Option<u32>,
+
+    /// Path to the interpreter, for systems where it has non-standard name or location.
+    /// Compilation fails if it is not installed. <br/>
+    /// This takes precedence over both `minor_version` and `python_version`. <br/>
+    /// Default is `None`.
+    pub interpreter_path: Option<PathBuf>,
+
+    /// Whether to check syntax of the code during compilation. <br/>
+    /// This runs `python -m py_compile` and returns `CompilationFailed` error with the traceback
+    /// if the code is invalid. <br/>
+    /// Default is `true`.
+    pub check_syntax: bool,
+
+    /// Name of the file containing the code, visible to it as `__file__` and module name
+    /// (e.g. `main.py`). It must be a plain file name.
+    /// Files run in place by [`compile_file`](Compiler::compile_file) keep their own name. <br/>
+    /// Default is `code.py`.
+    pub main_file_name: String,
+
+    /// Additional files written next to the code, keyed by path relative to it (e.g. `helper.py`),
+    /// so that the code can import them. <br/>
+    /// These are not used when compiling with cython. <br/>
+    /// Default is empty (code is a single file).
+    pub extra_files: BTreeMap<PathBuf, Vec<u8>>,
+
     /// Whether to use cython to compile the code. <br/>
     /// This option is only available if `cython` feature is enabled.
     #[cfg(feature = "cython")]
@@ -43,13 +83,38 @@ pub struct PythonCompilerConfig {
     /// This is only used if `use_cython` is true.
     #[cfg(feature = "cython")]
     pub cpp_config: super::cpp_compiler::CppCompilerConfig,
+
+    /// Prefix of temporary directory used for compilation, useful for telling apart concurrent runs. <br/>
+    /// Default is `exers-python-`.
+    pub temp_prefix: String,
+
+    /// Maximum time the compilation can take, compiler is killed when it's exceeded. <br/>
+    /// Default is `None` (no limit).
+    pub compile_timeout: Option<Duration>,
+    /// Number of times spawning the compiler is retried when it fails with transient error
+    /// (e.g. `ETXTBSY` or `EAGAIN`). <br/>
+    /// Default is 3.
+    pub spawn_retries: u32,
 }
 
 impl Debug for PythonCompilerConfig {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.debug_struct("PythonCompilerConfig")
+        let mut debug = f.debug_struct("PythonCompilerConfig");
+        debug
             .field("python_version", &self.python_version)
-            .finish()
+            .field("minor_version", &self.minor_version)
+            .field("interpreter_path", &self.interpreter_path)
+            .field("check_syntax", &self.check_syntax)
+            .field("main_file_name", &self.main_file_name)
+            .field("extra_files", &self.extra_files)
+            .field("temp_prefix", &self.temp_prefix)
+            .field("compile_timeout", &self.compile_timeout)
+            .field("spawn_retries", &self.spawn_retries);
+        #[cfg(feature = "cython")]
+        debug
+            .field("use_cython", &self.use_cython)
+            .field("cpp_config", &self.cpp_config);
+        debug.finish()
     }
 }
 
@@ -57,6 +122,14 @@ impl Clone for PythonCompilerConfig {
     fn clone(&self) -> Self {
         Self {
             python_version: self.python_version.clone(),
+            minor_version: self.minor_version,
+            interpreter_path: self.interpreter_path.clone(),
+            check_syntax: self.check_syntax,
+            main_file_name: self.main_file_name.clone(),
+            extra_files: self.extra_files.clone(),
+            temp_prefix: self.temp_prefix.clone(),
+            compile_timeout: self.compile_timeout,
+            spawn_retries: self.spawn_retries,
             #[cfg(feature = "cython")]
             use_cython: self.use_cython,
             #[cfg(feature = "cython")]
@@ -69,25 +142,103 @@ impl Default for PythonCompilerConfig {
     fn default() -> Self {
         Self {
             python_version: "python3".to_string(),
+            minor_version: None,
+            interpreter_path: None,
+            check_syntax: true,
+            main_file_name: "code.py".to_string(),
+            extra_files: BTreeMap::new(),
             #[cfg(feature = "cython")]
             use_cython: false,
             #[cfg(feature = "cython")]
             cpp_config: super::cpp_compiler::CppCompilerConfig::default(),
+            temp_prefix: "exers-python-".to_string(),
+            compile_timeout: None,
+            spawn_retries: DEFAULT_SPAWN_RETRIES,
         }
     }
 }
 
 impl PythonCompilerConfig {
+    /// Creates configuration using given minor version of Python 3 (e.g. `10` for `python3.10`).
+    pub fn with_minor_version(minor_version: u32) -> Self {
+        Self {
+            minor_version: Some(minor_version),
+            ..Default::default()
+        }
+    }
+
     #[cfg(feature = "cython")]
     fn cython_default() -> Self {
         Self {
             python_version: "python3".to_string(),
+            minor_version: None,
+            interpreter_path: None,
+            check_syntax: true,
+            main_file_name: "code.py".to_string(),
+            extra_files: BTreeMap::new(),
+            temp_prefix: "exers-python-".to_string(),
+            compile_timeout: None,
+            spawn_retries: DEFAULT_SPAWN_RETRIES,
             use_cython: true,
             cpp_config: super::cpp_compiler::CppCompilerConfig::default(),
         }
     }
 }
 
+/// Python interpreter installed on the system.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PythonInterpreter {
+    /// Minor version of Python 3 (e.g. `10` for `python3.10`).
+    pub minor_version: u32,
+
+    /// Path to the interpreter.
+    pub path: PathBuf,
+}
+
+/// Returns all Python 3 interpreters found in `PATH` (executables named `python3.x`),
+/// sorted by minor version. <br/>
+/// If the same version is installed multiple times, the first one in `PATH` is returned.
+pub fn installed_python_versions() -> Vec<PythonInterpreter> {
+    let mut interpreters: Vec<PythonInterpreter> = Vec::new();
+
+    let paths = std::env::var_os("PATH").unwrap_or_default();
+    for dir in std::env::split_paths(&paths) {
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            continue;
+        };
+
+        for entry in entries.flatten() {
+            // Parse minor version from file name.
+            let file_name = entry.file_name();
+            let Some(minor_version) = file_name
+                .to_str()
+                .and_then(|name| name.strip_prefix("python3."))
+                .and_then(|minor| minor.parse().ok())
+            else {
+                continue;
+            };
+
+            // Skip directories and duplicates.
+            let path = entry.path();
+            if !path.is_file()
+                || interpreters
+                    .iter()
+                    .any(|interpreter| interpreter.minor_version == minor_version)
+            {
+                continue;
+            }
+
+            interpreters.push(PythonInterpreter {
+                minor_version,
+                path,
+            });
+        }
+    }
+
+    interpreters.sort_by_key(|interpreter| interpreter.minor_version);
+    interpreters
+}
+
 impl IntoArgs for PythonCompilerConfig {
     /// Convert this configuration to arguments for `python` command.
     fn into_args(self) -> Vec<String> {
@@ -106,10 +257,31 @@ impl IntoArgs for PythonCompilerConfig {
     }
 }
 
+impl PythonCompiler {
+    /// Returns interpreter requested by the config.
+    fn interpreter(config: &PythonCompilerConfig) -> CompilationResult<String> {
+        if let Some(path) = &config.interpreter_path {
+            return interpreter_program(Some(path), "python3");
+        }
+
+        match config.minor_version {
+            Some(minor_version) => installed_python_versions()
+                .into_iter()
+                .find(|interpreter| interpreter.minor_version == minor_version)
+                .map(|interpreter| interpreter.path.to_string_lossy().into_owned())
+                .ok_or_else(|| {
+                    CompilationError::ProgramNotInstalled(format!("python3.{}", minor_version))
+                }),
+            None => Ok(config.python_version.clone()),
+        }
+    }
+}
+
 /// Compiler for native runtime.
 impl Compiler<NativeRuntime> for PythonCompiler {
     /// Configuration for python compiler.
     type Config = PythonCompilerConfig;
+    const NAME: &'static str = "python";
 
     #[allow(unused_variables)]
     fn compile(
@@ -117,12 +289,28 @@ impl Compiler<NativeRuntime> for PythonCompiler {
         code: &mut impl std::io::Read,
         config: Self::Config,
     ) -> CompilationResult<super::CompiledCode<NativeRuntime>> {
+        // Find requested interpreter.
+        let program = Self::interpreter(&config)?;
+        check_file_name(&config.main_file_name)?;
+
         // Create temporary directory.
-        let temp_dir = tempfile::Builder::new().prefix("exers-").tempdir()?;
+        let temp_dir = tempfile::Builder::new()
+            .prefix(&config.temp_prefix)
+            .tempdir()?;
 
         // Create file with python code
-        let mut code_file = File::create(temp_dir.path().join("code.py"))?;
+        let code_path = temp_dir.path().join(&config.main_file_name);
+        let mut code_file = File::create(&code_path)?;
         std::io::copy(code, &mut code_file)?;
+        write_extra_files(temp_dir.path(), &config.extra_files)?;
+
+        // Check syntax of the code.
+        if config.check_syntax {
+            let mut command = std::process::Command::new(&program);
+            command.current_dir(temp_dir.path());
+            command.args(["-m", "py_compile", &config.main_file_name]);
+            run_compiler(&mut command, config.compile_timeout, config.spawn_retries)?;
+        }
 
         // If cython is enabled, compile the code to C and then compile it using C compiler.
         #[cfg(feature = "cython")]
@@ -130,28 +318,21 @@ impl Compiler<NativeRuntime> for PythonCompiler {
             if config.use_cython {
                 check_program_installed("cython")?;
                 let mut command = std::process::Command::new("cython");
-                command.stderr(std::process::Stdio::piped());
-                command.stdout(std::process::Stdio::null());
-                command.stdin(std::process::Stdio::null());
-
                 command.current_dir(temp_dir.path());
-                command.arg("code.py");
+                command.arg(&config.main_file_name);
                 command.arg("-3"); // Python 3
                 command.arg("--cplus"); // C++ instead of C
                 command.arg("--embed"); // Embed python interpreter into the code
                 command.arg("-o");
                 command.arg("code.cpp");
 
-                let output = command.spawn()?.wait_with_output()?;
-                if !output.status.success() {
-                    return Err(CompilationError::CompilationFailed(
-                        String::from_utf8_lossy(&output.stderr).to_string(),
-                    ));
-                }
+                let cython_warnings =
+                    run_compiler(&mut command, config.compile_timeout, config.spawn_retries)?;
 
                 // Compile the generated C++ code.
                 let mut code_stream = File::open(temp_dir.path().join("code.cpp"))?;
-                let compiled = CppCompiler.compile(&mut code_stream, config.cpp_config)?;
+                let mut compiled = CppCompiler.compile(&mut code_stream, config.cpp_config)?;
+                compiled.warnings.insert_str(0, &cython_warnings);
 
                 // Return the compiled code.
                 return Ok(compiled);
@@ -160,11 +341,56 @@ impl Compiler<NativeRuntime> for PythonCompiler {
 
         // If cython is not enabled, just return the path to the python file.
         Ok(super::CompiledCode {
-            executable: Some(temp_dir.path().join("code.py")),
+            executable: Some(Executable::Path(code_path)),
             temp_dir_handle: Arc::new(Mutex::new(Some(temp_dir))),
             additional_data: NativeAdditionalData {
-                program: Some(config.python_version),
+                program: Some(program),
+                program_args: Vec::new(),
             },
+            warnings: String::new(),
+            command_line: None,
+            runtime_marker: std::marker::PhantomData,
+        })
+    }
+
+    /// Runs `.py` files in place, unless they have to be compiled with Cython or have extra files.
+    fn compile_file(
+        &self,
+        path: &Path,
+        config: Self::Config,
+    ) -> CompilationResult<CompiledCode<NativeRuntime>> {
+        #[cfg(feature = "cython")]
+        let use_cython = config.use_cython;
+        #[cfg(not(feature = "cython"))]
+        let use_cython = false;
+
+        if use_cython || !config.extra_files.is_empty() || path.extension() != Some("py".as_ref()) {
+            return self.compile(&mut File::open(path)?, config);
+        }
+
+        let path = std::fs::canonicalize(path)?;
+        let program = Self::interpreter(&config)?;
+
+        // Check syntax without writing bytecode next to the file (unlike `py_compile`).
+        if config.check_syntax {
+            let mut command = std::process::Command::new(&program);
+            command.args([
+                "-c",
+                "import sys; compile(open(sys.argv[1], 'rb').read(), sys.argv[1], 'exec')",
+            ]);
+            command.arg(&path);
+            run_compiler(&mut command, config.compile_timeout, config.spawn_retries)?;
+        }
+
+        Ok(CompiledCode {
+            executable: Some(Executable::Path(path)),
+            temp_dir_handle: Arc::new(Mutex::new(None)),
+            additional_data: NativeAdditionalData {
+                program: Some(program),
+                program_args: Vec::new(),
+            },
+            warnings: String::new(),
+            command_line: None,
             runtime_marker: std::marker::PhantomData,
         })
     }
@@ -178,6 +404,7 @@ use crate::runtimes::wasm_runtime::{WasmAdditionalData, WasmRuntime};
 impl Compiler<WasmRuntime> for PythonCompiler {
     /// Configuration for python compiler.
     type Config = PythonCompilerConfig;
+    const NAME: &'static str = "python";
 
     #[allow(unused_variables, unreachable_code)]
     fn compile(
@@ -195,33 +422,54 @@ impl Compiler<WasmRuntime> for PythonCompiler {
         }
 
         // Create temporary directory.
-        let temp_dir = tempfile::Builder::new().prefix("exers-").tempdir()?;
+        let temp_dir = tempfile::Builder::new()
+            .prefix(&config.temp_prefix)
+            .tempdir()?;
 
         // Copy python.wasm to the temporary directory.
         let mut wasm_file = File::create(temp_dir.path().join("python.wasm"))?;
         // std::io::copy(&mut PYTHON_WASM.clone(), &mut wasm_file)?;
 
+        check_file_name(&config.main_file_name)?;
+
         // Create sandbox directory.
         std::fs::create_dir(temp_dir.path().join("sandbox"))?;
 
         // Create file with python code
-        let mut code_file = File::create(temp_dir.path().join("sandbox").join("code.py"))?;
+        let mut code_file =
+            File::create(temp_dir.path().join("sandbox").join(&config.main_file_name))?;
         std::io::copy(code, &mut code_file)?;
 
         // Return the compiled code.
         let sandbox_path = temp_dir.path().join("sandbox");
         Ok(super::CompiledCode {
-            executable: Some(temp_dir.path().join("python.wasm")),
+            executable: Some(Executable::Path(temp_dir.path().join("python.wasm"))),
             temp_dir_handle: Arc::new(Mutex::new(Some(temp_dir))),
             additional_data: WasmAdditionalData {
-                args: vec!["/sandbox/code.py".into()],
+                args: vec![format!("/sandbox/{}", config.main_file_name)],
                 preopen_dir: Some(sandbox_path),
             },
+            warnings: String::new(),
+            command_line: None,
             runtime_marker: std::marker::PhantomData,
         })
     }
 }
 
+impl CompilerInfo for PythonCompiler {
+    fn name(&self) -> &'static str {
+        "python"
+    }
+
+    fn supported_runtimes(&self) -> Vec<RuntimeKind> {
+        native_runtimes()
+    }
+
+    fn toolchain_version(&self) -> CompilationResult<String> {
+        program_version("python3")
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::{
@@ -243,6 +491,193 @@ print("Hello, world!", end="")
         assert_eq!(result.stdout, Some("Hello, world!".to_string()));
     }
 
+    #[test]
+    fn test_python_extra_files() {
+        use crate::common::compiler::CompilationError;
+
+        let code = r#"
+from helper import greet
+from utils.strings import world
+print(greet(world()), end="")
+"#;
+        let config = super::PythonCompilerConfig {
+            extra_files: [
+                ("helper.py", "def greet(name): return 'Hello, ' + name"),
+                ("utils/strings.py", "def world(): return 'world!'"),
+            ]
+            .into_iter()
+            .map(|(path, contents)| (path.into(), contents.into()))
+            .collect(),
+            ..Default::default()
+        };
+
+        let compiled = super::PythonCompiler
+            .compile(&mut code.as_bytes(), config)
+            .unwrap();
+        let result = NativeRuntime.run(&compiled, Default::default()).unwrap();
+        assert_eq!(result.stdout, Some("Hello, world!".to_string()));
+
+        // Files can't be written outside of the code directory.
+        let config = super::PythonCompilerConfig {
+            extra_files: [("../helper.py".into(), Vec::new())].into(),
+            ..Default::default()
+        };
+        let result = Compiler::<NativeRuntime>::compile(
+            &super::PythonCompiler,
+            &mut code.as_bytes(),
+            config,
+        );
+        assert!(matches!(
+            result,
+            Err(CompilationError::IoError(e)) if e.kind() == std::io::ErrorKind::InvalidInput
+        ));
+    }
+
+    #[test]
+    fn test_python_main_file_name() {
+        let code = r#"
+import os
+print(os.path.basename(__file__), end="")
+"#;
+        let config = super::PythonCompilerConfig {
+            main_file_name: "main.py".to_string(),
+            ..Default::default()
+        };
+
+        let compiled = super::PythonCompiler
+            .compile(&mut code.as_bytes(), config)
+            .unwrap();
+        assert!(compiled.executable_path().unwrap().ends_with("main.py"));
+        let result = NativeRuntime.run(&compiled, Default::default()).unwrap();
+        assert_eq!(result.stdout, Some("main.py".to_string()));
+
+        // Name can't point outside of the code directory.
+        let config = super::PythonCompilerConfig {
+            main_file_name: "../main.py".to_string(),
+            ..Default::default()
+        };
+        assert!(Compiler::<NativeRuntime>::compile(
+            &super::PythonCompiler,
+            &mut code.as_bytes(),
+            config
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_python_compile_file() {
+        use crate::common::compiler::CompilationError;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("code.py");
+        std::fs::write(&path, r#"print("Hello, world!", end="")"#).unwrap();
+
+        // File is run in place and not deleted with the code.
+        let compiled = super::PythonCompiler
+            .compile_file(&path, Default::default())
+            .unwrap();
+        assert_eq!(
+            compiled.executable_path().unwrap(),
+            std::fs::canonicalize(&path).unwrap()
+        );
+        let result = NativeRuntime.run(&compiled, Default::default()).unwrap();
+        assert_eq!(result.stdout, Some("Hello, world!".to_string()));
+        drop(compiled);
+        assert!(path.exists());
+
+        // Syntax check doesn't leave bytecode behind.
+        std::fs::write(&path, r#"print("Hello, world!""#).unwrap();
+        let compiled: Result<crate::compilers::CompiledCode<NativeRuntime>, _> =
+            super::PythonCompiler.compile_file(&path, Default::default());
+        assert!(matches!(
+            compiled,
+            Err(CompilationError::CompilationFailed { stderr, .. }) if stderr.contains("SyntaxError")
+        ));
+        assert!(!dir.path().join("__pycache__").exists());
+    }
+
+    #[test]
+    fn test_python_minor_version() {
+        use crate::common::compiler::CompilationError;
+        use crate::compilers::python_compiler::{installed_python_versions, PythonCompilerConfig};
+
+        let code = r#"
+import sys
+print(sys.version_info.minor, end="")
+"#;
+
+        // Use any installed version.
+        let interpreter = installed_python_versions().pop().unwrap();
+        let compiled = super::PythonCompiler
+            .compile(
+                &mut code.as_bytes(),
+                PythonCompilerConfig::with_minor_version(interpreter.minor_version),
+            )
+            .unwrap();
+        let result = NativeRuntime.run(&compiled, Default::default()).unwrap();
+        assert_eq!(result.stdout, Some(interpreter.minor_version.to_string()));
+
+        // Version that surely doesn't exist.
+        let compiled: Result<crate::compilers::CompiledCode<NativeRuntime>, _> =
+            super::PythonCompiler.compile(
+                &mut code.as_bytes(),
+                PythonCompilerConfig::with_minor_version(999),
+            );
+        assert!(matches!(
+            compiled,
+            Err(CompilationError::ProgramNotInstalled(_))
+        ));
+    }
+
+    #[test]
+    fn test_python_interpreter_path() {
+        use crate::compilers::python_compiler::PythonCompilerConfig;
+
+        let code = r#"print("Hello, world!", end="")"#;
+
+        // Path takes precedence over minor version that doesn't exist.
+        let config = PythonCompilerConfig {
+            interpreter_path: Some(which::which("python3").unwrap()),
+            minor_version: Some(999),
+            ..Default::default()
+        };
+        let compiled = super::PythonCompiler
+            .compile(&mut code.as_bytes(), config)
+            .unwrap();
+        let result = NativeRuntime.run(&compiled, Default::default()).unwrap();
+        assert_eq!(result.stdout, Some("Hello, world!".to_string()));
+    }
+
+    #[test]
+    fn test_python_check_syntax() {
+        use crate::common::compiler::CompilationError;
+        use crate::compilers::python_compiler::PythonCompilerConfig;
+
+        let code = r#"
+print("Hello, world!"
+"#;
+
+        let compiled: Result<crate::compilers::CompiledCode<NativeRuntime>, _> =
+            super::PythonCompiler.compile(&mut code.as_bytes(), Default::default());
+        assert!(matches!(
+            compiled,
+            Err(CompilationError::CompilationFailed { stderr, .. }) if stderr.contains("SyntaxError")
+        ));
+
+        // Without the check, error is reported only when running the code.
+        let compiled = super::PythonCompiler
+            .compile(
+                &mut code.as_bytes(),
+                PythonCompilerConfig {
+                    check_syntax: false,
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        let result = NativeRuntime.run(&compiled, Default::default()).unwrap();
+        assert_ne!(result.exit_code, 0);
+    }
+
     #[cfg(feature = "cython")]
     #[test]
     fn test_python_compile_native_cython() {
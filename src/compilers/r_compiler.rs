@@ -0,0 +1,224 @@
+use std::{
+    fs::File,
+    io,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use crate::{
+    common::compiler::{interpreter_program, program_version, CompilationResult},
+    runtimes::{
+        native_runtime::{NativeAdditionalData, NativeRuntime},
+        RuntimeKind,
+    },
+};
+
+use super::{native_runtimes, CompiledCode, Compiler, CompilerInfo, Executable, IntoArgs};
+
+/// R compiler. <br/>
+/// R is not compiled, this only creates a temporary file containing the code,
+/// which is then run by `Rscript` (or `R`). <br/>
+/// For configuration options see [`RCompilerConfig`].
+#[derive(Debug, Clone)]
+pub struct RCompiler;
+
+/// Configuration for R compiler.
+#[derive(Debug, Clone)]
+pub struct RCompilerConfig {
+    /// Frontend used to run the code, which decides the program and its arguments. <br/>
+    /// Default is [`RFrontend::Rscript`].
+    pub frontend: RFrontend,
+
+    /// Path to the program of the frontend (`Rscript` or `R`), for systems where it has non-standard location
+    /// (e.g. R installed with rig). <br/>
+    /// Default is `None` (program of the frontend from `PATH`).
+    pub interpreter_path: Option<PathBuf>,
+
+    /// Additional flags for the interpreter (e.g. `--default-packages=stats`).
+    pub additional_flags: Vec<String>,
+
+    /// Prefix of temporary directory used for compilation, useful for telling apart concurrent runs. <br/>
+    /// Default is `exers-r-`.
+    pub temp_prefix: String,
+}
+
+/// Frontend used to run R code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RFrontend {
+    /// `Rscript <file>`. <br/>
+    /// This is the default frontend.
+    #[default]
+    Rscript,
+    /// `R --vanilla --no-echo -f <file>`, which ignores all profiles and saved workspaces.
+    /// Commands are not echoed, so output is the same as with `Rscript`.
+    R,
+}
+
+impl RFrontend {
+    /// Returns name of the program.
+    pub fn program(&self) -> &'static str {
+        match self {
+            RFrontend::Rscript => "Rscript",
+            RFrontend::R => "R",
+        }
+    }
+}
+
+// Default configuration for R compiler.
+impl Default for RCompilerConfig {
+    fn default() -> Self {
+        Self {
+            frontend: RFrontend::default(),
+            interpreter_path: None,
+            additional_flags: Vec::new(),
+            temp_prefix: "exers-r-".to_string(),
+        }
+    }
+}
+
+impl IntoArgs for RCompilerConfig {
+    /// Convert this configuration to arguments for the interpreter, which are followed by the file.
+    fn into_args(self) -> Vec<String> {
+        let mut args = Vec::new();
+
+        match self.frontend {
+            RFrontend::Rscript => args.extend(self.additional_flags),
+            RFrontend::R => {
+                args.push("--vanilla".to_string());
+                args.push("--no-echo".to_string());
+                args.extend(self.additional_flags);
+                // File has to be last, right after `-f`.
+                args.push("-f".to_string());
+            }
+        }
+
+        args
+    }
+}
+
+/// Compiler for native runtime.
+impl Compiler<NativeRuntime> for RCompiler {
+    type Config = RCompilerConfig;
+    const NAME: &'static str = "r";
+
+    fn compile(
+        &self,
+        code: &mut impl io::Read,
+        config: Self::Config,
+    ) -> CompilationResult<CompiledCode<NativeRuntime>> {
+        let program = interpreter_program(
+            config.interpreter_path.as_deref(),
+            config.frontend.program(),
+        )?;
+
+        // Create temporary directory.
+        let temp_dir = tempfile::Builder::new()
+            .prefix(&config.temp_prefix)
+            .tempdir()?;
+
+        // Create file with R code.
+        let mut code_file = File::create(temp_dir.path().join("code.R"))?;
+        io::copy(code, &mut code_file)?;
+
+        // Return the path to the R file.
+        Ok(CompiledCode {
+            executable: Some(Executable::Path(temp_dir.path().join("code.R"))),
+            temp_dir_handle: Arc::new(Mutex::new(Some(temp_dir))),
+            additional_data: NativeAdditionalData {
+                program: Some(program),
+                program_args: config.into_args(),
+            },
+            warnings: String::new(),
+            command_line: None,
+            runtime_marker: std::marker::PhantomData,
+        })
+    }
+
+    /// Runs `.R` files in place.
+    fn compile_file(
+        &self,
+        path: &Path,
+        config: Self::Config,
+    ) -> CompilationResult<CompiledCode<NativeRuntime>> {
+        if path.extension() != Some("R".as_ref()) {
+            return self.compile(&mut File::open(path)?, config);
+        }
+
+        let program = interpreter_program(
+            config.interpreter_path.as_deref(),
+            config.frontend.program(),
+        )?;
+        Ok(CompiledCode {
+            executable: Some(Executable::Path(std::fs::canonicalize(path)?)),
+            temp_dir_handle: Arc::new(Mutex::new(None)),
+            additional_data: NativeAdditionalData {
+                program: Some(program),
+                program_args: config.into_args(),
+            },
+            warnings: String::new(),
+            command_line: None,
+            runtime_marker: std::marker::PhantomData,
+        })
+    }
+}
+
+impl CompilerInfo for RCompiler {
+    fn name(&self) -> &'static str {
+        "r"
+    }
+
+    fn supported_runtimes(&self) -> Vec<RuntimeKind> {
+        native_runtimes()
+    }
+
+    fn toolchain_version(&self) -> CompilationResult<String> {
+        program_version("Rscript")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runtimes::CodeRuntime;
+
+    #[test]
+    fn test_r_args() {
+        let config = |frontend| RCompilerConfig {
+            frontend,
+            additional_flags: vec!["--default-packages=stats".to_string()],
+            ..Default::default()
+        };
+
+        assert_eq!(
+            config(RFrontend::Rscript).into_args(),
+            ["--default-packages=stats"]
+        );
+        assert_eq!(
+            config(RFrontend::R).into_args(),
+            ["--vanilla", "--no-echo", "--default-packages=stats", "-f"]
+        );
+    }
+
+    #[test]
+    fn test_r_native_runtime() {
+        let code = r#"cat("Hello")"#;
+
+        for frontend in [RFrontend::Rscript, RFrontend::R] {
+            let compiled_code = RCompiler
+                .compile(
+                    &mut code.as_bytes(),
+                    RCompilerConfig {
+                        frontend,
+                        ..Default::default()
+                    },
+                )
+                .unwrap();
+            let result = NativeRuntime
+                .run(&compiled_code, Default::default())
+                .unwrap();
+
+            assert_eq!(result.stdout, Some("Hello".to_string()));
+            assert_eq!(result.exit_code, 0);
+        }
+    }
+}
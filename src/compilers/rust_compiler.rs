@@ -1,14 +1,19 @@
 use std::{
     io,
+    path::PathBuf,
     sync::{Arc, Mutex},
+    time::Duration,
 };
 
 use crate::{
-    common::compiler::{check_program_installed, CompilationError, CompilationResult, OptLevel},
-    runtimes::CodeRuntime,
+    common::compiler::{
+        check_program_installed, command_line, program_version, run_compiler,
+        run_compiler_with_stdin, CompilationResult, OptLevel, DEFAULT_SPAWN_RETRIES,
+    },
+    runtimes::{CodeRuntime, RuntimeKind},
 };
 
-use super::{CompiledCode, Compiler, IntoArgs};
+use super::{native_runtimes, CompiledCode, Compiler, CompilerInfo, Executable, IntoArgs};
 
 /// Rust compiler.
 /// Compiles code using `rustc` command. <br/>
@@ -34,23 +39,36 @@ impl RustCompiler {
         check_program_installed("rustc")?;
 
         // Create temporary directory for code and executable.
-        let temp_dir = tempfile::Builder::new().prefix("exers-").tempdir()?;
+        let temp_dir = tempfile::Builder::new()
+            .prefix(&config.temp_prefix)
+            .tempdir()?;
 
-        // Create temporary file for code.
-        let mut code_file = tempfile::Builder::new()
-            .prefix("code-")
-            .suffix(".rs")
-            .tempfile_in(temp_dir.path())?;
-        io::copy(code, &mut code_file)?;
+        let compile_timeout = config.compile_timeout;
+
+        let spawn_retries = config.spawn_retries;
+
+        // Create temporary file for code, unless it's passed through stdin.
+        let (code_file, stdin) = if config.use_stdin {
+            let mut stdin = Vec::new();
+            code.read_to_end(&mut stdin)?;
+            (None, Some(stdin))
+        } else {
+            let mut code_file = tempfile::Builder::new()
+                .prefix("code-")
+                .suffix(".rs")
+                .tempfile_in(temp_dir.path())?;
+            io::copy(code, &mut code_file)?;
+            (Some(code_file), None)
+        };
 
         // Compile the code using `rustc` command with given arguments.
         let mut command = std::process::Command::new("rustc");
-        command.stderr(std::process::Stdio::piped());
-        command.stdout(std::process::Stdio::null());
-        command.stdin(std::process::Stdio::null());
         command.current_dir(temp_dir.path());
         command.args(args);
-        command.arg(code_file.path());
+        match &code_file {
+            Some(code_file) => command.arg(code_file.path()),
+            None => command.args(["-"]),
+        };
 
         // Add compiler arguments.
         for arg in config.into_args() {
@@ -60,23 +78,59 @@ impl RustCompiler {
         command.arg("-o");
         command.arg(temp_dir.path().join(output_name));
 
-        let output = command.spawn()?.wait_with_output()?;
-
-        // Check if compilation was successful.
-        if !output.status.success() {
-            return Err(CompilationError::CompilationFailed(
-                String::from_utf8_lossy(&output.stderr).into(),
-            ));
-        }
+        let command_line = command_line(&command);
+        let warnings =
+            run_compiler_with_stdin(&mut command, stdin, compile_timeout, spawn_retries)?;
 
         // Return compiled code.
         Ok(CompiledCode {
-            executable: Some(temp_dir.path().join(output_name)),
+            executable: Some(Executable::Path(temp_dir.path().join(output_name))),
             temp_dir_handle: Arc::new(Mutex::new(Some(temp_dir))),
             additional_data: R::AdditionalData::default(),
+            warnings,
+            command_line: Some(command_line),
             runtime_marker: std::marker::PhantomData,
         })
     }
+
+    /// Check if the given code compiles, without producing an executable.
+    /// This runs `rustc --emit=metadata`, which skips code generation and linking,
+    /// so it's much faster than full compilation.
+    pub fn check(
+        &self,
+        code: &mut impl io::Read,
+        config: RustCompilerConfig,
+    ) -> CompilationResult<()> {
+        check_program_installed("rustc")?;
+
+        // Create temporary directory for code and metadata.
+        let temp_dir = tempfile::Builder::new()
+            .prefix(&config.temp_prefix)
+            .tempdir()?;
+
+        let compile_timeout = config.compile_timeout;
+
+        let spawn_retries = config.spawn_retries;
+
+        // Create temporary file for code.
+        let mut code_file = tempfile::Builder::new()
+            .prefix("code-")
+            .suffix(".rs")
+            .tempfile_in(temp_dir.path())?;
+        io::copy(code, &mut code_file)?;
+
+        // Check the code using `rustc` command.
+        let mut command = std::process::Command::new("rustc");
+        command.current_dir(temp_dir.path());
+        command.arg("--emit=metadata");
+        command.arg(code_file.path());
+        command.args(config.into_args());
+        command.arg("--out-dir");
+        command.arg(temp_dir.path());
+
+        run_compiler(&mut command, compile_timeout, spawn_retries)?;
+        Ok(())
+    }
 }
 
 /// Configuration for rust compiler.
@@ -88,6 +142,34 @@ pub struct RustCompilerConfig {
     /// Codegen units for rust compiler. <br/>
     /// This is passed to `rustc` command using `-C codegen-units=<units>` argument.
     pub codegen_units: u32,
+    /// Rust edition used to compile the code. <br/>
+    /// This is passed to `rustc` command using `--edition <edition>` argument.
+    /// Default is 2021, note that the argument is always passed, so `rustc`'s own default (2015) is never used.
+    pub edition: RustEdition,
+    /// Target triple to compile the code for. <br/>
+    /// This is passed to `rustc` command using `--target <target>` argument.
+    /// If not set, runtime default is used (host for native, `wasm32-wasi` for wasm).
+    pub target: Option<String>,
+    /// Kind of crate produced by the compiler. <br/>
+    /// This is passed to `rustc` command using `--crate-type <type>` argument.
+    /// Default is [`RustCrateType::Bin`].
+    pub crate_type: RustCrateType,
+
+    /// Passes the code to the compiler through stdin (as `-`) instead of temporary file. <br/>
+    /// Default is `false`.
+    pub use_stdin: bool,
+
+    /// Prefix of temporary directory used for compilation, useful for telling apart concurrent runs. <br/>
+    /// Default is `exers-rust-`.
+    pub temp_prefix: String,
+
+    /// Maximum time the compilation can take, compiler is killed when it's exceeded. <br/>
+    /// Default is `None` (no limit).
+    pub compile_timeout: Option<Duration>,
+    /// Number of times spawning the compiler is retried when it fails with transient error
+    /// (e.g. `ETXTBSY` or `EAGAIN`). <br/>
+    /// Default is 3.
+    pub spawn_retries: u32,
 }
 
 impl RustCompilerConfig {
@@ -95,7 +177,19 @@ impl RustCompilerConfig {
     pub fn optimized() -> Self {
         Self {
             opt_level: OptLevel::O3,
-            codegen_units: 1,
+            ..Default::default()
+        }
+    }
+
+    /// Creates configuration for compiling a library to `wasm32-unknown-unknown` module without wasi. <br/>
+    /// Functions marked with `#[no_mangle] pub extern "C"` are exported by the module
+    /// and can be called directly using [`WasmConfig::export`](crate::runtimes::wasm_runtime::WasmConfig::export),
+    /// so the code doesn't need a `main` function.
+    pub fn wasm_library() -> Self {
+        Self {
+            target: Some("wasm32-unknown-unknown".to_string()),
+            crate_type: RustCrateType::Cdylib,
+            ..Default::default()
         }
     }
 }
@@ -106,6 +200,65 @@ impl Default for RustCompilerConfig {
         Self {
             opt_level: OptLevel::None,
             codegen_units: 1,
+            edition: RustEdition::default(),
+            target: None,
+            crate_type: RustCrateType::default(),
+            use_stdin: false,
+            temp_prefix: "exers-rust-".to_string(),
+            compile_timeout: None,
+            spawn_retries: DEFAULT_SPAWN_RETRIES,
+        }
+    }
+}
+
+/// Rust edition. <br/>
+/// Default is 2021 (not 2015 like `rustc` itself uses when no edition is given).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RustEdition {
+    /// Rust 2015 edition.
+    E2015,
+    /// Rust 2018 edition.
+    E2018,
+    /// Rust 2021 edition. <br/>
+    /// This is the default edition.
+    #[default]
+    E2021,
+    /// Rust 2024 edition. <br/>
+    /// Requires rustc 1.85 or newer.
+    E2024,
+}
+
+impl RustEdition {
+    /// Returns edition as accepted by `--edition` argument.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RustEdition::E2015 => "2015",
+            RustEdition::E2018 => "2018",
+            RustEdition::E2021 => "2021",
+            RustEdition::E2024 => "2024",
+        }
+    }
+}
+
+/// Kind of crate produced by rust compiler.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RustCrateType {
+    /// Executable program with `main` function. <br/>
+    /// This is the default crate type.
+    #[default]
+    Bin,
+    /// Dynamic library for use from other languages. <br/>
+    /// For wasm targets this is a module exporting all `#[no_mangle] pub extern "C"` functions,
+    /// which can't be run by native runtime.
+    Cdylib,
+}
+
+impl RustCrateType {
+    /// Returns crate type as accepted by `--crate-type` argument.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RustCrateType::Bin => "bin",
+            RustCrateType::Cdylib => "cdylib",
         }
     }
 }
@@ -115,19 +268,40 @@ impl IntoArgs for RustCompilerConfig {
     fn into_args(self) -> Vec<String> {
         let mut args: Vec<String> = Vec::new();
 
-        // Add opt level.
-        if !matches!(self.opt_level, OptLevel::None) {
+        // Add opt level, rustc has no separate level for speed.
+        let opt_level = match self.opt_level {
+            OptLevel::None => None,
+            OptLevel::O1 => Some("1".to_string()),
+            OptLevel::O2 => Some("2".to_string()),
+            OptLevel::O3 | OptLevel::Speed => Some("3".to_string()),
+            OptLevel::Size => Some("z".to_string()),
+            OptLevel::Custom(level) => Some(level),
+        };
+        if let Some(opt_level) = opt_level {
             args.push("-C".to_string());
-            args.push(format!(
-                "opt-level={}",
-                self.opt_level.as_stanard_opt_char()
-            ));
+            args.push(format!("opt-level={}", opt_level));
         }
 
         // Add codegen units.
         args.push("-C".to_string());
         args.push(format!("codegen-units={}", self.codegen_units));
 
+        // Add edition.
+        args.push("--edition".to_string());
+        args.push(self.edition.as_str().to_string());
+
+        // Add target.
+        if let Some(target) = self.target {
+            args.push("--target".to_string());
+            args.push(target);
+        }
+
+        // Add crate type, binary is the default.
+        if self.crate_type != RustCrateType::Bin {
+            args.push("--crate-type".to_string());
+            args.push(self.crate_type.as_str().to_string());
+        }
+
         args
     }
 }
@@ -138,6 +312,7 @@ use crate::runtimes::wasm_runtime::WasmRuntime;
 #[cfg(feature = "wasm")]
 impl Compiler<WasmRuntime> for RustCompiler {
     type Config = RustCompilerConfig;
+    const NAME: &'static str = "rust";
 
     fn compile(
         &self,
@@ -145,12 +320,34 @@ impl Compiler<WasmRuntime> for RustCompiler {
         config: RustCompilerConfig,
     ) -> CompilationResult<CompiledCode<WasmRuntime>> {
         // Compile the code using `rustc` command with given arguments.
-        self.compile_with_args(
-            code,
-            config,
-            &["--target", "wasm32-wasi"],
-            "executable.wasm",
-        )
+        self.compile_with_args(code, with_wasi_target(config), &[], "executable.wasm")
+    }
+}
+
+/// Compiler for wasmtime runtime.
+#[cfg(feature = "wasmtime")]
+use crate::runtimes::wasmtime_runtime::WasmtimeRuntime;
+#[cfg(feature = "wasmtime")]
+impl Compiler<WasmtimeRuntime> for RustCompiler {
+    type Config = RustCompilerConfig;
+    const NAME: &'static str = "rust";
+
+    fn compile(
+        &self,
+        code: &mut impl io::Read,
+        config: RustCompilerConfig,
+    ) -> CompilationResult<CompiledCode<WasmtimeRuntime>> {
+        // Compile the code using `rustc` command with given arguments.
+        self.compile_with_args(code, with_wasi_target(config), &[], "executable.wasm")
+    }
+}
+
+/// Compile for wasi, unless user wants another wasm target.
+#[cfg(any(feature = "wasm", feature = "wasmtime"))]
+fn with_wasi_target(config: RustCompilerConfig) -> RustCompilerConfig {
+    RustCompilerConfig {
+        target: config.target.or_else(|| Some("wasm32-wasi".to_string())),
+        ..config
     }
 }
 
@@ -160,6 +357,7 @@ use crate::runtimes::native_runtime::NativeRuntime;
 #[cfg(feature = "native")]
 impl Compiler<NativeRuntime> for RustCompiler {
     type Config = RustCompilerConfig;
+    const NAME: &'static str = "rust";
 
     fn compile(
         &self,
@@ -171,9 +369,274 @@ impl Compiler<NativeRuntime> for RustCompiler {
     }
 }
 
+/// Rust compiler with support for external crates.
+/// Compiles code using `cargo`, by generating a temporary Cargo project. <br/>
+/// Dependencies are downloaded by cargo, so this requires network access (or cached crates).
+/// For configuration options see [`CargoRustCompilerConfig`].
+#[derive(Debug, Clone)]
+pub struct CargoRustCompiler;
+
+/// Configuration for cargo rust compiler.
+#[derive(Debug, Clone)]
+pub struct CargoRustCompilerConfig {
+    /// Crates the code depends on, as pairs of name and version requirement (e.g. `("rand", "0.8")`). <br/>
+    /// Names can only contain ASCII letters, digits, `-` and `_`, so only registry dependencies can be added.
+    pub dependencies: Vec<(String, String)>,
+    /// Rust edition used to compile the code. <br/>
+    /// Default is 2021, which is always written to `Cargo.toml`.
+    pub edition: RustEdition,
+    /// Whether to build with `--release` profile. <br/>
+    /// Default: true
+    pub release: bool,
+    /// Maximum number of parallel jobs used by cargo. <br/>
+    /// This is passed to `cargo build` command using `--jobs <jobs>` argument.
+    /// Default is `None` (cargo uses all CPU cores).
+    pub jobs: Option<usize>,
+
+    /// Cargo home directory (registry index, downloaded crates). <br/>
+    /// This is passed to `cargo build` command using `CARGO_HOME` environment variable.
+    /// Default is `None` (cargo's default, usually `~/.cargo`).
+    pub cargo_home: Option<PathBuf>,
+    /// Target directory shared between compilations, so dependencies are built only once. <br/>
+    /// This is passed to `cargo build` command using `--target-dir <dir>` argument,
+    /// concurrent builds are serialized by cargo's own lock on the directory.
+    /// It grows with every compilation, so it should be cleaned up from time to time. <br/>
+    /// Default is `None` (every compilation uses its own target directory).
+    pub target_dir: Option<PathBuf>,
+
+    /// Prefix of temporary directory used for compilation, useful for telling apart concurrent runs. <br/>
+    /// Default is `exers-cargo-`.
+    pub temp_prefix: String,
+
+    /// Maximum time the compilation can take, compiler is killed when it's exceeded. <br/>
+    /// Default is `None` (no limit).
+    pub compile_timeout: Option<Duration>,
+    /// Number of times spawning the compiler is retried when it fails with transient error
+    /// (e.g. `ETXTBSY` or `EAGAIN`). <br/>
+    /// Default is 3.
+    pub spawn_retries: u32,
+}
+
+// Default configuration for cargo rust compiler.
+impl Default for CargoRustCompilerConfig {
+    fn default() -> Self {
+        Self {
+            dependencies: Vec::new(),
+            edition: RustEdition::default(),
+            release: true,
+            jobs: None,
+            cargo_home: None,
+            target_dir: None,
+            temp_prefix: "exers-cargo-".to_string(),
+            compile_timeout: None,
+            spawn_retries: DEFAULT_SPAWN_RETRIES,
+        }
+    }
+}
+
+impl CargoRustCompilerConfig {
+    /// Returns contents of `Cargo.toml` for the generated project. <br/>
+    /// Fails with [`InvalidInput`](io::ErrorKind::InvalidInput) error if any dependency name is invalid.
+    pub fn manifest(&self) -> io::Result<String> {
+        self.manifest_with_name("code")
+    }
+
+    /// Returns contents of `Cargo.toml` for the generated project with given package name.
+    fn manifest_with_name(&self, package_name: &str) -> io::Result<String> {
+        let mut manifest = format!(
+            "[package]\nname = \"{}\"\nversion = \"0.1.0\"\nedition = \"{}\"\n\n[dependencies]\n",
+            package_name,
+            self.edition.as_str()
+        );
+
+        for (name, version) in &self.dependencies {
+            // Anything else could add path or git dependencies, whose build scripts run on the host.
+            let valid_name = !name.is_empty()
+                && name
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+            if !valid_name {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("invalid dependency name: {:?}", name),
+                ));
+            }
+
+            manifest.push_str(&format!("{} = {}\n", name, toml_string(version)));
+        }
+
+        Ok(manifest)
+    }
+}
+
+/// Returns `value` as TOML basic string (quoted and escaped).
+fn toml_string(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len() + 2);
+    escaped.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if c.is_control() => escaped.push_str(&format!("\\u{:04X}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped.push('"');
+    escaped
+}
+
+impl IntoArgs for CargoRustCompilerConfig {
+    /// Convert this configuration to arguments for `cargo build` command.
+    fn into_args(self) -> Vec<String> {
+        let mut args = vec!["--quiet".to_string()];
+
+        if self.release {
+            args.push("--release".to_string());
+        }
+
+        // Add jobs limit.
+        if let Some(jobs) = self.jobs {
+            args.push("--jobs".to_string());
+            args.push(jobs.to_string());
+        }
+
+        // Add shared target directory.
+        if let Some(target_dir) = self.target_dir {
+            args.push("--target-dir".to_string());
+            args.push(target_dir.to_string_lossy().to_string());
+        }
+
+        args
+    }
+}
+
+/// Compiler for native runtime.
+#[cfg(feature = "native")]
+impl Compiler<NativeRuntime> for CargoRustCompiler {
+    type Config = CargoRustCompilerConfig;
+    const NAME: &'static str = "cargo";
+
+    fn compile(
+        &self,
+        code: &mut impl io::Read,
+        config: CargoRustCompilerConfig,
+    ) -> CompilationResult<CompiledCode<NativeRuntime>> {
+        check_program_installed("cargo")?;
+
+        // Create temporary directory for the project.
+        let temp_dir = tempfile::Builder::new()
+            .prefix(&config.temp_prefix)
+            .tempdir()?;
+
+        let compile_timeout = config.compile_timeout;
+
+        let spawn_retries = config.spawn_retries;
+        let cargo_home = config.cargo_home.clone();
+        let shared_target_dir = config.target_dir.clone();
+
+        // Binaries of all projects end up in the same directory when it's shared,
+        // so every project needs its own name (random part of the temporary directory).
+        let package_name = match shared_target_dir {
+            Some(_) => format!(
+                "code-{}",
+                temp_dir
+                    .path()
+                    .file_name()
+                    .unwrap_or_default()
+                    .to_string_lossy()
+                    .chars()
+                    .filter(|c| c.is_ascii_alphanumeric())
+                    .collect::<String>()
+            ),
+            None => "code".to_string(),
+        };
+
+        // Create project files.
+        std::fs::write(
+            temp_dir.path().join("Cargo.toml"),
+            config.manifest_with_name(&package_name)?,
+        )?;
+        std::fs::create_dir(temp_dir.path().join("src"))?;
+        let mut code_file = std::fs::File::create(temp_dir.path().join("src").join("main.rs"))?;
+        io::copy(code, &mut code_file)?;
+
+        // Build the project.
+        let profile = if config.release { "release" } else { "debug" };
+        let mut command = std::process::Command::new("cargo");
+        command.current_dir(temp_dir.path());
+        command.arg("build");
+        command.args(config.into_args());
+        if let Some(cargo_home) = cargo_home {
+            command.env("CARGO_HOME", cargo_home);
+        }
+
+        let warnings = run_compiler(&mut command, compile_timeout, spawn_retries)?;
+
+        let executable = match shared_target_dir {
+            // Move the binary out of shared target directory, so it's cleaned up with the code.
+            Some(target_dir) => {
+                let built = target_dir.join(profile).join(&package_name);
+                let executable = temp_dir.path().join("code");
+                std::fs::copy(&built, &executable)?;
+                std::fs::remove_file(&built)?;
+                executable
+            }
+            None => temp_dir.path().join("target").join(profile).join("code"),
+        };
+
+        // Return compiled code.
+        Ok(CompiledCode {
+            executable: Some(Executable::Path(executable)),
+            temp_dir_handle: Arc::new(Mutex::new(Some(temp_dir))),
+            additional_data: Default::default(),
+            warnings,
+            command_line: None,
+            runtime_marker: std::marker::PhantomData,
+        })
+    }
+}
+
+impl CompilerInfo for RustCompiler {
+    fn name(&self) -> &'static str {
+        "rust"
+    }
+
+    fn supported_runtimes(&self) -> Vec<RuntimeKind> {
+        #[allow(unused_mut)]
+        let mut runtimes = native_runtimes();
+        #[cfg(feature = "wasm")]
+        runtimes.push(RuntimeKind::Wasm);
+        #[cfg(feature = "wasmtime")]
+        runtimes.push(RuntimeKind::Wasmtime);
+        runtimes
+    }
+
+    fn toolchain_version(&self) -> CompilationResult<String> {
+        program_version("rustc")
+    }
+}
+
+impl CompilerInfo for CargoRustCompiler {
+    fn name(&self) -> &'static str {
+        "cargo"
+    }
+
+    fn supported_runtimes(&self) -> Vec<RuntimeKind> {
+        native_runtimes()
+    }
+
+    fn toolchain_version(&self) -> CompilationResult<String> {
+        program_version("cargo")
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::common::compiler::CompilationError;
 
     #[test]
     #[cfg(feature = "wasm")]
@@ -183,8 +646,402 @@ mod tests {
 
         let compiled_code: CompiledCode<WasmRuntime> =
             RustCompiler.compile(&mut code, config).unwrap();
-        let executable = compiled_code.executable.as_ref().unwrap();
+        let executable = compiled_code.executable_path().unwrap();
 
         assert!(executable.exists());
     }
+
+    #[test]
+    #[cfg(feature = "wasm")]
+    fn test_compile_wasm_library() {
+        use crate::runtimes::wasm_runtime::WasmConfig;
+
+        let mut code =
+            "#[no_mangle] pub extern \"C\" fn square(x: i32) -> i32 { x * x }".as_bytes();
+        let compiled_code: CompiledCode<WasmRuntime> = RustCompiler
+            .compile(&mut code, RustCompilerConfig::wasm_library())
+            .unwrap();
+
+        let (_, values) = WasmRuntime
+            .call(
+                &compiled_code,
+                WasmConfig::export("square", vec![wasmer::Value::I32(7)]),
+            )
+            .unwrap();
+        assert_eq!(values.as_ref(), &[wasmer::Value::I32(49)]);
+    }
+
+    #[test]
+    #[cfg(feature = "native")]
+    fn test_compile_warnings() {
+        let mut code = "fn main() { let unused = 1; }".as_bytes();
+
+        let compiled_code: CompiledCode<NativeRuntime> =
+            RustCompiler.compile(&mut code, Default::default()).unwrap();
+
+        assert!(compiled_code.warnings.contains("unused variable"));
+    }
+
+    #[test]
+    #[cfg(feature = "native")]
+    fn test_compile_opt_levels() {
+        let levels = [
+            OptLevel::None,
+            OptLevel::Speed,
+            OptLevel::Size,
+            OptLevel::O1,
+            OptLevel::O2,
+            OptLevel::O3,
+            OptLevel::Custom("s".to_string()),
+        ];
+
+        for opt_level in levels {
+            let mut code = "fn main() {}".as_bytes();
+            let config = RustCompilerConfig {
+                opt_level: opt_level.clone(),
+                ..Default::default()
+            };
+
+            let compiled_code: CompilationResult<CompiledCode<NativeRuntime>> =
+                RustCompiler.compile(&mut code, config);
+            assert!(compiled_code.is_ok(), "{:?} failed to compile", opt_level);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "native")]
+    fn test_compile_stdin() {
+        use crate::runtimes::CodeRuntime;
+
+        let mut code = "fn main() { println!(\"Hello, world!\"); }".as_bytes();
+        let config = RustCompilerConfig {
+            use_stdin: true,
+            ..Default::default()
+        };
+
+        let compiled_code: CompiledCode<NativeRuntime> =
+            RustCompiler.compile(&mut code, config.clone()).unwrap();
+        let result = NativeRuntime
+            .run(&compiled_code, Default::default())
+            .unwrap();
+        assert_eq!(result.stdout, Some("Hello, world!\n".to_string()));
+        assert!(compiled_code.command_line.clone().unwrap().contains(" - "));
+
+        // Errors are reported like for files.
+        let mut code = "fn main() { this is not rust }".as_bytes();
+        let compiled_code: CompilationResult<CompiledCode<NativeRuntime>> =
+            RustCompiler.compile(&mut code, config);
+        assert!(matches!(
+            compiled_code,
+            Err(CompilationError::CompilationFailed { exit_code: Some(1), command, .. })
+                if command.starts_with("rustc ")
+        ));
+    }
+
+    #[test]
+    #[cfg(feature = "native")]
+    fn test_compile_command_line() {
+        let mut code = "fn main() {}".as_bytes();
+
+        let compiled_code: CompiledCode<NativeRuntime> =
+            RustCompiler.compile(&mut code, Default::default()).unwrap();
+        let command_line = compiled_code.command_line.clone().unwrap();
+
+        assert!(command_line.starts_with("rustc "));
+        assert!(command_line.contains(" --edition 2021 "));
+    }
+
+    #[test]
+    #[cfg(feature = "native")]
+    fn test_compile_edition() {
+        // Disjoint closure captures are only allowed since 2021 edition.
+        let code = r#"
+            struct Point { x: String, y: String }
+            fn main() {
+                let mut point = Point { x: "x".to_string(), y: "y".to_string() };
+                let print_x = || println!("{}", point.x);
+                point.y.push('!');
+                print_x();
+            }
+        "#;
+
+        let compiled_code: CompilationResult<CompiledCode<NativeRuntime>> =
+            RustCompiler.compile(&mut code.as_bytes(), Default::default());
+        assert!(compiled_code.is_ok());
+
+        let compiled_code: CompilationResult<CompiledCode<NativeRuntime>> = RustCompiler.compile(
+            &mut code.as_bytes(),
+            RustCompilerConfig {
+                edition: RustEdition::E2018,
+                ..Default::default()
+            },
+        );
+        assert!(compiled_code.is_err());
+
+        let compiled_code: CompilationResult<CompiledCode<NativeRuntime>> = RustCompiler.compile(
+            &mut code.as_bytes(),
+            RustCompilerConfig {
+                edition: RustEdition::E2024,
+                ..Default::default()
+            },
+        );
+        assert!(compiled_code.is_ok());
+    }
+
+    #[test]
+    #[cfg(feature = "native")]
+    fn test_compile_to() {
+        let mut code = "fn main() { println!(\"Hello, world!\"); }".as_bytes();
+        let dest_dir = tempfile::tempdir().unwrap();
+        let dest = dest_dir.path().join("hello");
+
+        Compiler::<NativeRuntime>::compile_to(&RustCompiler, &mut code, Default::default(), &dest)
+            .unwrap();
+
+        let output = std::process::Command::new(&dest).output().unwrap();
+        assert_eq!(output.stdout, b"Hello, world!\n");
+    }
+
+    #[test]
+    #[cfg(feature = "native")]
+    fn test_persist() {
+        let mut code = "fn main() { println!(\"Hello, world!\"); }".as_bytes();
+        let dest_dir = tempfile::tempdir().unwrap();
+        let dest = dest_dir.path().join("artifact");
+
+        let mut compiled_code: CompiledCode<NativeRuntime> =
+            RustCompiler.compile(&mut code, Default::default()).unwrap();
+        let temp_dir = compiled_code
+            .executable_path()
+            .unwrap()
+            .parent()
+            .unwrap()
+            .to_path_buf();
+
+        let path = compiled_code.persist(&dest).unwrap();
+        assert!(path.starts_with(&dest));
+        assert!(!temp_dir.exists());
+        assert_eq!(compiled_code.executable_path().unwrap(), path);
+
+        // Files are kept after the code is dropped.
+        let result = NativeRuntime
+            .run(&compiled_code, Default::default())
+            .unwrap();
+        assert_eq!(result.stdout, Some("Hello, world!\n".to_string()));
+        drop(compiled_code);
+
+        let output = std::process::Command::new(&path).output().unwrap();
+        assert_eq!(output.stdout, b"Hello, world!\n");
+    }
+
+    #[test]
+    #[cfg(feature = "native")]
+    fn test_work_dir() {
+        let mut code = "fn main() {}".as_bytes();
+
+        let mut compiled_code: CompiledCode<NativeRuntime> =
+            RustCompiler.compile(&mut code, Default::default()).unwrap();
+        let work_dir = compiled_code.work_dir().unwrap();
+        assert!(compiled_code
+            .executable_path()
+            .unwrap()
+            .starts_with(&work_dir));
+
+        // Directory is still deleted along with the code.
+        let clone = compiled_code.clone();
+        assert_eq!(clone.work_dir(), Some(work_dir.clone()));
+        assert!(work_dir.exists());
+        compiled_code.clean_up().unwrap();
+        assert!(!work_dir.exists());
+        assert_eq!(clone.work_dir(), None);
+    }
+
+    #[test]
+    #[cfg(feature = "native")]
+    fn test_compile_temp_prefix() {
+        let mut code = "fn main() {}".as_bytes();
+
+        let compiled_code: CompiledCode<NativeRuntime> = RustCompiler
+            .compile(
+                &mut code,
+                RustCompilerConfig {
+                    temp_prefix: "exers-test-".to_string(),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        let temp_dir = compiled_code.executable_path().unwrap().parent().unwrap();
+
+        assert!(temp_dir
+            .file_name()
+            .unwrap()
+            .to_string_lossy()
+            .starts_with("exers-test-"));
+    }
+
+    #[test]
+    #[cfg(feature = "native")]
+    fn test_supported_runtimes() {
+        assert!(RustCompiler
+            .supported_runtimes()
+            .contains(&RuntimeKind::Native));
+
+        let compilers = crate::compilers::available_compilers();
+        let rust = compilers.iter().find(|c| c.name() == "rust").unwrap();
+        assert_eq!(rust.supported_runtimes(), RustCompiler.supported_runtimes());
+    }
+
+    #[test]
+    fn test_toolchain_version() {
+        assert!(RustCompiler
+            .toolchain_version()
+            .unwrap()
+            .starts_with("rustc "));
+        assert!(CargoRustCompiler
+            .toolchain_version()
+            .unwrap()
+            .starts_with("cargo "));
+    }
+
+    #[test]
+    fn test_cargo_manifest() {
+        let config = CargoRustCompilerConfig {
+            dependencies: vec![("rand".to_string(), "0.8".to_string())],
+            ..Default::default()
+        };
+
+        let manifest = config.manifest().unwrap();
+        assert!(manifest.contains("edition = \"2021\""));
+        assert!(manifest.ends_with("[dependencies]\nrand = \"0.8\"\n"));
+    }
+
+    #[test]
+    fn test_cargo_manifest_hostile_dependency() {
+        let config = CargoRustCompilerConfig {
+            dependencies: vec![(
+                "evil = { path = \"/tmp/evil\" }\nrand".to_string(),
+                "0.8".to_string(),
+            )],
+            ..Default::default()
+        };
+        assert_eq!(
+            config.manifest().unwrap_err().kind(),
+            io::ErrorKind::InvalidInput
+        );
+
+        // Version can't escape its string either.
+        let config = CargoRustCompilerConfig {
+            dependencies: vec![(
+                "rand".to_string(),
+                "0.8\", path = \"/tmp/evil\nx\u{7f}".to_string(),
+            )],
+            ..Default::default()
+        };
+        assert!(config
+            .manifest()
+            .unwrap()
+            .ends_with("rand = \"0.8\\\", path = \\\"/tmp/evil\\nx\\u007F\"\n"));
+    }
+
+    #[test]
+    fn test_opt_level_args() {
+        let args = |opt_level| {
+            RustCompilerConfig {
+                opt_level,
+                ..Default::default()
+            }
+            .into_args()
+        };
+
+        assert!(!args(OptLevel::None)
+            .iter()
+            .any(|a| a.starts_with("opt-level")));
+        assert!(args(OptLevel::Speed).contains(&"opt-level=3".to_string()));
+        assert!(args(OptLevel::Size).contains(&"opt-level=z".to_string()));
+        assert!(args(OptLevel::O2).contains(&"opt-level=2".to_string()));
+    }
+
+    #[test]
+    fn test_crate_type_args() {
+        let args = RustCompilerConfig::wasm_library().into_args();
+        assert!(args.ends_with(&[
+            "--target".to_string(),
+            "wasm32-unknown-unknown".to_string(),
+            "--crate-type".to_string(),
+            "cdylib".to_string()
+        ]));
+        assert!(!RustCompilerConfig::default()
+            .into_args()
+            .contains(&"--crate-type".to_string()));
+    }
+
+    #[test]
+    fn test_cargo_jobs() {
+        let config = CargoRustCompilerConfig {
+            jobs: Some(1),
+            ..Default::default()
+        };
+
+        assert_eq!(config.into_args(), ["--quiet", "--release", "--jobs", "1"]);
+    }
+
+    #[test]
+    #[cfg(feature = "native")]
+    fn test_cargo_shared_target_dir() {
+        use crate::runtimes::CodeRuntime;
+
+        let target_dir = tempfile::tempdir().unwrap();
+        let config = CargoRustCompilerConfig {
+            target_dir: Some(target_dir.path().to_path_buf()),
+            ..Default::default()
+        };
+
+        let compile = |message: &str| {
+            let code = format!("fn main() {{ println!(\"{}\"); }}", message);
+            CargoRustCompiler
+                .compile(&mut code.as_bytes(), config.clone())
+                .unwrap()
+        };
+        let first = compile("first");
+        let second = compile("second");
+
+        // Binaries don't overwrite each other and don't stay in the shared directory.
+        let run = |code| NativeRuntime.run(code, Default::default()).unwrap().stdout;
+        assert_eq!(run(&first), Some("first\n".to_string()));
+        assert_eq!(run(&second), Some("second\n".to_string()));
+        assert!(target_dir.path().join("release").exists());
+        assert!(!first
+            .executable_path()
+            .unwrap()
+            .starts_with(target_dir.path()));
+    }
+
+    #[test]
+    #[cfg(feature = "native")]
+    fn test_cargo_compile_native() {
+        use crate::runtimes::CodeRuntime;
+
+        let mut code = "fn main() { println!(\"Hello, world!\"); }".as_bytes();
+
+        let compiled_code = CargoRustCompiler
+            .compile(&mut code, Default::default())
+            .unwrap();
+        let result = NativeRuntime
+            .run(&compiled_code, Default::default())
+            .unwrap();
+
+        assert_eq!(result.stdout, Some("Hello, world!\n".to_string()));
+    }
+
+    #[test]
+    fn test_check() {
+        let mut code = "fn main() { println!(\"Hello, world!\"); }".as_bytes();
+        assert!(RustCompiler.check(&mut code, Default::default()).is_ok());
+
+        let mut code = "fn main() { let x: u32 = \"not a number\"; }".as_bytes();
+        assert!(matches!(
+            RustCompiler.check(&mut code, Default::default()),
+            Err(CompilationError::CompilationFailed { .. })
+        ));
+    }
 }
@@ -0,0 +1,184 @@
+use std::{
+    fs::File,
+    io,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use crate::{
+    common::compiler::{
+        check_program_installed, program_version, run_compiler, CompilationResult, OptLevel,
+        DEFAULT_SPAWN_RETRIES,
+    },
+    runtimes::{native_runtime::NativeRuntime, RuntimeKind},
+};
+
+use super::{native_runtimes, CompiledCode, Compiler, CompilerInfo, Executable, IntoArgs};
+
+/// Swift compiler.
+/// Compiles code to native executable using `swiftc`. <br/>
+/// For configuration options see [`SwiftCompilerConfig`].
+#[derive(Debug, Clone)]
+pub struct SwiftCompiler;
+
+/// Configuration for Swift compiler.
+#[derive(Debug, Clone)]
+pub struct SwiftCompilerConfig {
+    /// Opt level for Swift compiler. <br/>
+    /// This is passed to `swiftc` command using `-Onone`, `-O` or `-Osize` argument.
+    pub opt_level: OptLevel,
+
+    /// Additional flags for Swift compiler.
+    pub additional_flags: Vec<String>,
+
+    /// Maximum number of frontend jobs run in parallel. <br/>
+    /// This is passed to `swiftc` command using `-j <jobs>` argument.
+    /// Default is `None` (swiftc uses all CPU cores).
+    pub jobs: Option<usize>,
+
+    /// Prefix of temporary directory used for compilation, useful for telling apart concurrent runs. <br/>
+    /// Default is `exers-swift-`.
+    pub temp_prefix: String,
+
+    /// Maximum time the compilation can take, compiler is killed when it's exceeded. <br/>
+    /// Default is `None` (no limit).
+    pub compile_timeout: Option<Duration>,
+    /// Number of times spawning the compiler is retried when it fails with transient error
+    /// (e.g. `ETXTBSY` or `EAGAIN`). <br/>
+    /// Default is 3.
+    pub spawn_retries: u32,
+}
+
+impl SwiftCompilerConfig {
+    /// Creates new fully optimized configuration.
+    pub fn optimized() -> Self {
+        Self {
+            opt_level: OptLevel::O3,
+            ..Default::default()
+        }
+    }
+}
+
+// Default configuration for Swift compiler.
+impl Default for SwiftCompilerConfig {
+    fn default() -> Self {
+        Self {
+            opt_level: OptLevel::None,
+            additional_flags: Vec::new(),
+            jobs: None,
+            temp_prefix: "exers-swift-".to_string(),
+            compile_timeout: None,
+            spawn_retries: DEFAULT_SPAWN_RETRIES,
+        }
+    }
+}
+
+impl IntoArgs for SwiftCompilerConfig {
+    /// Convert this configuration to arguments for `swiftc` command.
+    fn into_args(self) -> Vec<String> {
+        let mut args = Vec::new();
+
+        // Add opt level.
+        match self.opt_level {
+            OptLevel::None => args.push("-Onone".to_string()),
+            OptLevel::Size => args.push("-Osize".to_string()),
+            OptLevel::O1 | OptLevel::O2 | OptLevel::O3 | OptLevel::Speed => {
+                args.push("-O".to_string())
+            }
+            OptLevel::Custom(level) => args.push(format!("-O{}", level)),
+        }
+
+        // Add jobs limit.
+        if let Some(jobs) = self.jobs {
+            args.push("-j".to_string());
+            args.push(jobs.to_string());
+        }
+
+        // Add additional flags.
+        args.extend(self.additional_flags);
+
+        args
+    }
+}
+
+/// Compiler for native runtime.
+impl Compiler<NativeRuntime> for SwiftCompiler {
+    type Config = SwiftCompilerConfig;
+    const NAME: &'static str = "swift";
+
+    fn compile(
+        &self,
+        code: &mut impl io::Read,
+        config: Self::Config,
+    ) -> CompilationResult<CompiledCode<NativeRuntime>> {
+        check_program_installed("swiftc")?;
+
+        // Create temporary directory for code and executable.
+        let temp_dir = tempfile::Builder::new()
+            .prefix(&config.temp_prefix)
+            .tempdir()?;
+
+        let compile_timeout = config.compile_timeout;
+
+        let spawn_retries = config.spawn_retries;
+
+        // Create file with Swift code, `main.swift` allows top-level code.
+        let mut code_file = File::create(temp_dir.path().join("main.swift"))?;
+        io::copy(code, &mut code_file)?;
+
+        // Compile the code.
+        let mut command = std::process::Command::new("swiftc");
+        command.current_dir(temp_dir.path());
+        command.arg("main.swift");
+        command.args(config.into_args());
+        command.arg("-o");
+        command.arg(temp_dir.path().join("executable"));
+
+        let warnings = run_compiler(&mut command, compile_timeout, spawn_retries)?;
+
+        // Return compiled code.
+        Ok(CompiledCode {
+            executable: Some(Executable::Path(temp_dir.path().join("executable"))),
+            temp_dir_handle: Arc::new(Mutex::new(Some(temp_dir))),
+            additional_data: Default::default(),
+            warnings,
+            command_line: None,
+            runtime_marker: std::marker::PhantomData,
+        })
+    }
+}
+
+impl CompilerInfo for SwiftCompiler {
+    fn name(&self) -> &'static str {
+        "swift"
+    }
+
+    fn supported_runtimes(&self) -> Vec<RuntimeKind> {
+        native_runtimes()
+    }
+
+    fn toolchain_version(&self) -> CompilationResult<String> {
+        program_version("swiftc")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runtimes::CodeRuntime;
+
+    #[test]
+    fn test_swift_native_runtime() {
+        let code = r#"print("Hello")"#;
+
+        let compiled_code = SwiftCompiler
+            .compile(&mut code.as_bytes(), Default::default())
+            .unwrap();
+        let result = NativeRuntime
+            .run(&compiled_code, Default::default())
+            .unwrap();
+
+        assert_eq!(result.stdout, Some("Hello\n".to_string()));
+        assert_eq!(result.exit_code, 0);
+    }
+}
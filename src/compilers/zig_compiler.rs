@@ -0,0 +1,275 @@
+use std::{
+    fs::File,
+    io,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use crate::{
+    common::compiler::{
+        check_program_installed, command_line, resolve_program, run_compiler, CompilationResult,
+        OptLevel, DEFAULT_SPAWN_RETRIES,
+    },
+    runtimes::{CodeRuntime, RuntimeKind},
+};
+
+use super::{native_runtimes, CompiledCode, Compiler, CompilerInfo, Executable, IntoArgs};
+
+/// Zig compiler.
+/// Compiles code using `zig build-exe`, both for native and wasm (`wasm32-wasi` target) code. <br/>
+/// For configuration options see [`ZigCompilerConfig`].
+#[derive(Debug, Clone)]
+pub struct ZigCompiler;
+
+impl ZigCompiler {
+    /// Compile the given code (as stream of bytes) and return the executable (in temporary file).
+    /// This also takes additional arguments for `zig build-exe` command (e.g. target).
+    pub fn compile_with_args<R: CodeRuntime>(
+        &self,
+        code: &mut impl io::Read,
+        config: ZigCompilerConfig,
+        args: &[&str],
+        output_name: &str,
+    ) -> CompilationResult<CompiledCode<R>>
+    where
+        Self: Compiler<R>,
+    {
+        check_program_installed("zig")?;
+
+        // Create temporary directory for code and executable.
+        let temp_dir = tempfile::Builder::new()
+            .prefix(&config.temp_prefix)
+            .tempdir()?;
+
+        let compile_timeout = config.compile_timeout;
+
+        let spawn_retries = config.spawn_retries;
+
+        // Create file with Zig code.
+        let mut code_file = File::create(temp_dir.path().join("main.zig"))?;
+        io::copy(code, &mut code_file)?;
+
+        // Compile the code, cache is kept in temporary directory.
+        let mut command = std::process::Command::new("zig");
+        command.current_dir(temp_dir.path());
+        command.args(["build-exe", "main.zig"]);
+        command.args(args);
+        command.args(config.into_args());
+        command.arg(format!(
+            "-femit-bin={}",
+            temp_dir.path().join(output_name).display()
+        ));
+
+        let command_line = command_line(&command);
+        let warnings = run_compiler(&mut command, compile_timeout, spawn_retries)?;
+
+        // Return compiled code.
+        Ok(CompiledCode {
+            executable: Some(Executable::Path(temp_dir.path().join(output_name))),
+            temp_dir_handle: Arc::new(Mutex::new(Some(temp_dir))),
+            additional_data: R::AdditionalData::default(),
+            warnings,
+            command_line: Some(command_line),
+            runtime_marker: std::marker::PhantomData,
+        })
+    }
+}
+
+/// Configuration for Zig compiler.
+#[derive(Debug, Clone)]
+pub struct ZigCompilerConfig {
+    /// Opt level for Zig compiler. <br/>
+    /// This is passed to `zig` command using `-O Debug`, `-O ReleaseFast` or `-O ReleaseSmall` argument.
+    /// Custom level is passed as is (e.g. `ReleaseSafe`).
+    pub opt_level: OptLevel,
+
+    /// Additional flags for Zig compiler.
+    pub additional_flags: Vec<String>,
+
+    /// Maximum number of parallel jobs used by Zig compiler. <br/>
+    /// This is passed to `zig` command using `-j<jobs>` argument.
+    /// Default is `None` (zig uses all CPU cores).
+    pub jobs: Option<usize>,
+
+    /// Prefix of temporary directory used for compilation, useful for telling apart concurrent runs. <br/>
+    /// Default is `exers-zig-`.
+    pub temp_prefix: String,
+
+    /// Maximum time the compilation can take, compiler is killed when it's exceeded. <br/>
+    /// Default is `None` (no limit).
+    pub compile_timeout: Option<Duration>,
+    /// Number of times spawning the compiler is retried when it fails with transient error
+    /// (e.g. `ETXTBSY` or `EAGAIN`). <br/>
+    /// Default is 3.
+    pub spawn_retries: u32,
+}
+
+impl ZigCompilerConfig {
+    /// Creates new fully optimized configuration.
+    pub fn optimized() -> Self {
+        Self {
+            opt_level: OptLevel::Speed,
+            ..Default::default()
+        }
+    }
+}
+
+// Default configuration for Zig compiler.
+impl Default for ZigCompilerConfig {
+    fn default() -> Self {
+        Self {
+            opt_level: OptLevel::None,
+            additional_flags: Vec::new(),
+            jobs: None,
+            temp_prefix: "exers-zig-".to_string(),
+            compile_timeout: None,
+            spawn_retries: DEFAULT_SPAWN_RETRIES,
+        }
+    }
+}
+
+impl IntoArgs for ZigCompilerConfig {
+    /// Convert this configuration to arguments for `zig build-exe` command.
+    fn into_args(self) -> Vec<String> {
+        let mut args = Vec::new();
+
+        // Add opt level, zig has build modes instead of numeric levels.
+        let mode = match self.opt_level {
+            OptLevel::None => "Debug".to_string(),
+            OptLevel::Size => "ReleaseSmall".to_string(),
+            OptLevel::O1 | OptLevel::O2 | OptLevel::O3 | OptLevel::Speed => {
+                "ReleaseFast".to_string()
+            }
+            OptLevel::Custom(mode) => mode,
+        };
+        args.push("-O".to_string());
+        args.push(mode);
+
+        // Add jobs limit.
+        if let Some(jobs) = self.jobs {
+            args.push(format!("-j{}", jobs));
+        }
+
+        // Add additional flags.
+        args.extend(self.additional_flags);
+
+        args
+    }
+}
+
+/// Compiler for wasm runtime.
+#[cfg(feature = "wasm")]
+use crate::runtimes::wasm_runtime::WasmRuntime;
+#[cfg(feature = "wasm")]
+impl Compiler<WasmRuntime> for ZigCompiler {
+    type Config = ZigCompilerConfig;
+    const NAME: &'static str = "zig";
+
+    fn compile(
+        &self,
+        code: &mut impl io::Read,
+        config: Self::Config,
+    ) -> CompilationResult<CompiledCode<WasmRuntime>> {
+        self.compile_with_args(code, config, &["-target", "wasm32-wasi"], "executable.wasm")
+    }
+}
+
+/// Compiler for wasmtime runtime.
+#[cfg(feature = "wasmtime")]
+use crate::runtimes::wasmtime_runtime::WasmtimeRuntime;
+#[cfg(feature = "wasmtime")]
+impl Compiler<WasmtimeRuntime> for ZigCompiler {
+    type Config = ZigCompilerConfig;
+    const NAME: &'static str = "zig";
+
+    fn compile(
+        &self,
+        code: &mut impl io::Read,
+        config: Self::Config,
+    ) -> CompilationResult<CompiledCode<WasmtimeRuntime>> {
+        self.compile_with_args(code, config, &["-target", "wasm32-wasi"], "executable.wasm")
+    }
+}
+
+/// Compiler for native runtime.
+#[cfg(feature = "native")]
+use crate::runtimes::native_runtime::NativeRuntime;
+#[cfg(feature = "native")]
+impl Compiler<NativeRuntime> for ZigCompiler {
+    type Config = ZigCompilerConfig;
+    const NAME: &'static str = "zig";
+
+    fn compile(
+        &self,
+        code: &mut impl io::Read,
+        config: Self::Config,
+    ) -> CompilationResult<CompiledCode<NativeRuntime>> {
+        self.compile_with_args(code, config, &[], "executable")
+    }
+}
+
+impl CompilerInfo for ZigCompiler {
+    fn name(&self) -> &'static str {
+        "zig"
+    }
+
+    fn supported_runtimes(&self) -> Vec<RuntimeKind> {
+        #[allow(unused_mut)]
+        let mut runtimes = native_runtimes();
+        #[cfg(feature = "wasm")]
+        runtimes.push(RuntimeKind::Wasm);
+        #[cfg(feature = "wasmtime")]
+        runtimes.push(RuntimeKind::Wasmtime);
+        runtimes
+    }
+
+    fn toolchain_version(&self) -> CompilationResult<String> {
+        // Zig uses `version` subcommand instead of `--version` flag.
+        let output = std::process::Command::new(resolve_program("zig")?)
+            .arg("version")
+            .output()?;
+        Ok(format!(
+            "zig {}",
+            String::from_utf8_lossy(&output.stdout).trim()
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CODE: &str = r#"
+        const std = @import("std");
+
+        pub fn main() !void {
+            try std.io.getStdOut().writer().print("Hello\n", .{});
+        }
+    "#;
+
+    #[cfg(feature = "native")]
+    #[test]
+    fn test_zig_native_runtime() {
+        let compiled_code = ZigCompiler
+            .compile(&mut CODE.as_bytes(), Default::default())
+            .unwrap();
+        let result = NativeRuntime
+            .run(&compiled_code, Default::default())
+            .unwrap();
+
+        assert_eq!(result.stdout, Some("Hello\n".to_string()));
+        assert_eq!(result.exit_code, 0);
+    }
+
+    #[cfg(feature = "wasm")]
+    #[test]
+    fn test_zig_wasm_runtime() {
+        let compiled_code = ZigCompiler
+            .compile(&mut CODE.as_bytes(), Default::default())
+            .unwrap();
+        let result = WasmRuntime.run(&compiled_code, Default::default()).unwrap();
+
+        assert_eq!(result.stdout, Some("Hello\n".to_string()));
+        assert_eq!(result.exit_code, 0);
+    }
+}
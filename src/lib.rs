@@ -48,9 +48,32 @@
 //! // Print the result.
 //! println!("stdout: {}", result.stdout.unwrap());
 //! ```
+//!
+//! Both steps can also be done at once with [`compile_and_run`](crate::common::report::compile_and_run),
+//! which returns single report with compiler output, execution result and verdict.
+//! Compiled code can be judged against test cases with [`grade`](crate::common::grade::grade).
+//!
+//! ## Tracing
+//! With `tracing` feature enabled, compilation and execution are instrumented using [tracing](https://docs.rs/tracing)
+//! (at debug level), so any subscriber can be used to collect durations and exit statuses. <br/>
+//! Spans:
+//! - `exers::compile` - compilation of the code by [builder](crate::common::builder) or [dynamic](crate::common::dynamic) runtimes.
+//! - `exers::compiler_process` - single compiler subprocess, with `command` field.
+//! - `exers::run` - execution of the code by a runtime, with `runtime` field.
+//! - `exers::wasm_instantiate` - instantiation of wasm module.
+//!
+//! When finished, compiler processes and executed code emit an event with exit status and time taken.
 
 #![allow(clippy::clone_double_ref, clippy::uninlined_format_args)]
 
+/// Emits `tracing` event at debug level, if `tracing` feature is enabled.
+macro_rules! trace_event {
+    ($($arg:tt)*) => {
+        #[cfg(feature = "tracing")]
+        tracing::debug!($($arg)*);
+    };
+}
+
 pub mod common;
 pub mod compilers;
 pub mod runtimes;
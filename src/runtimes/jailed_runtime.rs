@@ -1,7 +1,7 @@
-use std::{io::Write, process::Command};
+use std::{error::Error, fmt::Display, path::PathBuf, process::Command};
 
 use crate::{
-    common::{compiler::CompilationResult, runtime::InputData},
+    common::compiler::{spawn_with_retries, CompilationResult},
     compilers::{CompiledCode, Compiler},
 };
 
@@ -20,6 +20,16 @@ pub struct JailedRuntime;
 #[derive(Debug, Clone, Default)]
 pub struct JailedConfig {
     native_runtime_config: super::native_runtime::NativeConfig,
+
+    /// Prepared root filesystem the code is run in, instead of one assembled from program dependencies. <br/>
+    /// It is mounted read-only and the directory with executable is mounted read-only at `/exers`, so it must contain:
+    /// - empty `/exers` directory (mount point for the code),
+    /// - `/lib` (and `/lib64`) with libc and dynamic loader for dynamically linked programs,
+    /// - interpreter in `/bin` or `/usr/bin` for interpreted languages (e.g. `python3`).
+    ///
+    /// Minimal busybox or debootstrap based rootfs works well. <br/>
+    /// Default is `None` (chroot is built from `ldd` output of the program).
+    pub rootfs: Option<PathBuf>,
 }
 
 /// Error type for the runtime.
@@ -29,7 +39,22 @@ pub enum JailedError {
     IOError(std::io::Error),
     /// Root privileges are required to run chroot jail.
     RootRequired,
+    /// Compiled code doesn't contain an executable.
+    NoExecutable,
+}
+
+impl Display for JailedError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            JailedError::IOError(e) => write!(f, "IO error: {}", e),
+            JailedError::RootRequired => write!(f, "Root privileges are required to run jail"),
+            JailedError::NoExecutable => {
+                write!(f, "Compiled code does not contain an executable")
+            }
+        }
+    }
 }
+impl Error for JailedError {}
 
 impl From<std::io::Error> for JailedError {
     fn from(e: std::io::Error) -> Self {
@@ -37,6 +62,12 @@ impl From<std::io::Error> for JailedError {
     }
 }
 
+impl crate::common::grade::StdinConfig for JailedConfig {
+    fn set_stdin(&mut self, stdin: crate::common::runtime::InputData) {
+        self.native_runtime_config.stdin = stdin;
+    }
+}
+
 /// Runtime for jailed code.
 impl CodeRuntime for JailedRuntime {
     /// Configuration for the runtime.
@@ -45,8 +76,14 @@ impl CodeRuntime for JailedRuntime {
     type AdditionalData = super::native_runtime::NativeAdditionalData;
     /// Error type for the runtime.
     type Error = JailedError;
+    /// Name of the runtime.
+    const NAME: &'static str = "jailed";
 
     /// Runs the code in a chroot jail.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "exers::run", skip_all, fields(runtime = "jailed"))
+    )]
     fn run(
         &self,
         code: &crate::compilers::CompiledCode<Self>,
@@ -57,8 +94,14 @@ impl CodeRuntime for JailedRuntime {
             return Err(Self::Error::RootRequired);
         }
 
+        if code.executable.is_none() {
+            return Err(Self::Error::NoExecutable);
+        }
+
         // Get temporary directory.
-        let temp_dir = code.executable.as_ref().unwrap().parent().unwrap();
+        // In-memory executable is written to its own temporary directory, which must outlive the jail.
+        let (executable, _executable_dir) = code.materialize_executable()?;
+        let temp_dir = executable.parent().unwrap();
 
         // Copy jail script to temporary directory.
         let jail_path = temp_dir.join("jail.sh");
@@ -67,29 +110,42 @@ impl CodeRuntime for JailedRuntime {
         std::fs::write(&jail_path, JAIL)?;
 
         // Run jail
+        let jail_dir = temp_dir.join("jail");
         let mut command = Command::new("bash");
-        command.arg(jail_path);
-        command.arg(temp_dir.join("jail"));
+        command.arg(&jail_path);
+        command.arg(&jail_dir);
 
-        match &code.additional_data.program {
-            Some(program) => {
-                command.arg(which::which(program).unwrap());
-                command.arg(code.executable.as_ref().unwrap());
-            }
-            None => {
-                command.arg(code.executable.as_ref().unwrap());
+        // Script reports when the program started, so time doesn't include jail setup.
+        let start_path = temp_dir.join("jail-start");
+        command.env("JAIL_START_FILE", &start_path);
+
+        match &config.rootfs {
+            Some(rootfs) => {
+                // Code directory is mounted at `/exers` and program is looked up inside rootfs.
+                command.env("JAIL_ROOTFS", rootfs);
+                command.arg(temp_dir);
+
+                let executable = PathBuf::from("/exers").join(executable.file_name().unwrap());
+                if let Some(program) = &code.additional_data.program {
+                    command.arg(program);
+                    command.args(&code.additional_data.program_args);
+                }
+                command.arg(executable);
             }
+            None => match &code.additional_data.program {
+                Some(program) => {
+                    command.arg(which::which(program).unwrap());
+                    command.args(&code.additional_data.program_args);
+                    command.arg(&executable);
+                }
+                None => {
+                    command.arg(&executable);
+                }
+            },
         }
 
         // Setup stdin.
-        match config.native_runtime_config.stdin {
-            InputData::Ignore => {
-                command.stdin(std::process::Stdio::null());
-            }
-            _ => {
-                command.stdin(std::process::Stdio::piped());
-            }
-        };
+        command.stdin(config.native_runtime_config.stdin.stdio()?);
 
         // Setup stdout.
         command.stdout(std::process::Stdio::piped());
@@ -97,28 +153,33 @@ impl CodeRuntime for JailedRuntime {
         command.stderr(std::process::Stdio::piped());
 
         // Spawn the command.
-        let mut child = command.spawn()?;
+        let mut child =
+            spawn_with_retries(&mut command, config.native_runtime_config.spawn_retries)?;
 
         // Start timer.
         let start_time = std::time::Instant::now();
 
-        // Write to stdin.
-        match config.native_runtime_config.stdin {
-            InputData::Ignore => {}
-            InputData::String(data) => {
-                child.stdin.as_mut().unwrap().write_all(data.as_bytes())?;
-            }
-            InputData::File(path) => {
-                let mut file = std::fs::File::open(path)?;
-                std::io::copy(&mut file, child.stdin.as_mut().unwrap())?;
-            }
-        };
+        // Write to stdin in the background.
+        let stdin_writer = config
+            .native_runtime_config
+            .stdin
+            .write_to(child.stdin.take());
 
         // Wait for the child to finish.
-        let output = child.wait_with_output()?;
+        // Script replaces itself with the program, so this is the program's own exit status.
+        let output = child.wait_with_output();
+        let end_time = std::time::SystemTime::now();
+
+        // Remove the jail, even if waiting failed.
+        let cleanup = cleanup_jail(&jail_path, &jail_dir, config.rootfs.as_deref());
+        let output = output?;
+        cleanup?;
+        stdin_writer.join().unwrap()?;
 
-        // Stop timer.
-        let time_taken = start_time.elapsed();
+        // Stop timer, falling back to the wrapper time if the script didn't report it.
+        let time_taken = read_start_time(&start_path)
+            .and_then(|start| end_time.duration_since(start).ok())
+            .unwrap_or_else(|| start_time.elapsed());
 
         // Get stdout.
         let stdout = match output.stdout.len() {
@@ -132,12 +193,19 @@ impl CodeRuntime for JailedRuntime {
             _ => Some(String::from_utf8(output.stderr).unwrap()),
         };
 
+        // Get exit code.
+        let (exit_code, signal) = super::exit_status(output.status);
+        trace_event!(exit_code, ?signal, ?time_taken, "code finished");
+
         // Return the result.
         Ok(super::ExecutionResult {
             stdout,
             stderr,
             time_taken,
-            exit_code: output.status.code().unwrap_or(0),
+            exit_code,
+            signal,
+            sandbox: vec![super::SandboxFeature::Chroot],
+            instructions: None,
         })
     }
 }
@@ -149,6 +217,7 @@ where
 {
     /// Configuration for the compiler.
     type Config = C::Config;
+    const NAME: &'static str = <C as Compiler<NativeRuntime>>::NAME;
 
     /// Compiles the code using the native compiler.
     fn compile(
@@ -157,15 +226,21 @@ where
         config: Self::Config,
     ) -> CompilationResult<crate::compilers::CompiledCode<JailedRuntime>> {
         let native_code: CompiledCode<NativeRuntime> = C::compile(self, code, config)?;
-        // Without this somehow temp_dir disappears :p
-        let temp_dir = native_code.temp_dir_handle.lock().unwrap().take().unwrap();
-        let temp_dir_handle = std::sync::Arc::new(std::sync::Mutex::new(Some(temp_dir)));
-        Ok(CompiledCode {
-            executable: native_code.executable.clone(),
-            temp_dir_handle,
-            runtime_marker: std::marker::PhantomData,
-            additional_data: native_code.additional_data.clone(),
-        })
+        Ok(native_code.into())
+    }
+}
+
+/// Native code can be run in jail without recompiling, as both runtimes use the same additional data.
+impl From<CompiledCode<NativeRuntime>> for CompiledCode<JailedRuntime> {
+    fn from(code: CompiledCode<NativeRuntime>) -> Self {
+        code.map_runtime(|additional_data| additional_data)
+    }
+}
+
+/// Jailed code can be run natively without recompiling, as both runtimes use the same additional data.
+impl From<CompiledCode<JailedRuntime>> for CompiledCode<NativeRuntime> {
+    fn from(code: CompiledCode<JailedRuntime>) -> Self {
+        code.map_runtime(|additional_data| additional_data)
     }
 }
 
@@ -191,6 +266,165 @@ mod tests {
 
         assert_eq!(result.stdout, Some("Hello, world!\n".to_string()));
     }
+
+    #[test]
+    fn test_run_jailed_exit_code() {
+        let code = r#"
+        fn main() {
+            std::process::exit(3);
+        }
+        "#;
+
+        let compiled_code = RustCompiler
+            .compile(&mut code.as_bytes(), Default::default())
+            .unwrap();
+        let result = JailedRuntime
+            .run(&compiled_code, Default::default())
+            .unwrap();
+
+        assert_eq!(result.exit_code, 3);
+        assert_eq!(result.signal, None);
+    }
+
+    #[test]
+    fn test_run_jailed_exit_code_above_128() {
+        let code = r#"
+        fn main() {
+            std::process::exit(130);
+        }
+        "#;
+
+        let compiled_code = RustCompiler
+            .compile(&mut code.as_bytes(), Default::default())
+            .unwrap();
+        let result = JailedRuntime
+            .run(&compiled_code, Default::default())
+            .unwrap();
+
+        // Exit code above 128 is not mistaken for a signal.
+        assert_eq!(result.exit_code, 130);
+        assert_eq!(result.signal, None);
+    }
+
+    #[test]
+    fn test_run_jailed_signal() {
+        let code = r#"
+        fn main() {
+            std::process::abort();
+        }
+        "#;
+
+        let compiled_code = RustCompiler
+            .compile(&mut code.as_bytes(), Default::default())
+            .unwrap();
+        let result = JailedRuntime
+            .run(&compiled_code, Default::default())
+            .unwrap();
+
+        assert_eq!(result.signal, Some(libc::SIGABRT));
+    }
+
+    #[test]
+    fn test_run_jailed_rootfs() {
+        use crate::compilers::Executable;
+
+        let code = r#"
+        fn main() {
+            println!("Hello, world!");
+        }
+        "#;
+
+        let compiled_code = RustCompiler
+            .compile(&mut code.as_bytes(), Default::default())
+            .unwrap();
+        let Some(Executable::Path(executable)) = &compiled_code.executable else {
+            panic!("executable should be stored in a file");
+        };
+
+        // Build minimal rootfs with only the libraries executable links to.
+        let rootfs = tempfile::tempdir().unwrap();
+        std::fs::create_dir(rootfs.path().join("exers")).unwrap();
+        let ldd = Command::new("ldd").arg(executable).output().unwrap();
+        for library in String::from_utf8_lossy(&ldd.stdout)
+            .split_whitespace()
+            .filter(|token| token.starts_with('/'))
+        {
+            let dest = rootfs.path().join(&library[1..]);
+            std::fs::create_dir_all(dest.parent().unwrap()).unwrap();
+            std::fs::copy(library, dest).unwrap();
+        }
+
+        let config = JailedConfig {
+            rootfs: Some(rootfs.path().to_path_buf()),
+            ..Default::default()
+        };
+        let result = JailedRuntime.run(&compiled_code, config).unwrap();
+
+        assert_eq!(result.stdout, Some("Hello, world!\n".to_string()));
+    }
+
+    #[test]
+    fn test_run_jailed_no_executable() {
+        let compiled_code = CompiledCode::<JailedRuntime> {
+            executable: None,
+            temp_dir_handle: Default::default(),
+            additional_data: Default::default(),
+            warnings: String::new(),
+            command_line: None,
+            runtime_marker: std::marker::PhantomData,
+        };
+        let result = JailedRuntime.run(&compiled_code, Default::default());
+
+        assert!(matches!(
+            result,
+            Err(JailedError::NoExecutable) | Err(JailedError::RootRequired)
+        ));
+    }
+
+    #[test]
+    fn test_jailed_code_into_native() {
+        let code = r#"
+        fn main() {
+            println!("Hello, world!");
+        }
+        "#;
+
+        let compiled_code: CompiledCode<JailedRuntime> = RustCompiler
+            .compile(&mut code.as_bytes(), Default::default())
+            .unwrap();
+        let native_code: CompiledCode<NativeRuntime> = compiled_code.into();
+        let result = NativeRuntime.run(&native_code, Default::default()).unwrap();
+
+        assert_eq!(result.stdout, Some("Hello, world!\n".to_string()));
+    }
+}
+
+/// Reads time the jailed program started at (in nanoseconds since epoch), as reported by the jail script.
+fn read_start_time(path: &std::path::Path) -> Option<std::time::SystemTime> {
+    let nanos = std::fs::read_to_string(path).ok()?.trim().parse().ok()?;
+    Some(std::time::UNIX_EPOCH + std::time::Duration::from_nanos(nanos))
+}
+
+/// Removes the jail (unmounting rootfs if it was used) after the program finished.
+fn cleanup_jail(
+    jail_path: &std::path::Path,
+    jail_dir: &std::path::Path,
+    rootfs: Option<&std::path::Path>,
+) -> std::io::Result<()> {
+    let mut command = Command::new("bash");
+    command
+        .arg(jail_path)
+        .arg(jail_dir)
+        .env("JAIL_CLEANUP", "1");
+    if let Some(rootfs) = rootfs {
+        command.env("JAIL_ROOTFS", rootfs);
+    }
+    command
+        .stdin(std::process::Stdio::null())
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .status()?;
+    Ok(())
 }
 
 fn check_root() -> bool {
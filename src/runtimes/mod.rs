@@ -3,6 +3,7 @@
 //! Currently available runtimes are:
 //! - [Native](native_runtime)
 //! - [WASM](wasm_runtime)
+//! - [Wasmtime](wasmtime_runtime)
 
 use crate::compilers::CompiledCode;
 use std::fmt::Debug;
@@ -11,8 +12,18 @@ use std::fmt::Debug;
 pub mod jailed_runtime;
 #[cfg(feature = "native")]
 pub mod native_runtime;
+#[cfg(all(feature = "native", target_os = "linux"))]
+pub mod seccomp;
+#[cfg(feature = "wasm")]
+pub mod wasm_cache;
+#[cfg(feature = "wasm")]
+pub mod wasm_determinism;
+#[cfg(feature = "wasm")]
+pub mod wasm_profiler;
 #[cfg(feature = "wasm")]
 pub mod wasm_runtime;
+#[cfg(feature = "wasmtime")]
+pub mod wasmtime_runtime;
 
 /// Trait for every code runtime.
 /// Represents a runtime that can be used to run some code.
@@ -23,7 +34,9 @@ pub trait CodeRuntime: Send + Sync + Sized {
     /// This is used to pass additional data from the compiler to the runtime.
     type AdditionalData: Send + Sync + Sized + Debug + Clone + Default;
     /// Error type for the runtime.
-    type Error: Send + Sync + Sized + 'static;
+    type Error: std::error::Error + Send + Sync + Sized + 'static;
+    /// Name of the runtime (e.g. `native`), same as [`RuntimeKind::name`].
+    const NAME: &'static str;
 
     /// Run compiled code. Returns saved output (if any) and exit code.
     fn run(
@@ -42,6 +55,120 @@ pub struct ExecutionResult {
     pub stderr: Option<String>,
     /// Time taken by the code to run.
     pub time_taken: std::time::Duration,
-    /// Exit code of the code.
+    /// Exit code of the code. <br/>
+    /// If the process was killed by a signal, this is `128 + signal` (like in shells).
     pub exit_code: i32,
+    /// Signal that killed the process (if any). <br/>
+    /// This is only set on Unix, e.g. `Some(11)` for segmentation fault.
+    pub signal: Option<i32>,
+    /// Sandbox features that were applied to the code. <br/>
+    /// This can be used to verify the isolation, features are only listed if they were successfully applied.
+    pub sandbox: Vec<SandboxFeature>,
+    /// Number of instructions executed by the code in user space (if counted). <br/>
+    /// This is only set by native runtime with `perf` feature, see `NativeConfig::count_instructions`.
+    pub instructions: Option<u64>,
+}
+
+impl ExecutionResult {
+    /// Returns true if the code exited with exit code 0 and wasn't killed by a signal. <br/>
+    /// For more detailed classification see [`ExecutionResult::outcome`].
+    pub fn success(&self) -> bool {
+        self.outcome() == ExecutionOutcome::Success
+    }
+
+    /// Returns outcome of the execution, computed from exit code and signal.
+    pub fn outcome(&self) -> ExecutionOutcome {
+        if let Some(signal) = self.signal {
+            // Process exceeded its CPU time limit.
+            #[cfg(unix)]
+            if signal == libc::SIGXCPU {
+                return ExecutionOutcome::Timeout;
+            }
+
+            return ExecutionOutcome::Signaled(signal);
+        }
+
+        match self.exit_code {
+            0 => ExecutionOutcome::Success,
+            code => ExecutionOutcome::NonZeroExit(code),
+        }
+    }
+}
+
+/// Outcome of the execution.
+/// This is a match-friendly summary of [`ExecutionResult`], see [`ExecutionResult::outcome`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExecutionOutcome {
+    /// Code exited with exit code 0.
+    Success,
+    /// Code exited with non-zero exit code.
+    NonZeroExit(i32),
+    /// Code was killed by a signal.
+    Signaled(i32),
+    /// Code exceeded its time limit.
+    Timeout,
+    /// Code exceeded its memory limit. <br/>
+    /// This is reserved and currently never returned by [`ExecutionResult::outcome`]:
+    /// processes hitting an address space limit just fail to allocate and exit (or crash) on their own,
+    /// so they are reported as [`NonZeroExit`](Self::NonZeroExit) or [`Signaled`](Self::Signaled),
+    /// and wasm runtimes return an error when the code traps because memory can't grow.
+    MemoryExceeded,
+}
+
+/// Protection applied to the running code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum SandboxFeature {
+    /// Code runs inside wasm virtual machine, with access only to mounted directories.
+    Wasm,
+    /// Code runs inside a chroot jail.
+    Chroot,
+    /// Kernel resource limits (`setrlimit`) are set.
+    ResourceLimits,
+    /// Code runs in a new network namespace, without network access.
+    NetworkNamespace,
+    /// Syscalls made by the code are filtered by seccomp.
+    Seccomp,
+}
+
+/// Kind of runtime.
+/// This describes runtimes without knowing their types at compile time, see [`CompilerInfo`](crate::compilers::CompilerInfo).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum RuntimeKind {
+    /// [Native](native_runtime) runtime.
+    Native,
+    /// [Jailed](jailed_runtime) runtime.
+    Jailed,
+    /// [WASM](wasm_runtime) runtime.
+    Wasm,
+    /// [Wasmtime](wasmtime_runtime) runtime.
+    Wasmtime,
+}
+
+impl RuntimeKind {
+    /// Returns name of the runtime (e.g. `native`).
+    pub fn name(&self) -> &'static str {
+        match self {
+            RuntimeKind::Native => "native",
+            RuntimeKind::Jailed => "jailed",
+            RuntimeKind::Wasm => "wasm",
+            RuntimeKind::Wasmtime => "wasmtime",
+        }
+    }
+}
+
+/// Returns exit code and signal (if any) for given exit status.
+#[cfg(feature = "native")]
+pub(crate) fn exit_status(status: std::process::ExitStatus) -> (i32, Option<i32>) {
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::ExitStatusExt;
+
+        if let Some(signal) = status.signal() {
+            return (128 + signal, Some(signal));
+        }
+    }
+
+    (status.code().unwrap_or(0), None)
 }
@@ -1,8 +1,25 @@
-use std::{io::Write, process::Stdio};
+use std::{
+    io::{PipeReader, PipeWriter, Read},
+    path::PathBuf,
+    process::{Command, Stdio},
+};
 
-use crate::common::runtime::InputData;
+#[cfg(unix)]
+use std::{os::fd::OwnedFd, sync::Arc};
 
-use super::CodeRuntime;
+use tempfile::{NamedTempFile, TempDir};
+
+use crate::{
+    common::{
+        compiler::{spawn_with_retries, DEFAULT_SPAWN_RETRIES},
+        runtime::InputData,
+    },
+    compilers::CompiledCode,
+};
+
+#[cfg(target_os = "linux")]
+use super::seccomp::SeccompProfile;
+use super::{exit_status, CodeRuntime, ExecutionResult, SandboxFeature};
 
 /// Native runtime.
 /// This runtime runs the code natively on the server.
@@ -15,13 +32,138 @@ pub struct NativeRuntime;
 pub struct NativeConfig {
     /// File containing stdin to be used by the code.
     pub stdin: InputData,
+
+    /// Working directory of the code, which relative paths used by the code are resolved against. <br/>
+    /// Default: None (temporary directory of the compiled code, see [`CompiledCode::work_dir`],
+    /// or working directory of this process if the code has none)
+    pub working_dir: Option<PathBuf>,
+
+    /// Disables network access for the code. <br/>
+    /// On Linux the code is started in a new network namespace, so it has no connectivity.
+    /// On other systems this does nothing (and prints a warning). <br/>
+    /// Default: false
+    pub disable_network: bool,
+
+    /// File descriptor to which stdout should be written directly (e.g. write end of OS pipe). <br/>
+    /// If set, stdout is not captured and returned result has no stdout. <br/>
+    /// Default: None
+    #[cfg(unix)]
+    pub stdout_fd: Option<Arc<OwnedFd>>,
+
+    /// Writes stderr of the code to the same pipe as stdout, so the streams are interleaved
+    /// in the order they were written. <br/>
+    /// If set, `stdout` of returned result contains both streams and `stderr` is `None`. <br/>
+    /// Default: false
+    pub merge_stderr: bool,
+
+    /// Resource limits enforced by the kernel (on Unix). <br/>
+    /// Default: no limits
+    pub limits: ResourceLimits,
+
+    /// Counts instructions executed by the code in user space by running it under `perf stat`. <br/>
+    /// Unlike time taken, the count doesn't depend on the machine or its load,
+    /// so it's better suited for comparing solutions (like gas in wasm runtime).
+    /// Requires `perf` with access to hardware counters, result has no count if they are not available. <br/>
+    /// Default: false
+    #[cfg(feature = "perf")]
+    pub count_instructions: bool,
+
+    /// Number of times spawning the code is retried when it fails with transient error
+    /// (e.g. `Text file busy` right after the executable was written). <br/>
+    /// Default: 3
+    pub spawn_retries: u32,
+
+    /// CPU cores the code is allowed to run on (e.g. `vec![2]` pins it to the third core). <br/>
+    /// Pinning reduces timing variance caused by the scheduler migrating the process between cores.
+    /// On Linux this is set using `sched_setaffinity`, on other systems this does nothing (and prints a warning). <br/>
+    /// Default: None (code can run on any core)
+    pub cpu_affinity: Option<Vec<usize>>,
+
+    /// Seccomp filter of syscalls the code can make, see [`SeccompProfile`]. <br/>
+    /// This isolates the code without root privileges, so it works in unprivileged containers
+    /// where the jailed runtime doesn't. It can't be used together with instruction counting,
+    /// as `perf` has to execute the code. <br/>
+    /// Default: None (no filter)
+    #[cfg(target_os = "linux")]
+    pub seccomp_profile: Option<SeccompProfile>,
 }
 
 impl Default for NativeConfig {
     fn default() -> Self {
         Self {
             stdin: InputData::Ignore,
+            working_dir: None,
+            disable_network: false,
+            #[cfg(unix)]
+            stdout_fd: None,
+            merge_stderr: false,
+            limits: ResourceLimits::default(),
+            #[cfg(feature = "perf")]
+            count_instructions: false,
+            spawn_retries: DEFAULT_SPAWN_RETRIES,
+            cpu_affinity: None,
+            #[cfg(target_os = "linux")]
+            seccomp_profile: None,
+        }
+    }
+}
+
+/// Resource limits for native code.
+/// These are set using `setrlimit` in the child process, right before the code is executed.
+/// Only limits that are set (not `None`) are applied. <br/>
+/// On systems other than Unix these do nothing (and print a warning).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ResourceLimits {
+    /// Maximum CPU time in seconds (`RLIMIT_CPU`). <br/>
+    /// Process is killed with `SIGXCPU` when it exceeds this limit
+    /// (or with `SIGKILL` one second later if it handles the signal).
+    pub cpu_time: Option<u64>,
+
+    /// Maximum size of a file that can be written by the code in bytes (`RLIMIT_FSIZE`). <br/>
+    /// Process is killed with `SIGXFSZ` when it tries to write more.
+    pub file_size: Option<u64>,
+
+    /// Maximum size of virtual memory in bytes (`RLIMIT_AS`). <br/>
+    /// Allocations above this limit fail.
+    pub address_space: Option<u64>,
+
+    /// Maximum number of processes (`RLIMIT_NPROC`). <br/>
+    /// Note that this is counted per user, not per process tree,
+    /// so processes of the same user that are already running count as well.
+    pub processes: Option<u64>,
+}
+
+impl ResourceLimits {
+    /// Applies the limits to current process.
+    #[cfg(unix)]
+    fn apply(&self) -> std::io::Result<()> {
+        let limits = [
+            (libc::RLIMIT_CPU, self.cpu_time),
+            (libc::RLIMIT_FSIZE, self.file_size),
+            (libc::RLIMIT_AS, self.address_space),
+            (libc::RLIMIT_NPROC, self.processes),
+        ];
+
+        for (resource, limit) in limits {
+            if let Some(limit) = limit {
+                // Reaching hard CPU limit sends SIGKILL instead of SIGXCPU,
+                // so leave one second between the limits.
+                let hard_limit = match resource {
+                    libc::RLIMIT_CPU => limit.saturating_add(1),
+                    _ => limit,
+                };
+                let rlimit = libc::rlimit {
+                    rlim_cur: limit as libc::rlim_t,
+                    rlim_max: hard_limit as libc::rlim_t,
+                };
+                // Safety: rlimit is a valid pointer.
+                if unsafe { libc::setrlimit(resource, &rlimit) } != 0 {
+                    return Err(std::io::Error::last_os_error());
+                }
+            }
         }
+
+        Ok(())
     }
 }
 
@@ -32,6 +174,22 @@ pub struct NativeAdditionalData {
     /// Program that should be used to run the code. <br/>
     /// Default is None, which means that the executable will be treated as a program.
     pub program: Option<String>,
+
+    /// Arguments passed to the program before the executable (e.g. interpreter options). <br/>
+    /// These are ignored if `program` is not set.
+    pub program_args: Vec<String>,
+}
+
+/// Command created by [`NativeRuntime::command`] along with resources it uses.
+struct PreparedCommand {
+    /// Command running the code.
+    command: Command,
+    /// Temporary directory with the executable if it was held in memory, must outlive the process.
+    executable_dir: Option<TempDir>,
+    /// Read end of the pipe shared by stdout and stderr, if stderr is merged.
+    merged_output: Option<PipeReader>,
+    /// File to which `perf stat` writes the number of executed instructions, if they are counted.
+    perf_output: Option<NamedTempFile>,
 }
 
 /// Runtime for native code.
@@ -42,66 +200,60 @@ impl CodeRuntime for NativeRuntime {
     type AdditionalData = NativeAdditionalData;
     /// Error type for the runtime.
     type Error = std::io::Error;
+    /// Name of the runtime.
+    const NAME: &'static str = "native";
 
     /// Runs the code natively on the server.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "exers::run", skip_all, fields(runtime = "native"))
+    )]
     fn run(
         &self,
         code: &crate::compilers::CompiledCode<Self>,
         config: Self::Config,
     ) -> Result<super::ExecutionResult, Self::Error> {
-        // Create new process.
-        let mut process = match &code.additional_data.program {
-            Some(program) => {
-                let mut cmd = std::process::Command::new(program);
-                cmd.arg(code.executable.as_ref().unwrap());
-                cmd
-            }
-            None => std::process::Command::new(code.executable.as_ref().unwrap()),
-        };
-
-        // Set stdin.
-        match config.stdin {
-            InputData::Ignore => {
-                process.stdin(std::process::Stdio::null());
-            }
-            _ => {
-                process.stdin(Stdio::piped());
-            }
-        };
-
-        // Set stdout.
-        process.stdout(Stdio::piped());
-        // Set stderr.
-        process.stderr(Stdio::piped());
-
         // Spawn the process.
-        let mut process = process.spawn()?;
+        // Temporary executable (if any) must outlive the process.
+        let PreparedCommand {
+            mut command,
+            executable_dir: _executable_dir,
+            merged_output,
+            perf_output,
+        } = Self::command(code, &config)?;
+        let mut process = spawn_with_retries(&mut command, config.spawn_retries)?;
+        // Command holds write end of merged output pipe, which would never be closed otherwise.
+        drop(command);
+        let sandbox = Self::sandbox_features(&config);
 
         // Start timer.
         let start_time = std::time::Instant::now();
 
-        // Write to stdin.
-        match config.stdin {
-            InputData::Ignore => {}
-            InputData::String(data) => {
-                process.stdin.as_mut().unwrap().write_all(data.as_bytes())?;
-            }
-            InputData::File(path) => {
-                let mut file = std::fs::File::open(path)?;
-                std::io::copy(&mut file, process.stdin.as_mut().unwrap())?;
-            }
-        };
+        // Write to stdin in the background.
+        let stdin_writer = config.stdin.write_to(process.stdin.take());
+
+        // Read merged output until the process closes it.
+        let mut merged_stdout = Vec::new();
+        if let Some(mut merged_output) = merged_output {
+            merged_output.read_to_end(&mut merged_stdout)?;
+        }
 
         // Wait for the process to finish.
         let output = process.wait_with_output()?;
+        stdin_writer.join().unwrap()?;
 
         // Stop timer.
         let time_taken = start_time.elapsed();
+        let instructions = perf_output.as_ref().map(read_instructions).transpose()?;
 
         // Get stdout.
-        let stdout = match output.stdout.len() {
+        let stdout = match config.merge_stderr {
+            true => merged_stdout,
+            false => output.stdout,
+        };
+        let stdout = match stdout.len() {
             0 => None,
-            _ => Some(String::from_utf8(output.stdout).unwrap()),
+            _ => Some(String::from_utf8(stdout).unwrap()),
         };
 
         // Get stderr.
@@ -110,19 +262,371 @@ impl CodeRuntime for NativeRuntime {
             _ => Some(String::from_utf8(output.stderr).unwrap()),
         };
 
+        // Get exit code.
+        let (exit_code, signal) = exit_status(output.status);
+        trace_event!(exit_code, ?signal, ?time_taken, "code finished");
+
         // Return the result.
         Ok(super::ExecutionResult {
             stdout,
             stderr,
             time_taken,
-            exit_code: output.status.code().unwrap_or(0),
+            exit_code,
+            signal,
+            sandbox,
+            instructions: instructions.flatten(),
+        })
+    }
+}
+
+impl NativeRuntime {
+    /// Runs the code natively, passing stdout to the callback as soon as it is produced. <br/>
+    /// This is useful for long-running programs or programs with huge output,
+    /// as stdout is not buffered in memory. Returned result has no stdout.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "exers::run", skip_all, fields(runtime = "native"))
+    )]
+    pub fn run_streaming(
+        &self,
+        code: &CompiledCode<Self>,
+        config: NativeConfig,
+        mut on_stdout: impl FnMut(&[u8]),
+    ) -> Result<ExecutionResult, std::io::Error> {
+        // Spawn the process.
+        // Temporary executable (if any) must outlive the process.
+        let PreparedCommand {
+            mut command,
+            executable_dir: _executable_dir,
+            merged_output,
+            perf_output,
+        } = Self::command(code, &config)?;
+        let mut process = spawn_with_retries(&mut command, config.spawn_retries)?;
+        // Command holds write end of merged output pipe, which would never be closed otherwise.
+        drop(command);
+        let sandbox = Self::sandbox_features(&config);
+
+        // Start timer.
+        let start_time = std::time::Instant::now();
+
+        // Read stderr in the background, so the process doesn't block on it.
+        // There is no stderr pipe if it is merged into stdout.
+        let stderr_pipe = process.stderr.take();
+        let stderr_reader = std::thread::spawn(move || -> std::io::Result<Vec<u8>> {
+            let mut stderr = Vec::new();
+            if let Some(mut stderr_pipe) = stderr_pipe {
+                stderr_pipe.read_to_end(&mut stderr)?;
+            }
+            Ok(stderr)
+        });
+
+        // Write to stdin in the background.
+        let stdin_writer = config.stdin.write_to(process.stdin.take());
+
+        // Pass stdout chunks to the callback until the process closes it.
+        // There is nothing to read if stdout is redirected to a file descriptor.
+        let stdout_pipe: Option<Box<dyn Read>> = match merged_output {
+            Some(merged_output) => Some(Box::new(merged_output)),
+            None => process
+                .stdout
+                .take()
+                .map(|pipe| Box::new(pipe) as Box<dyn Read>),
+        };
+        if let Some(mut stdout_pipe) = stdout_pipe {
+            let mut buffer = [0; 8192];
+            loop {
+                match stdout_pipe.read(&mut buffer)? {
+                    0 => break,
+                    read => on_stdout(&buffer[..read]),
+                }
+            }
+        }
+
+        // Wait for the process to finish.
+        let status = process.wait()?;
+        let stderr = stderr_reader.join().unwrap()?;
+        stdin_writer.join().unwrap()?;
+
+        // Stop timer.
+        let time_taken = start_time.elapsed();
+        let instructions = perf_output.as_ref().map(read_instructions).transpose()?;
+
+        // Get stderr.
+        let stderr = match stderr.len() {
+            0 => None,
+            _ => Some(String::from_utf8(stderr).unwrap()),
+        };
+
+        // Get exit code.
+        let (exit_code, signal) = exit_status(status);
+        trace_event!(exit_code, ?signal, ?time_taken, "code finished");
+
+        // Return the result.
+        Ok(ExecutionResult {
+            stdout: None,
+            stderr,
+            time_taken,
+            exit_code,
+            signal,
+            sandbox,
+            instructions: instructions.flatten(),
+        })
+    }
+
+    /// Creates command running the code with pipes set up according to the config. <br/>
+    /// If the executable is held in memory, it is written to returned temporary directory.
+    fn command(
+        code: &CompiledCode<Self>,
+        config: &NativeConfig,
+    ) -> std::io::Result<PreparedCommand> {
+        // Create new process.
+        let (executable, executable_dir) = code.materialize_executable()?;
+        // Executable path can't be relative to the working directory of the code.
+        let executable = std::path::absolute(executable)?;
+        let mut process = match &code.additional_data.program {
+            Some(program) => {
+                let mut cmd = Command::new(program);
+                cmd.args(&code.additional_data.program_args);
+                cmd.arg(executable);
+                cmd
+            }
+            None => Command::new(executable),
+        };
+
+        // Count instructions by running the code under `perf stat`.
+        #[cfg(feature = "perf")]
+        let perf_output = match config.count_instructions {
+            true => {
+                let (perf, output) = Self::count_instructions(&process)?;
+                process = perf;
+                Some(output)
+            }
+            false => None,
+        };
+        #[cfg(not(feature = "perf"))]
+        let perf_output = None;
+
+        // Set working directory.
+        if let Some(working_dir) = config.working_dir.clone().or_else(|| code.work_dir()) {
+            process.current_dir(working_dir);
+        }
+
+        // Set stdin.
+        process.stdin(config.stdin.stdio()?);
+
+        // Set stdout, merged output needs its own pipe as stderr is written to it as well.
+        let (stdout_writer, merged_output) = match Self::stdout_writer(config)? {
+            Some(writer) => (Some(writer), None),
+            None if config.merge_stderr => {
+                let (reader, writer) = std::io::pipe()?;
+                (Some(writer), Some(reader))
+            }
+            None => (None, None),
+        };
+        match stdout_writer {
+            Some(writer) => {
+                if config.merge_stderr {
+                    process.stderr(writer.try_clone()?);
+                }
+                process.stdout(writer);
+            }
+            None => {
+                process.stdout(Stdio::piped());
+            }
+        }
+        // Set stderr.
+        if !config.merge_stderr {
+            process.stderr(Stdio::piped());
+        }
+
+        // Isolate network.
+        if config.disable_network {
+            Self::disable_network(&mut process);
+        }
+
+        // Set resource limits.
+        if config.limits != ResourceLimits::default() {
+            Self::set_limits(&mut process, config.limits);
+        }
+
+        // Pin to CPU cores.
+        if let Some(cpus) = &config.cpu_affinity {
+            Self::set_cpu_affinity(&mut process, cpus);
+        }
+
+        // Filter syscalls, this executes the code so it must be the last hook.
+        #[cfg(target_os = "linux")]
+        if let Some(profile) = &config.seccomp_profile {
+            #[cfg(feature = "perf")]
+            if config.count_instructions {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    "seccomp profile can't be used with instruction counting",
+                ));
+            }
+            profile.apply(&mut process)?;
+        }
+
+        Ok(PreparedCommand {
+            command: process,
+            executable_dir,
+            merged_output,
+            perf_output,
         })
     }
+
+    /// Wraps the command in `perf stat`, which writes number of executed instructions to returned file. <br/>
+    /// Only user space instructions are counted, so the count doesn't include work done by the kernel.
+    #[cfg(feature = "perf")]
+    fn count_instructions(command: &Command) -> std::io::Result<(Command, NamedTempFile)> {
+        let output = NamedTempFile::new()?;
+
+        let mut perf = Command::new("perf");
+        perf.args(["stat", "-x", ",", "-e", "instructions:u", "-o"]);
+        perf.arg(output.path());
+        perf.arg("--");
+        perf.arg(command.get_program());
+        perf.args(command.get_args());
+
+        Ok((perf, output))
+    }
+
+    /// Returns writer for file descriptor to which stdout should be written, if set.
+    #[cfg(unix)]
+    fn stdout_writer(config: &NativeConfig) -> std::io::Result<Option<PipeWriter>> {
+        config
+            .stdout_fd
+            .as_ref()
+            .map(|fd| fd.try_clone().map(PipeWriter::from))
+            .transpose()
+    }
+
+    /// Stdout can't be redirected to a file descriptor on this platform.
+    #[cfg(not(unix))]
+    fn stdout_writer(_config: &NativeConfig) -> std::io::Result<Option<PipeWriter>> {
+        Ok(None)
+    }
+
+    /// Returns sandbox features applied by the command for given config. <br/>
+    /// These are applied before the code is executed, so they are active if the process was spawned.
+    #[cfg_attr(not(unix), allow(unused_variables))]
+    fn sandbox_features(config: &NativeConfig) -> Vec<SandboxFeature> {
+        let mut features = Vec::new();
+
+        #[cfg(target_os = "linux")]
+        if config.disable_network {
+            features.push(SandboxFeature::NetworkNamespace);
+        }
+
+        #[cfg(target_os = "linux")]
+        if config.seccomp_profile.is_some() {
+            features.push(SandboxFeature::Seccomp);
+        }
+
+        #[cfg(unix)]
+        if config.limits != ResourceLimits::default() {
+            features.push(SandboxFeature::ResourceLimits);
+        }
+
+        features
+    }
+
+    /// Makes the command start in a new network namespace.
+    #[cfg(target_os = "linux")]
+    fn disable_network(command: &mut Command) {
+        use std::os::unix::process::CommandExt;
+
+        // Safety: only async-signal-safe functions are called between fork and exec.
+        unsafe {
+            command.pre_exec(|| {
+                // Creating network namespace requires CAP_SYS_ADMIN,
+                // unprivileged users have to create user namespace as well.
+                if libc::unshare(libc::CLONE_NEWNET) != 0
+                    && libc::unshare(libc::CLONE_NEWUSER | libc::CLONE_NEWNET) != 0
+                {
+                    return Err(std::io::Error::last_os_error());
+                }
+                Ok(())
+            });
+        }
+    }
+
+    /// Makes the command apply resource limits before executing the code.
+    #[cfg(unix)]
+    fn set_limits(command: &mut Command, limits: ResourceLimits) {
+        use std::os::unix::process::CommandExt;
+
+        // Safety: only async-signal-safe functions are called between fork and exec.
+        unsafe {
+            command.pre_exec(move || limits.apply());
+        }
+    }
+
+    /// Makes the command run only on given CPU cores.
+    #[cfg(target_os = "linux")]
+    fn set_cpu_affinity(command: &mut Command, cpus: &[usize]) {
+        use std::os::unix::process::CommandExt;
+
+        // Build the set before forking, so the hook doesn't allocate.
+        // Safety: cpu_set_t is plain bitmask, zeroed one is an empty set.
+        let mut set: libc::cpu_set_t = unsafe { std::mem::zeroed() };
+        for &cpu in cpus {
+            // Safety: CPU_SET ignores cores above CPU_SETSIZE.
+            unsafe { libc::CPU_SET(cpu, &mut set) };
+        }
+
+        // Safety: only async-signal-safe functions are called between fork and exec.
+        unsafe {
+            command.pre_exec(move || {
+                if libc::sched_setaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &set) != 0 {
+                    return Err(std::io::Error::last_os_error());
+                }
+                Ok(())
+            });
+        }
+    }
+
+    /// CPU affinity is not supported on this platform.
+    #[cfg(not(target_os = "linux"))]
+    fn set_cpu_affinity(_command: &mut Command, _cpus: &[usize]) {
+        eprintln!("warning: CPU affinity is only supported on Linux, code can run on any core");
+    }
+
+    /// Resource limits are not supported on this platform.
+    #[cfg(not(unix))]
+    fn set_limits(_command: &mut Command, _limits: ResourceLimits) {
+        eprintln!("warning: resource limits are only supported on Unix, code runs without limits");
+    }
+
+    /// Network isolation is not supported on this platform.
+    #[cfg(not(target_os = "linux"))]
+    fn disable_network(_command: &mut Command) {
+        eprintln!(
+            "warning: network isolation is only supported on Linux, network is still enabled"
+        );
+    }
+}
+
+/// Reads number of instructions from `perf stat` output in CSV format. <br/>
+/// Returns `None` if the counter was not supported (e.g. in virtual machines).
+fn read_instructions(output: &NamedTempFile) -> std::io::Result<Option<u64>> {
+    let output = std::fs::read_to_string(output.path())?;
+
+    // Counter line looks like `1234,,instructions:u,...`, comments start with `#`.
+    Ok(output
+        .lines()
+        .filter(|line| !line.starts_with('#'))
+        .find(|line| line.contains("instructions"))
+        .and_then(|line| line.split(',').next())
+        .and_then(|count| count.parse().ok()))
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::compilers::{rust_compiler::RustCompiler, Compiler};
+    use crate::{
+        compilers::{rust_compiler::RustCompiler, Compiler, Executable},
+        runtimes::ExecutionOutcome,
+    };
 
     use super::*;
 
@@ -142,5 +646,646 @@ mod tests {
             .unwrap();
 
         assert_eq!(result.stdout, Some("Hello, world!\n".to_owned()));
+        assert_eq!(result.outcome(), ExecutionOutcome::Success);
+        assert!(result.success());
+    }
+
+    #[test]
+    fn test_native_runtime_streaming() {
+        let code = r#"
+        fn main() {
+            for i in 0..3 {
+                println!("Line {}", i);
+            }
+        }
+        "#;
+
+        let compiled_code = RustCompiler
+            .compile(&mut code.as_bytes(), Default::default())
+            .unwrap();
+
+        let mut stdout = Vec::new();
+        let result = NativeRuntime
+            .run_streaming(&compiled_code, Default::default(), |chunk| {
+                stdout.extend_from_slice(chunk)
+            })
+            .unwrap();
+
+        assert_eq!(stdout, b"Line 0\nLine 1\nLine 2\n");
+        assert_eq!(result.stdout, None);
+        assert_eq!(result.exit_code, 0);
+    }
+
+    #[test]
+    fn test_native_runtime_merge_stderr() {
+        let code = r#"
+        use std::io::Write;
+
+        fn main() {
+            for i in 0..3 {
+                writeln!(std::io::stdout(), "out {}", i).unwrap();
+                std::io::stdout().flush().unwrap();
+                writeln!(std::io::stderr(), "err {}", i).unwrap();
+            }
+        }
+        "#;
+
+        let compiled_code = RustCompiler
+            .compile(&mut code.as_bytes(), Default::default())
+            .unwrap();
+        let config = NativeConfig {
+            merge_stderr: true,
+            ..Default::default()
+        };
+        let expected = "out 0\nerr 0\nout 1\nerr 1\nout 2\nerr 2\n";
+
+        let result = NativeRuntime.run(&compiled_code, config.clone()).unwrap();
+        assert_eq!(result.stdout, Some(expected.to_owned()));
+        assert_eq!(result.stderr, None);
+
+        let mut stdout = Vec::new();
+        let result = NativeRuntime
+            .run_streaming(&compiled_code, config, |chunk| {
+                stdout.extend_from_slice(chunk)
+            })
+            .unwrap();
+        assert_eq!(stdout, expected.as_bytes());
+        assert_eq!(result.stderr, None);
+    }
+
+    #[test]
+    #[cfg(feature = "perf")]
+    fn test_native_runtime_count_instructions() {
+        let code = r#"
+        fn main() {
+            let n: u64 = std::env::args().count() as u64 * 1_000_000;
+            let mut sum = 0u64;
+            for i in 0..n {
+                sum = std::hint::black_box(sum.wrapping_add(i));
+            }
+            println!("{}", sum);
+        }
+        "#;
+
+        let compiled_code = RustCompiler
+            .compile(&mut code.as_bytes(), Default::default())
+            .unwrap();
+        let config = NativeConfig {
+            count_instructions: true,
+            ..Default::default()
+        };
+
+        let first = NativeRuntime.run(&compiled_code, config.clone()).unwrap();
+        let second = NativeRuntime.run(&compiled_code, config).unwrap();
+        assert_eq!(first.stdout, Some("499999500000\n".to_owned()));
+
+        // Count is (almost) the same for the same work, regardless of timing.
+        let (first, second) = (first.instructions.unwrap(), second.instructions.unwrap());
+        assert!(first > 1_000_000);
+        assert!(first.abs_diff(second) < first / 100);
+    }
+
+    #[test]
+    fn test_read_instructions() {
+        let mut output = NamedTempFile::new().unwrap();
+        std::io::Write::write_all(
+            &mut output,
+            b"# started on Mon Jan  1 00:00:00 2024\n\n1234567,,instructions:u,1000,100.00,,\n",
+        )
+        .unwrap();
+        assert_eq!(read_instructions(&output).unwrap(), Some(1234567));
+
+        let mut output = NamedTempFile::new().unwrap();
+        std::io::Write::write_all(&mut output, b"<not supported>,,instructions:u,0,100.00,,\n")
+            .unwrap();
+        assert_eq!(read_instructions(&output).unwrap(), None);
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn test_native_runtime_disable_network() {
+        let code = r#"
+        use std::net::{TcpListener, TcpStream};
+
+        fn main() {
+            let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+            let connected = TcpStream::connect(listener.local_addr().unwrap()).is_ok();
+            println!("{}", connected);
+        }
+        "#;
+
+        let compiled_code = RustCompiler
+            .compile(&mut code.as_bytes(), Default::default())
+            .unwrap();
+
+        let result = NativeRuntime
+            .run(&compiled_code, Default::default())
+            .unwrap();
+        assert_eq!(result.stdout, Some("true\n".to_owned()));
+
+        let result = NativeRuntime
+            .run(
+                &compiled_code,
+                NativeConfig {
+                    disable_network: true,
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        assert_eq!(result.stdout, Some("false\n".to_owned()));
+        assert_eq!(result.sandbox, vec![SandboxFeature::NetworkNamespace]);
+    }
+
+    #[test]
+    #[cfg(all(
+        target_os = "linux",
+        any(target_arch = "x86_64", target_arch = "aarch64")
+    ))]
+    fn test_native_runtime_seccomp() {
+        let code = r#"
+        fn main() {
+            let thread = std::thread::spawn(|| 21 * 2).join().unwrap();
+            let network = std::net::TcpListener::bind("127.0.0.1:0").is_ok();
+            let process = std::process::Command::new("true")
+                .status()
+                .map_or(false, |status| status.success());
+            println!("{} {} {}", thread, network, process);
+        }
+        "#;
+
+        let compiled_code = RustCompiler
+            .compile(&mut code.as_bytes(), Default::default())
+            .unwrap();
+        let run = |profile| {
+            NativeRuntime
+                .run(
+                    &compiled_code,
+                    NativeConfig {
+                        seccomp_profile: Some(profile),
+                        ..Default::default()
+                    },
+                )
+                .unwrap()
+        };
+
+        let result = run(SeccompProfile::compute_only());
+        assert_eq!(result.stdout, Some("42 false false\n".to_owned()));
+        assert_eq!(result.sandbox, vec![SandboxFeature::Seccomp]);
+
+        let result = run(SeccompProfile::Deny(vec![libc::SYS_socket]));
+        assert_eq!(result.stdout, Some("42 false true\n".to_owned()));
+
+        // Child process is killed when it tries to execute other program.
+        let result = run(SeccompProfile::deny_dangerous());
+        assert_eq!(result.stdout, Some("42 false false\n".to_owned()));
+    }
+
+    #[test]
+    #[cfg(all(
+        target_os = "linux",
+        any(target_arch = "x86_64", target_arch = "aarch64")
+    ))]
+    fn test_native_runtime_seccomp_execve() {
+        let code = r#"
+        use std::os::unix::process::CommandExt;
+
+        fn main() {
+            // Path is copied, so it's not at the address the code was executed from.
+            let shell = String::from("/bin/sh");
+            let error = std::process::Command::new(shell.clone())
+                .args(["-c", "echo escaped"])
+                .exec();
+            println!("{}", error);
+        }
+        "#;
+
+        let compiled_code = RustCompiler
+            .compile(&mut code.as_bytes(), Default::default())
+            .unwrap();
+        for profile in [
+            SeccompProfile::compute_only(),
+            SeccompProfile::deny_dangerous(),
+        ] {
+            let result = NativeRuntime
+                .run(
+                    &compiled_code,
+                    NativeConfig {
+                        seccomp_profile: Some(profile),
+                        ..Default::default()
+                    },
+                )
+                .unwrap();
+            assert_eq!(result.stdout, None);
+            assert_eq!(result.signal, Some(libc::SIGKILL));
+        }
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn test_native_runtime_cpu_affinity() {
+        let code = r#"
+        fn main() {
+            let status = std::fs::read_to_string("/proc/self/status").unwrap();
+            let cpus = status.lines().find(|line| line.starts_with("Cpus_allowed_list")).unwrap();
+            println!("{}", cpus.split_whitespace().last().unwrap());
+        }
+        "#;
+
+        let compiled_code = RustCompiler
+            .compile(&mut code.as_bytes(), Default::default())
+            .unwrap();
+
+        let result = NativeRuntime
+            .run(
+                &compiled_code,
+                NativeConfig {
+                    cpu_affinity: Some(vec![0]),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        assert_eq!(result.stdout, Some("0\n".to_owned()));
+    }
+
+    #[test]
+    fn test_native_runtime_working_dir() {
+        let code = r#"
+        fn main() {
+            std::fs::write("output.txt", "Hello").unwrap();
+            println!("{}", std::env::current_dir().unwrap().display());
+        }
+        "#;
+
+        let compiled_code = RustCompiler
+            .compile(&mut code.as_bytes(), Default::default())
+            .unwrap();
+        let work_dir = compiled_code.work_dir().unwrap();
+
+        // Code runs in its temporary directory by default.
+        let result = NativeRuntime
+            .run(&compiled_code, Default::default())
+            .unwrap();
+        assert_eq!(result.stdout, Some(format!("{}\n", work_dir.display())));
+        assert!(work_dir.join("output.txt").exists());
+
+        let working_dir = tempfile::tempdir().unwrap();
+        let result = NativeRuntime
+            .run(
+                &compiled_code,
+                NativeConfig {
+                    working_dir: Some(working_dir.path().to_path_buf()),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        assert_eq!(
+            result.stdout,
+            Some(format!("{}\n", working_dir.path().display()))
+        );
+        assert_eq!(
+            std::fs::read_to_string(working_dir.path().join("output.txt")).unwrap(),
+            "Hello"
+        );
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_native_runtime_pipe_chaining() {
+        use std::os::fd::FromRawFd;
+
+        let generator = r#"
+        fn main() {
+            for i in 0..100000 {
+                println!("{}", i);
+            }
+        }
+        "#;
+        let solver = r#"
+        use std::io::BufRead;
+
+        fn main() {
+            let sum: u64 = std::io::stdin().lock().lines().map(|l| l.unwrap().parse::<u64>().unwrap()).sum();
+            println!("{}", sum);
+        }
+        "#;
+
+        let generator: CompiledCode<NativeRuntime> = RustCompiler
+            .compile(&mut generator.as_bytes(), Default::default())
+            .unwrap();
+        let solver: CompiledCode<NativeRuntime> = RustCompiler
+            .compile(&mut solver.as_bytes(), Default::default())
+            .unwrap();
+
+        // Create OS pipe.
+        let mut fds = [0; 2];
+        assert_eq!(unsafe { libc::pipe2(fds.as_mut_ptr(), libc::O_CLOEXEC) }, 0);
+        let (read_end, write_end) =
+            unsafe { (OwnedFd::from_raw_fd(fds[0]), OwnedFd::from_raw_fd(fds[1])) };
+
+        // Run both programs at once, so the pipe never fills up.
+        let solver_config = NativeConfig {
+            stdin: InputData::Fd(Arc::new(read_end)),
+            ..Default::default()
+        };
+        let solver_thread = std::thread::spawn(move || NativeRuntime.run(&solver, solver_config));
+
+        let generator_result = NativeRuntime
+            .run(
+                &generator,
+                NativeConfig {
+                    stdout_fd: Some(Arc::new(write_end)),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        let solver_result = solver_thread.join().unwrap().unwrap();
+
+        assert_eq!(generator_result.stdout, None);
+        assert_eq!(solver_result.stdout, Some("4999950000\n".to_owned()));
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_native_runtime_cpu_time_limit() {
+        let code = r#"
+        fn main() {
+            let mut i: u64 = 0;
+            loop {
+                i = std::hint::black_box(i.wrapping_add(1));
+            }
+        }
+        "#;
+
+        let compiled_code = RustCompiler
+            .compile(&mut code.as_bytes(), Default::default())
+            .unwrap();
+        let result = NativeRuntime
+            .run(
+                &compiled_code,
+                NativeConfig {
+                    limits: ResourceLimits {
+                        cpu_time: Some(1),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+
+        assert!(result.time_taken < std::time::Duration::from_secs(10));
+        assert_eq!(result.signal, Some(libc::SIGXCPU));
+        assert_eq!(result.outcome(), ExecutionOutcome::Timeout);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_native_runtime_file_size_limit() {
+        let code = r#"
+        fn main() {
+            let path = std::env::temp_dir().join(format!("exers-fsize-{}", std::process::id()));
+            let result = std::fs::write(&path, vec![0u8; 1024 * 1024]);
+            let _ = std::fs::remove_file(&path);
+            println!("{}", result.is_ok());
+        }
+        "#;
+
+        let compiled_code = RustCompiler
+            .compile(&mut code.as_bytes(), Default::default())
+            .unwrap();
+        let result = NativeRuntime
+            .run(
+                &compiled_code,
+                NativeConfig {
+                    limits: ResourceLimits {
+                        file_size: Some(1024),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+
+        // Process is killed by SIGXFSZ before it prints anything.
+        assert_eq!(result.stdout, None);
+        assert_eq!(result.signal, Some(libc::SIGXFSZ));
+        assert_eq!(result.exit_code, 128 + libc::SIGXFSZ);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_native_runtime_signal() {
+        let code = r#"
+        fn main() {
+            std::process::abort();
+        }
+        "#;
+
+        let compiled_code = RustCompiler
+            .compile(&mut code.as_bytes(), Default::default())
+            .unwrap();
+        let result = NativeRuntime
+            .run(&compiled_code, Default::default())
+            .unwrap();
+
+        assert_eq!(result.signal, Some(libc::SIGABRT));
+        assert_ne!(result.exit_code, 0);
+        assert_eq!(result.outcome(), ExecutionOutcome::Signaled(libc::SIGABRT));
+        assert!(!result.success());
+    }
+
+    #[test]
+    fn test_native_runtime_no_executable() {
+        let compiled_code = CompiledCode::<NativeRuntime> {
+            executable: None,
+            temp_dir_handle: Default::default(),
+            additional_data: Default::default(),
+            warnings: String::new(),
+            command_line: None,
+            runtime_marker: std::marker::PhantomData,
+        };
+        let result = NativeRuntime.run(&compiled_code, Default::default());
+
+        assert_eq!(result.unwrap_err().kind(), std::io::ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn test_native_runtime_from_memory() {
+        let code = r#"
+        fn main() {
+            println!("Hello, world!");
+        }
+        "#;
+
+        let compiled_code: CompiledCode<NativeRuntime> = RustCompiler
+            .compile(&mut code.as_bytes(), Default::default())
+            .unwrap();
+        let bytes = std::fs::read(compiled_code.executable_path().unwrap()).unwrap();
+
+        let in_memory_code = CompiledCode::<NativeRuntime> {
+            executable: Some(Executable::Bytes(bytes)),
+            temp_dir_handle: Default::default(),
+            additional_data: Default::default(),
+            warnings: String::new(),
+            command_line: None,
+            runtime_marker: std::marker::PhantomData,
+        };
+        let result = NativeRuntime
+            .run(&in_memory_code, Default::default())
+            .unwrap();
+
+        assert_eq!(result.stdout, Some("Hello, world!\n".to_owned()));
+    }
+
+    #[test]
+    fn test_native_runtime_large_file_input() {
+        // Echo input line by line, so output fills the pipe before all input is consumed.
+        let code = r#"
+        use std::io::{BufRead, Write};
+        fn main() {
+            let mut stdout = std::io::stdout();
+            for line in std::io::stdin().lock().lines() {
+                writeln!(stdout, "{}", line.unwrap()).unwrap();
+            }
+        }
+        "#;
+
+        let input_dir = tempfile::tempdir().unwrap();
+        let input_path = input_dir.path().join("input.txt");
+        let input = "0123456789abcdef\n".repeat(64 * 1024);
+        std::fs::write(&input_path, &input).unwrap();
+
+        let compiled_code = RustCompiler
+            .compile(&mut code.as_bytes(), Default::default())
+            .unwrap();
+        let result = NativeRuntime
+            .run(
+                &compiled_code,
+                NativeConfig {
+                    stdin: InputData::File(input_path),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+
+        assert_eq!(result.stdout, Some(input));
+    }
+
+    #[test]
+    fn test_native_runtime_string_input_newline() {
+        let code = r#"
+        use std::io::Read;
+        fn main() {
+            let mut input = String::new();
+            std::io::stdin().read_to_string(&mut input).unwrap();
+            print!("{:?}", input);
+        }
+        "#;
+
+        let compiled_code = RustCompiler
+            .compile(&mut code.as_bytes(), Default::default())
+            .unwrap();
+        let run = |stdin| {
+            NativeRuntime
+                .run(
+                    &compiled_code,
+                    NativeConfig {
+                        stdin,
+                        ..Default::default()
+                    },
+                )
+                .unwrap()
+                .stdout
+        };
+
+        // String is passed as is, line ends with exactly one newline.
+        assert_eq!(
+            run(InputData::String("a".to_owned())),
+            Some(r#""a""#.to_owned())
+        );
+        assert_eq!(run(InputData::line("a")), Some(r#""a\n""#.to_owned()));
+        assert_eq!(run(InputData::line("a\n")), Some(r#""a\n""#.to_owned()));
+    }
+
+    #[test]
+    fn test_native_runtime_large_string_input() {
+        // Both input and output are much larger than pipe buffers.
+        let code = r#"
+        fn main() {
+            std::io::copy(&mut std::io::stdin(), &mut std::io::stdout()).unwrap();
+        }
+        "#;
+
+        let input = "x".repeat(1024 * 1024);
+
+        let compiled_code = RustCompiler
+            .compile(&mut code.as_bytes(), Default::default())
+            .unwrap();
+        let result = NativeRuntime
+            .run(
+                &compiled_code,
+                NativeConfig {
+                    stdin: InputData::String(input.clone()),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+
+        assert_eq!(result.stdout, Some(input));
+    }
+
+    #[test]
+    fn test_native_runtime_reader_input() {
+        let code = r#"
+        fn main() {
+            std::io::copy(&mut std::io::stdin(), &mut std::io::stdout()).unwrap();
+        }
+        "#;
+
+        // Input is produced by chained readers, without being collected first.
+        let reader = "Hello, ".as_bytes().chain("world!".as_bytes());
+
+        let compiled_code = RustCompiler
+            .compile(&mut code.as_bytes(), Default::default())
+            .unwrap();
+        let result = NativeRuntime
+            .run(
+                &compiled_code,
+                NativeConfig {
+                    stdin: InputData::reader(reader),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+
+        assert_eq!(result.stdout, Some("Hello, world!".to_owned()));
+    }
+
+    #[test]
+    fn test_native_runtime_from_bytes() {
+        let code = r#"
+        fn main() {
+            println!("Hello, world!");
+        }
+        "#;
+
+        // Compile and read the executable, like on a build machine.
+        let compiled_code: CompiledCode<NativeRuntime> = RustCompiler
+            .compile(&mut code.as_bytes(), Default::default())
+            .unwrap();
+        let bytes = compiled_code.read_executable().unwrap();
+        let additional_data = compiled_code.additional_data.clone();
+        let hash = compiled_code.executable_bytes_hash().unwrap();
+        drop(compiled_code);
+
+        // Reconstitute it, like on a worker machine.
+        let compiled_code =
+            CompiledCode::<NativeRuntime>::from_bytes(&bytes, additional_data).unwrap();
+        assert_eq!(compiled_code.executable_bytes_hash().unwrap(), hash);
+        assert_eq!(hash.len(), 64);
+        let result = NativeRuntime
+            .run(&compiled_code, Default::default())
+            .unwrap();
+
+        assert_eq!(result.stdout, Some("Hello, world!\n".to_owned()));
     }
 }
@@ -0,0 +1,665 @@
+//! Seccomp syscall filters for native runtime (Linux only). <br/>
+//! Filters are plain BPF programs, so no additional libraries are required.
+//! See [`SeccompProfile`] for available profiles.
+
+use std::{
+    collections::BTreeMap,
+    ffi::{CString, OsStr},
+    os::unix::{
+        ffi::OsStrExt,
+        io::{AsRawFd, FromRawFd, OwnedFd, RawFd},
+        net::UnixStream,
+        process::CommandExt,
+    },
+    process::Command,
+};
+
+use libc::{c_long, sock_filter};
+
+/// Syscall filter installed right before the code is executed. <br/>
+/// Filter is inherited by all threads and child processes of the code and can't be removed.
+/// The code itself is always allowed to be executed, but it can't execute other programs
+/// if `execve` is blocked (trying to do so kills the process).
+/// This is enforced by a supervisor thread that allows only the first `execve` (requires Linux 5.5).
+/// This includes wrapper scripts that execute the real program (e.g. pyenv shims),
+/// so interpreters should be given by path to the actual binary.
+/// Syscalls made for different architecture (e.g. 32-bit syscalls on x86_64) kill the process as well. <br/>
+/// Supported only on x86_64 and aarch64.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SeccompProfile {
+    /// Only listed syscalls are allowed, other syscalls fail with `EPERM`. <br/>
+    /// `clone` is allowed only for creating threads and `clone3` fails with `ENOSYS`
+    /// (libc falls back to `clone`), as arguments of `clone3` can't be inspected.
+    AllowOnly(Vec<c_long>),
+    /// Listed syscalls fail with `EPERM`, other syscalls are allowed.
+    Deny(Vec<c_long>),
+}
+
+impl SeccompProfile {
+    /// Profile for code that only computes, reading stdin and writing stdout. <br/>
+    /// Code can read files, allocate memory and create threads,
+    /// but it can't write to files it didn't get, use network, create processes or execute programs.
+    pub fn compute_only() -> Self {
+        let mut syscalls = vec![
+            // Reading and writing to files.
+            libc::SYS_read,
+            libc::SYS_write,
+            libc::SYS_readv,
+            libc::SYS_writev,
+            libc::SYS_pread64,
+            libc::SYS_pwrite64,
+            libc::SYS_lseek,
+            libc::SYS_close,
+            libc::SYS_fcntl,
+            libc::SYS_ioctl,
+            libc::SYS_dup,
+            libc::SYS_dup3,
+            libc::SYS_pipe2,
+            libc::SYS_ppoll,
+            libc::SYS_pselect6,
+            // Inspecting filesystem (needed for loading libraries and interpreter modules).
+            libc::SYS_openat,
+            libc::SYS_fstat,
+            libc::SYS_newfstatat,
+            libc::SYS_statx,
+            libc::SYS_readlinkat,
+            libc::SYS_faccessat,
+            libc::SYS_faccessat2,
+            libc::SYS_getdents64,
+            libc::SYS_getcwd,
+            // Memory.
+            libc::SYS_brk,
+            libc::SYS_mmap,
+            libc::SYS_munmap,
+            libc::SYS_mremap,
+            libc::SYS_mprotect,
+            libc::SYS_madvise,
+            // Threads and signals.
+            libc::SYS_clone,
+            libc::SYS_futex,
+            libc::SYS_set_robust_list,
+            libc::SYS_get_robust_list,
+            libc::SYS_set_tid_address,
+            libc::SYS_rseq,
+            libc::SYS_membarrier,
+            libc::SYS_sched_yield,
+            libc::SYS_sched_getaffinity,
+            libc::SYS_rt_sigaction,
+            libc::SYS_rt_sigprocmask,
+            libc::SYS_rt_sigreturn,
+            libc::SYS_sigaltstack,
+            libc::SYS_tgkill,
+            libc::SYS_exit,
+            libc::SYS_exit_group,
+            // Time, randomness and process information.
+            libc::SYS_clock_gettime,
+            libc::SYS_clock_getres,
+            libc::SYS_clock_nanosleep,
+            libc::SYS_nanosleep,
+            libc::SYS_gettimeofday,
+            libc::SYS_getrandom,
+            libc::SYS_getpid,
+            libc::SYS_gettid,
+            libc::SYS_getppid,
+            libc::SYS_getuid,
+            libc::SYS_geteuid,
+            libc::SYS_getgid,
+            libc::SYS_getegid,
+            libc::SYS_uname,
+            libc::SYS_sysinfo,
+            libc::SYS_prlimit64,
+            libc::SYS_getrusage,
+        ];
+
+        // Legacy syscalls, which are still used by libc on x86_64.
+        #[cfg(target_arch = "x86_64")]
+        syscalls.extend([
+            libc::SYS_open,
+            libc::SYS_stat,
+            libc::SYS_lstat,
+            libc::SYS_access,
+            libc::SYS_readlink,
+            libc::SYS_poll,
+            libc::SYS_dup2,
+            libc::SYS_pipe,
+            libc::SYS_arch_prctl,
+        ]);
+
+        Self::AllowOnly(syscalls)
+    }
+
+    /// Profile blocking only syscalls that are dangerous in most cases:
+    /// executing programs, network, debugging other processes, sending signals to them
+    /// and changing the system (mounts, kernel modules, ...).
+    pub fn deny_dangerous() -> Self {
+        Self::Deny(vec![
+            libc::SYS_execve,
+            libc::SYS_execveat,
+            libc::SYS_socket,
+            libc::SYS_socketpair,
+            libc::SYS_ptrace,
+            libc::SYS_process_vm_readv,
+            libc::SYS_process_vm_writev,
+            libc::SYS_kill,
+            libc::SYS_mount,
+            libc::SYS_umount2,
+            libc::SYS_pivot_root,
+            libc::SYS_chroot,
+            libc::SYS_unshare,
+            libc::SYS_setns,
+            libc::SYS_init_module,
+            libc::SYS_finit_module,
+            libc::SYS_delete_module,
+            libc::SYS_kexec_load,
+            libc::SYS_reboot,
+            libc::SYS_bpf,
+            libc::SYS_perf_event_open,
+        ])
+    }
+
+    /// Makes the command install the filter and execute the code. <br/>
+    /// This must be the last `pre_exec` hook of the command, as it executes the program itself,
+    /// so the supervisor can allow this `execve` and block all others. <br/>
+    /// Environment of the code is the inherited one with changes made by [`Command::env`]
+    /// and [`Command::env_remove`], [`Command::env_clear`] can't be detected and is not supported.
+    pub(crate) fn apply(&self, command: &mut Command) -> std::io::Result<()> {
+        let Some(arch) = AUDIT_ARCH else {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::Unsupported,
+                "seccomp profiles are only supported on x86_64 and aarch64",
+            ));
+        };
+
+        // Prepare everything before forking, so the hook doesn't allocate.
+        let program = which::which(command.get_program())
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::NotFound, e))?;
+        let program = c_string(program.as_os_str())?;
+        let args = std::iter::once(command.get_program())
+            .chain(command.get_args())
+            .map(c_string)
+            .collect::<std::io::Result<Vec<_>>>()?;
+        let argv = Argv::new(&args);
+        let env = environment(command)?;
+        let envp = Argv::new(&env);
+        let mut filter = self.filter(arch)?;
+        let mut execve_filter = execve_filter()?;
+
+        // Supervisor gets listener of the filter from the hook, so it can allow the `execve` made by the hook.
+        let socket = match self.blocks_execve() {
+            true => {
+                let (supervisor, socket) = UnixStream::pair()?;
+                std::thread::spawn(move || supervise(supervisor));
+                Some(socket)
+            }
+            false => None,
+        };
+
+        // Safety: only async-signal-safe functions are called between fork and exec.
+        unsafe {
+            command.pre_exec(move || {
+                // Keep arguments and environment alive as long as the hook.
+                let _ = (&args, &env);
+
+                // Filter can be installed without privileges only if no new privileges can be gained.
+                if libc::prctl(libc::PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0) != 0 {
+                    return Err(std::io::Error::last_os_error());
+                }
+
+                // Filter passing `execve` to the supervisor is installed first,
+                // as sending its listener may not be allowed by the profile.
+                if let Some(socket) = &socket {
+                    let listener =
+                        install(&mut execve_filter, libc::SECCOMP_FILTER_FLAG_NEW_LISTENER)?;
+                    let sent = send_fd(socket.as_raw_fd(), listener);
+                    libc::close(listener);
+                    sent?;
+                }
+                install(&mut filter, 0)?;
+
+                // Environment set on the command is applied by std only after this hook, so it's passed here.
+                libc::execve(program.as_ptr(), argv.as_ptr(), envp.as_ptr());
+                Err(std::io::Error::last_os_error())
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Returns whether the profile blocks `execve`.
+    fn blocks_execve(&self) -> bool {
+        match self {
+            Self::AllowOnly(syscalls) => !syscalls.contains(&libc::SYS_execve),
+            Self::Deny(syscalls) => syscalls.contains(&libc::SYS_execve),
+        }
+    }
+
+    /// Builds BPF program of the filter. <br/>
+    /// If `execve` is blocked, it's allowed here and left to [`execve_filter`], which passes it to the supervisor.
+    fn filter(&self, arch: u32) -> std::io::Result<Vec<sock_filter>> {
+        let (syscalls, allow_listed) = match self {
+            Self::AllowOnly(syscalls) => (syscalls, true),
+            Self::Deny(syscalls) => (syscalls, false),
+        };
+        let (listed, other) = match allow_listed {
+            true => (Action::Allow, Action::Errno(libc::EPERM)),
+            false => (Action::Errno(libc::EPERM), Action::Allow),
+        };
+
+        let mut filter = FilterBuilder::default();
+
+        // Check architecture and reject x32 syscalls, which have separate numbers.
+        filter.load(OFFSET_ARCH);
+        filter.jump_unless(arch, Target::Action(Action::Kill));
+        filter.load(OFFSET_NR);
+        filter.jump_if_at_least(X32_SYSCALL_BIT, Target::Action(Action::Kill));
+
+        // Program of the code can always be executed.
+        if self.blocks_execve() {
+            filter.jump_if(libc::SYS_execve as u32, Target::Action(Action::Allow));
+        }
+        // Threads can be created even if processes can't.
+        if allow_listed {
+            filter.jump_if(libc::SYS_clone as u32, Target::Clone);
+            filter.jump_if(
+                libc::SYS_clone3 as u32,
+                Target::Action(Action::Errno(libc::ENOSYS)),
+            );
+        }
+        for &syscall in syscalls {
+            filter.jump_if(syscall as u32, Target::Action(listed));
+        }
+        filter.ret(other);
+
+        // Check that `clone` creates a thread.
+        if allow_listed {
+            filter.label(Target::Clone);
+            filter.load(OFFSET_ARG0);
+            filter.jump_if_set(libc::CLONE_THREAD as u32, Target::Action(Action::Allow));
+            filter.ret(Action::Errno(libc::EPERM));
+        }
+
+        filter.build()
+    }
+}
+
+/// Audit architecture of syscalls made by the code.
+#[cfg(target_arch = "x86_64")]
+const AUDIT_ARCH: Option<u32> = Some(0xc000_003e);
+#[cfg(target_arch = "aarch64")]
+const AUDIT_ARCH: Option<u32> = Some(0xc000_00b7);
+#[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+const AUDIT_ARCH: Option<u32> = None;
+
+/// Syscall numbers of x32 ABI have this bit set.
+const X32_SYSCALL_BIT: u32 = 0x4000_0000;
+
+// Offsets of fields in `seccomp_data`, arguments are little-endian on supported architectures.
+const OFFSET_NR: u32 = 0;
+const OFFSET_ARCH: u32 = 4;
+const OFFSET_ARG0: u32 = 16;
+
+/// Arguments or environment for `execve` prepared before forking.
+struct Argv(Vec<*const libc::c_char>);
+
+// Safety: pointers point to strings owned by the same hook and are only read.
+unsafe impl Send for Argv {}
+unsafe impl Sync for Argv {}
+
+impl Argv {
+    fn new(strings: &[CString]) -> Self {
+        Self(
+            strings
+                .iter()
+                .map(|string| string.as_ptr())
+                .chain(std::iter::once(std::ptr::null()))
+                .collect(),
+        )
+    }
+
+    fn as_ptr(&self) -> *const *const libc::c_char {
+        self.0.as_ptr()
+    }
+}
+
+fn c_string(value: &OsStr) -> std::io::Result<CString> {
+    CString::new(value.as_bytes()).map_err(std::io::Error::from)
+}
+
+/// Returns environment of the command as `KEY=value` strings.
+fn environment(command: &Command) -> std::io::Result<Vec<CString>> {
+    let mut env = std::env::vars_os().collect::<BTreeMap<_, _>>();
+    for (key, value) in command.get_envs() {
+        match value {
+            Some(value) => env.insert(key.to_owned(), value.to_owned()),
+            None => env.remove(key),
+        };
+    }
+
+    env.into_iter()
+        .map(|(key, value)| {
+            let mut variable = key;
+            variable.push("=");
+            variable.push(value);
+            c_string(&variable)
+        })
+        .collect()
+}
+
+/// Installs the filter for the current thread, returns listener if it was requested by `flags`. <br/>
+/// This is called between fork and exec, so it doesn't allocate.
+unsafe fn install(filter: &mut [sock_filter], flags: libc::c_ulong) -> std::io::Result<RawFd> {
+    let program = libc::sock_fprog {
+        len: filter.len() as u16,
+        filter: filter.as_mut_ptr(),
+    };
+    match libc::syscall(
+        libc::SYS_seccomp,
+        libc::SECCOMP_SET_MODE_FILTER,
+        flags,
+        &program,
+    ) {
+        -1 => Err(std::io::Error::last_os_error()),
+        listener => Ok(listener as RawFd),
+    }
+}
+
+/// Builds BPF program passing every `execve` to the supervisor, other syscalls are left to the profile.
+fn execve_filter() -> std::io::Result<Vec<sock_filter>> {
+    let mut filter = FilterBuilder::default();
+    filter.load(OFFSET_NR);
+    filter.jump_if(libc::SYS_execve as u32, Target::Action(Action::Notify));
+    filter.ret(Action::Allow);
+    filter.build()
+}
+
+/// Receives listeners of filters installed by the hook (one for each spawn attempt)
+/// and supervises each of them on its own thread, until the socket is closed.
+fn supervise(socket: UnixStream) {
+    while let Some(listener) = receive_fd(socket.as_raw_fd()) {
+        std::thread::spawn(move || supervise_listener(listener));
+    }
+}
+
+/// Allows the first `execve`, which is made by the hook, and kills processes making any other one. <br/>
+/// Returns once all processes using the filter exit.
+fn supervise_listener(listener: OwnedFd) {
+    let mut executed = false;
+    loop {
+        // Wait for notification, listener hangs up once all processes using the filter exit.
+        let mut poll = libc::pollfd {
+            fd: listener.as_raw_fd(),
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        // Safety: `poll` is a valid pollfd.
+        if unsafe { libc::poll(&mut poll, 1, -1) } < 0 {
+            match std::io::Error::last_os_error().kind() {
+                std::io::ErrorKind::Interrupted => continue,
+                _ => return,
+            }
+        }
+        if poll.revents & libc::POLLIN == 0 {
+            return;
+        }
+
+        // Safety: notification is zeroed as required by the kernel and has the expected size.
+        let mut notification: libc::seccomp_notif = unsafe { std::mem::zeroed() };
+        if unsafe {
+            libc::ioctl(
+                listener.as_raw_fd(),
+                libc::SECCOMP_IOCTL_NOTIF_RECV,
+                &mut notification,
+            )
+        } != 0
+        {
+            // Process may have been killed before notification was received.
+            continue;
+        }
+
+        let mut response = libc::seccomp_notif_resp {
+            id: notification.id,
+            val: 0,
+            error: -libc::EPERM,
+            flags: 0,
+        };
+        match executed {
+            false => {
+                executed = true;
+                response.error = 0;
+                response.flags = libc::SECCOMP_USER_NOTIF_FLAG_CONTINUE as u32;
+            }
+            // Safety: killing the process doesn't affect this process.
+            true => unsafe {
+                libc::kill(notification.pid as libc::pid_t, libc::SIGKILL);
+            },
+        }
+        // Safety: response is a valid seccomp_notif_resp.
+        unsafe {
+            libc::ioctl(
+                listener.as_raw_fd(),
+                libc::SECCOMP_IOCTL_NOTIF_SEND,
+                &mut response,
+            )
+        };
+    }
+}
+
+/// Sends file descriptor over unix socket. <br/>
+/// This is called between fork and exec, so it doesn't allocate.
+unsafe fn send_fd(socket: RawFd, fd: RawFd) -> std::io::Result<()> {
+    let mut data = [0u8];
+    let mut iov = libc::iovec {
+        iov_base: data.as_mut_ptr().cast(),
+        iov_len: data.len(),
+    };
+    // Aligned buffer large enough for one file descriptor.
+    let mut control = [0u64; 4];
+    let mut message: libc::msghdr = std::mem::zeroed();
+    message.msg_iov = &mut iov;
+    message.msg_iovlen = 1;
+    message.msg_control = control.as_mut_ptr().cast();
+    message.msg_controllen = libc::CMSG_SPACE(std::mem::size_of::<RawFd>() as u32) as _;
+
+    let header = libc::CMSG_FIRSTHDR(&message);
+    (*header).cmsg_level = libc::SOL_SOCKET;
+    (*header).cmsg_type = libc::SCM_RIGHTS;
+    (*header).cmsg_len = libc::CMSG_LEN(std::mem::size_of::<RawFd>() as u32) as _;
+    std::ptr::write_unaligned(libc::CMSG_DATA(header).cast(), fd);
+
+    match libc::sendmsg(socket, &message, 0) {
+        -1 => Err(std::io::Error::last_os_error()),
+        _ => Ok(()),
+    }
+}
+
+/// Receives file descriptor sent by [`send_fd`], returns `None` once the socket is closed.
+fn receive_fd(socket: RawFd) -> Option<OwnedFd> {
+    let mut data = [0u8];
+    let mut iov = libc::iovec {
+        iov_base: data.as_mut_ptr().cast(),
+        iov_len: data.len(),
+    };
+    let mut control = [0u64; 4];
+    // Safety: message points to buffers living until the end of this function.
+    unsafe {
+        let mut message: libc::msghdr = std::mem::zeroed();
+        message.msg_iov = &mut iov;
+        message.msg_iovlen = 1;
+        message.msg_control = control.as_mut_ptr().cast();
+        message.msg_controllen = std::mem::size_of_val(&control) as _;
+
+        loop {
+            match libc::recvmsg(socket, &mut message, libc::MSG_CMSG_CLOEXEC) {
+                -1 if std::io::Error::last_os_error().kind() == std::io::ErrorKind::Interrupted => {
+                }
+                -1 | 0 => return None,
+                _ => break,
+            }
+        }
+
+        let header = libc::CMSG_FIRSTHDR(&message);
+        if header.is_null()
+            || (*header).cmsg_level != libc::SOL_SOCKET
+            || (*header).cmsg_type != libc::SCM_RIGHTS
+        {
+            return None;
+        }
+        let fd = std::ptr::read_unaligned(libc::CMSG_DATA(header).cast::<RawFd>());
+        Some(OwnedFd::from_raw_fd(fd))
+    }
+}
+
+/// Result of the filter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Action {
+    Allow,
+    Errno(i32),
+    Kill,
+    Notify,
+}
+
+impl Action {
+    fn value(self) -> u32 {
+        match self {
+            Action::Allow => libc::SECCOMP_RET_ALLOW,
+            Action::Errno(errno) => libc::SECCOMP_RET_ERRNO | errno as u32,
+            Action::Kill => libc::SECCOMP_RET_KILL_PROCESS,
+            Action::Notify => libc::SECCOMP_RET_USER_NOTIF,
+        }
+    }
+}
+
+/// Target of a jump in the filter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Target {
+    /// Instruction returning given action, added at the end of the filter.
+    Action(Action),
+    /// Check of `clone` arguments.
+    Clone,
+}
+
+/// Targets of a jump if the condition is true and false, `None` continues with next instruction.
+type Jump = (Option<Target>, Option<Target>);
+
+/// Builder of BPF programs with jumps to labels, as BPF jumps only by relative offsets.
+#[derive(Default)]
+struct FilterBuilder {
+    instructions: Vec<(sock_filter, Jump)>,
+    labels: Vec<(Target, usize)>,
+}
+
+impl FilterBuilder {
+    fn load(&mut self, offset: u32) {
+        self.push(
+            libc::BPF_LD | libc::BPF_W | libc::BPF_ABS,
+            offset,
+            (None, None),
+        );
+    }
+
+    fn jump_if(&mut self, value: u32, target: Target) {
+        self.push_jump(libc::BPF_JEQ, value, Some(target), None);
+    }
+
+    fn jump_unless(&mut self, value: u32, target: Target) {
+        self.push_jump(libc::BPF_JEQ, value, None, Some(target));
+    }
+
+    fn jump_if_at_least(&mut self, value: u32, target: Target) {
+        self.push_jump(libc::BPF_JGE, value, Some(target), None);
+    }
+
+    fn jump_if_set(&mut self, bits: u32, target: Target) {
+        self.push_jump(libc::BPF_JSET, bits, Some(target), None);
+    }
+
+    fn ret(&mut self, action: Action) {
+        self.push(libc::BPF_RET | libc::BPF_K, action.value(), (None, None));
+    }
+
+    fn label(&mut self, target: Target) {
+        self.labels.push((target, self.instructions.len()));
+    }
+
+    fn push_jump(&mut self, op: u32, value: u32, jt: Option<Target>, jf: Option<Target>) {
+        self.push(libc::BPF_JMP | op | libc::BPF_K, value, (jt, jf));
+    }
+
+    fn push(&mut self, code: u32, k: u32, jump: Jump) {
+        let instruction = sock_filter {
+            code: code as u16,
+            jt: 0,
+            jf: 0,
+            k,
+        };
+        self.instructions.push((instruction, jump));
+    }
+
+    /// Appends returns for jumped to actions and resolves jump offsets. <br/>
+    /// Fails if a jump is too long for BPF (profile has too many syscalls).
+    fn build(mut self) -> std::io::Result<Vec<sock_filter>> {
+        let mut actions = Vec::new();
+        for (_, (jt, jf)) in &self.instructions {
+            for target in [jt, jf].into_iter().flatten() {
+                if let &Target::Action(action) = target {
+                    if !actions.contains(&action) {
+                        actions.push(action);
+                    }
+                }
+            }
+        }
+        for action in actions {
+            self.label(Target::Action(action));
+            self.ret(action);
+        }
+
+        let offset = |index: usize, target: Option<Target>| match target {
+            Some(target) => {
+                let (_, position) = self.labels.iter().find(|(label, _)| *label == target)?;
+                u8::try_from(position - index - 1).ok()
+            }
+            None => Some(0),
+        };
+        self.instructions
+            .iter()
+            .enumerate()
+            .map(|(index, (instruction, (jt, jf)))| {
+                Some(sock_filter {
+                    jt: offset(index, *jt)?,
+                    jf: offset(index, *jf)?,
+                    ..*instruction
+                })
+            })
+            .collect::<Option<_>>()
+            .ok_or_else(|| {
+                std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    "seccomp profile has too many syscalls",
+                )
+            })
+    }
+}
+
+#[cfg(test)]
+#[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_seccomp_environment() {
+        std::env::set_var("EXERS_REMOVED", "inherited");
+        let mut command = Command::new("sh");
+        command
+            .args(["-c", "echo $EXERS_SET-$EXERS_REMOVED"])
+            .env("EXERS_SET", "set")
+            .env_remove("EXERS_REMOVED")
+            .stdout(std::process::Stdio::piped());
+        SeccompProfile::Deny(vec![libc::SYS_socket])
+            .apply(&mut command)
+            .unwrap();
+
+        // Changes made on the command are applied, even though the hook executes the program.
+        let output = command.output().unwrap();
+        assert_eq!(String::from_utf8(output.stdout).unwrap(), "set-\n");
+    }
+}
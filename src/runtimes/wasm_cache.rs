@@ -0,0 +1,102 @@
+//! Cache of compiled wasm modules, so the same module is compiled only once.
+
+use std::{
+    collections::HashMap,
+    fs, io,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
+
+use wasmer::{Module, Store};
+
+use super::wasm_runtime::WasmRuntimeError;
+
+/// Cache of compiled wasm modules, see [`WasmConfig::module_cache`](super::wasm_runtime::WasmConfig::module_cache). <br/>
+/// Modules are stored serialized (in memory, and optionally in a directory),
+/// keyed by the executable contents and configuration affecting compilation.
+/// This is a shared handle, clones use the same cache.
+#[derive(Debug, Clone, Default)]
+pub struct WasmModuleCache {
+    /// Serialized modules by key.
+    modules: Arc<Mutex<HashMap<String, Arc<[u8]>>>>,
+    /// Directory where serialized modules are persisted (if any).
+    directory: Option<PathBuf>,
+}
+
+impl WasmModuleCache {
+    /// Creates new in-memory cache.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates new cache persisting modules in given directory, so they can be reused by other processes.
+    /// Directory is created if it doesn't exist. <br/>
+    /// Modules loaded from the directory are run as native code, so it must not be writable by untrusted users.
+    pub fn in_directory(path: impl Into<PathBuf>) -> io::Result<Self> {
+        let path = path.into();
+        fs::create_dir_all(&path)?;
+        Ok(Self {
+            modules: Default::default(),
+            directory: Some(path),
+        })
+    }
+
+    /// Returns number of modules held in memory.
+    pub fn len(&self) -> usize {
+        self.modules.lock().unwrap().len()
+    }
+
+    /// Returns true if no modules are held in memory.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns module stored under given key, compiling and storing it if it's not cached.
+    #[allow(clippy::result_large_err)]
+    pub(crate) fn load_or_compile(
+        &self,
+        store: &Store,
+        key: &str,
+        compile: impl FnOnce() -> Result<Module, WasmRuntimeError>,
+    ) -> Result<Module, WasmRuntimeError> {
+        if let Some(bytes) = self.get(key) {
+            // Safety: modules are serialized by this cache (with the same wasmer version),
+            // the directory is trusted as documented in `in_directory`.
+            return Ok(unsafe { Module::deserialize(store, &*bytes)? });
+        }
+
+        let module = compile()?;
+        self.put(key, module.serialize()?.to_vec())?;
+        Ok(module)
+    }
+
+    /// Returns serialized module, looking it up in memory and then in the directory.
+    fn get(&self, key: &str) -> Option<Arc<[u8]>> {
+        if let Some(bytes) = self.modules.lock().unwrap().get(key) {
+            return Some(bytes.clone());
+        }
+
+        let bytes: Arc<[u8]> = fs::read(self.directory.as_ref()?.join(key)).ok()?.into();
+        self.modules
+            .lock()
+            .unwrap()
+            .insert(key.to_string(), bytes.clone());
+        Some(bytes)
+    }
+
+    /// Stores serialized module in memory and in the directory.
+    fn put(&self, key: &str, bytes: Vec<u8>) -> io::Result<()> {
+        if let Some(directory) = &self.directory {
+            // Write into a temporary file first, so other processes never see partial modules.
+            let mut file = tempfile::NamedTempFile::new_in(directory)?;
+            io::Write::write_all(&mut file, &bytes)?;
+            file.persist(directory.join(key))?;
+        }
+
+        self.modules
+            .lock()
+            .unwrap()
+            .insert(key.to_string(), bytes.into());
+        Ok(())
+    }
+}
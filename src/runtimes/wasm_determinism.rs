@@ -0,0 +1,180 @@
+//! Deterministic clock and randomness for wasm runtime. <br/>
+//! wasi imports reading the clock and random data are replaced with functions controlled by the config,
+//! so the code produces the same output on every run.
+//! See [`WasmConfig::clock`](super::wasm_runtime::WasmConfig::clock)
+//! and [`WasmConfig::random_seed`](super::wasm_runtime::WasmConfig::random_seed).
+
+use std::time::Duration;
+
+use wasmer::{
+    ExternType, FunctionEnv, FunctionEnvMut, FunctionType, Imports, Instance, Memory, Module,
+    Store, Type,
+};
+use wasmer_wasix::wasmer_wasix_types::wasi::Errno;
+
+/// Clock seen by the code through wasi `clock_time_get`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WasmClock {
+    /// Clocks of the host. <br/>
+    /// This is the default clock.
+    #[default]
+    Host,
+    /// All clocks always return given time (since unix epoch for realtime clock),
+    /// so the time doesn't pass.
+    Fixed(Duration),
+    /// All clocks start at zero and advance by `step` on every read,
+    /// so time passes, but it's the same on every run.
+    FromZero {
+        /// Time added after every read.
+        step: Duration,
+    },
+}
+
+/// State of the replaced imports.
+struct DeterministicEnv {
+    /// Memory of the instance, which is set once it's created.
+    memory: Option<Memory>,
+    clock: WasmClock,
+    /// Current time of [`WasmClock::FromZero`] clock.
+    elapsed: Duration,
+    /// State of the random generator.
+    random_state: u64,
+}
+
+impl DeterministicEnv {
+    /// Generates next random number using SplitMix64, which is fast and has good statistical quality.
+    /// It's not cryptographically secure, but the seed is known anyway.
+    fn next_random(&mut self) -> u64 {
+        self.random_state = self.random_state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.random_state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Returns current time of the clock in nanoseconds.
+    fn now(&mut self) -> u64 {
+        match self.clock {
+            WasmClock::Host => unreachable!("host clock is not replaced"),
+            WasmClock::Fixed(time) => time.as_nanos() as u64,
+            WasmClock::FromZero { step } => {
+                let now = self.elapsed;
+                self.elapsed += step;
+                now.as_nanos() as u64
+            }
+        }
+    }
+}
+
+/// Replaced wasi imports, which need memory of the instance to be set before the code is run.
+pub(crate) struct DeterministicImports(FunctionEnv<DeterministicEnv>);
+
+impl DeterministicImports {
+    /// Replaces clock and random imports of the module in `imports` according to the config. <br/>
+    /// Only imports with wasm32 signatures are replaced, returns `None` if nothing has to be replaced.
+    pub(crate) fn define(
+        store: &mut Store,
+        module: &Module,
+        imports: &mut Imports,
+        clock: WasmClock,
+        random_seed: Option<u64>,
+    ) -> Option<Self> {
+        if clock == WasmClock::Host && random_seed.is_none() {
+            return None;
+        }
+
+        let env = FunctionEnv::new(
+            store,
+            DeterministicEnv {
+                memory: None,
+                clock,
+                elapsed: Duration::ZERO,
+                random_state: random_seed.unwrap_or_default(),
+            },
+        );
+
+        // Same functions are imported from different namespaces (e.g. `wasi_unstable`).
+        for import in module.imports() {
+            let ExternType::Function(ty) = import.ty() else {
+                continue;
+            };
+            if !import.module().starts_with("wasi") {
+                continue;
+            }
+
+            let function = match import.name() {
+                "clock_time_get"
+                    if clock != WasmClock::Host
+                        && *ty
+                            == FunctionType::new(
+                                [Type::I32, Type::I64, Type::I32],
+                                [Type::I32],
+                            ) =>
+                {
+                    wasmer::Function::new_typed_with_env(store, &env, clock_time_get)
+                }
+                "random_get"
+                    if random_seed.is_some()
+                        && *ty == FunctionType::new([Type::I32, Type::I32], [Type::I32]) =>
+                {
+                    wasmer::Function::new_typed_with_env(store, &env, random_get)
+                }
+                _ => continue,
+            };
+            imports.define(import.module(), import.name(), function);
+        }
+
+        Some(Self(env))
+    }
+
+    /// Gives the functions access to memory of the instance.
+    pub(crate) fn initialize(
+        &self,
+        store: &mut Store,
+        instance: &Instance,
+    ) -> Result<(), wasmer::ExportError> {
+        let memory = instance.exports.get_memory("memory")?.clone();
+        self.0.as_mut(store).memory = Some(memory);
+        Ok(())
+    }
+}
+
+/// Replacement of wasi `clock_time_get(clock_id, precision, *time) -> errno`.
+fn clock_time_get(
+    mut env: FunctionEnvMut<DeterministicEnv>,
+    _clock_id: u32,
+    _precision: u64,
+    time: u32,
+) -> u32 {
+    let (env, store) = env.data_and_store_mut();
+    let now = env.now();
+    write_memory(env, &store, time, &now.to_le_bytes())
+}
+
+/// Replacement of wasi `random_get(*buf, buf_len) -> errno`.
+fn random_get(mut env: FunctionEnvMut<DeterministicEnv>, buf: u32, buf_len: u32) -> u32 {
+    let (env, store) = env.data_and_store_mut();
+    let mut bytes = vec![0; buf_len as usize];
+    for chunk in bytes.chunks_mut(8) {
+        let random = env.next_random().to_le_bytes();
+        chunk.copy_from_slice(&random[..chunk.len()]);
+    }
+    write_memory(env, &store, buf, &bytes)
+}
+
+/// Writes data to memory of the instance, returning wasi errno.
+fn write_memory(
+    env: &DeterministicEnv,
+    store: &impl wasmer::AsStoreRef,
+    offset: u32,
+    data: &[u8],
+) -> u32 {
+    let written = env
+        .memory
+        .as_ref()
+        .is_some_and(|memory| memory.view(store).write(offset as u64, data).is_ok());
+    match written {
+        true => Errno::Success as u32,
+        false => Errno::Fault as u32,
+    }
+}
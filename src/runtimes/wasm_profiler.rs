@@ -0,0 +1,289 @@
+//! Profiling of wasm code, counting executed operators by category.
+
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, Mutex},
+};
+
+use wasmer::{
+    wasmparser::Operator, AsStoreMut, ExportIndex, FunctionMiddleware, GlobalInit, GlobalType,
+    Instance, LocalFunctionIndex, MiddlewareError, MiddlewareReaderState, ModuleMiddleware,
+    Mutability, Type,
+};
+use wasmer_types::{GlobalIndex, ModuleInfo};
+
+/// Category of wasm operators, used for grouping them in [`WasmProfile`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum OperatorCategory {
+    /// Blocks, branches and other control flow (e.g. `br_if`, `select`, `drop`).
+    Control,
+    /// Function calls (direct and indirect).
+    Call,
+    /// Reads and writes of locals and globals.
+    Variable,
+    /// Memory loads, stores and bulk operations (e.g. `memory.copy`).
+    Memory,
+    /// Constants.
+    Constant,
+    /// Arithmetic, comparisons, conversions and every other operator.
+    Numeric,
+}
+
+impl OperatorCategory {
+    /// All categories.
+    pub const ALL: [Self; 6] = [
+        Self::Control,
+        Self::Call,
+        Self::Variable,
+        Self::Memory,
+        Self::Constant,
+        Self::Numeric,
+    ];
+
+    /// Returns category of the operator.
+    pub fn of(operator: &Operator) -> Self {
+        match operator {
+            Operator::Unreachable
+            | Operator::Nop
+            | Operator::Block { .. }
+            | Operator::Loop { .. }
+            | Operator::If { .. }
+            | Operator::Else
+            | Operator::End
+            | Operator::Br { .. }
+            | Operator::BrIf { .. }
+            | Operator::BrTable { .. }
+            | Operator::Return
+            | Operator::Drop
+            | Operator::Select
+            | Operator::TypedSelect { .. } => Self::Control,
+            Operator::Call { .. }
+            | Operator::CallIndirect { .. }
+            | Operator::ReturnCall { .. }
+            | Operator::ReturnCallIndirect { .. } => Self::Call,
+            Operator::LocalGet { .. }
+            | Operator::LocalSet { .. }
+            | Operator::LocalTee { .. }
+            | Operator::GlobalGet { .. }
+            | Operator::GlobalSet { .. } => Self::Variable,
+            Operator::I32Load { .. }
+            | Operator::I64Load { .. }
+            | Operator::F32Load { .. }
+            | Operator::F64Load { .. }
+            | Operator::I32Load8S { .. }
+            | Operator::I32Load8U { .. }
+            | Operator::I32Load16S { .. }
+            | Operator::I32Load16U { .. }
+            | Operator::I64Load8S { .. }
+            | Operator::I64Load8U { .. }
+            | Operator::I64Load16S { .. }
+            | Operator::I64Load16U { .. }
+            | Operator::I64Load32S { .. }
+            | Operator::I64Load32U { .. }
+            | Operator::I32Store { .. }
+            | Operator::I64Store { .. }
+            | Operator::F32Store { .. }
+            | Operator::F64Store { .. }
+            | Operator::I32Store8 { .. }
+            | Operator::I32Store16 { .. }
+            | Operator::I64Store8 { .. }
+            | Operator::I64Store16 { .. }
+            | Operator::I64Store32 { .. }
+            | Operator::MemorySize { .. }
+            | Operator::MemoryGrow { .. }
+            | Operator::MemoryInit { .. }
+            | Operator::MemoryCopy { .. }
+            | Operator::MemoryFill { .. }
+            | Operator::DataDrop { .. } => Self::Memory,
+            Operator::I32Const { .. }
+            | Operator::I64Const { .. }
+            | Operator::F32Const { .. }
+            | Operator::F64Const { .. } => Self::Constant,
+            _ => Self::Numeric,
+        }
+    }
+
+    /// Name of the global counting operators of this category.
+    fn global_name(self) -> String {
+        format!("exers_profile_{:?}", self).to_lowercase()
+    }
+}
+
+/// Number of operators executed by the code, grouped by category. <br/>
+/// Operators are counted per basic block when the block finishes,
+/// so block that trapped (e.g. ran out of gas) is not included.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WasmProfile {
+    /// Number of executed operators by category, categories with no operators are omitted.
+    pub counts: BTreeMap<OperatorCategory, u64>,
+}
+
+impl WasmProfile {
+    /// Returns number of executed operators of given category.
+    pub fn count(&self, category: OperatorCategory) -> u64 {
+        self.counts.get(&category).copied().unwrap_or_default()
+    }
+
+    /// Returns total number of executed operators. <br/>
+    /// With default cost function this is equal to the gas used.
+    pub fn total(&self) -> u64 {
+        self.counts.values().sum()
+    }
+}
+
+/// Collects [`WasmProfile`] of the code, see [`WasmConfig::profiler`](super::wasm_runtime::WasmConfig::profiler). <br/>
+/// This is a shared handle, profile of the last run can be read after the code finishes (or traps).
+#[derive(Debug, Clone, Default)]
+pub struct WasmProfiler(Arc<Mutex<WasmProfile>>);
+
+impl WasmProfiler {
+    /// Creates new profiler.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns profile of the last run.
+    pub fn profile(&self) -> WasmProfile {
+        self.0.lock().unwrap().clone()
+    }
+
+    /// Reads counters from the instance compiled with [`ProfilingMiddleware`].
+    pub(crate) fn record(&self, store: &mut impl AsStoreMut, instance: &Instance) {
+        let counts = OperatorCategory::ALL
+            .into_iter()
+            .filter_map(|category| {
+                let global = instance.exports.get_global(&category.global_name()).ok()?;
+                let count = global.get(store).i64()? as u64;
+                (count > 0).then_some((category, count))
+            })
+            .collect();
+        *self.0.lock().unwrap() = WasmProfile { counts };
+    }
+}
+
+/// Middleware adding a counter global for each operator category and incrementing it
+/// at the end of each basic block (like metering middleware does with gas). <br/>
+/// Operators added by metering middleware (if it's applied before this one) are not counted.
+#[derive(Debug, Default)]
+pub(crate) struct ProfilingMiddleware {
+    /// Indexes of the counters and of the remaining points global of metering.
+    globals: Mutex<Option<ProfilingGlobals>>,
+}
+
+#[derive(Debug, Clone)]
+struct ProfilingGlobals {
+    counters: Vec<GlobalIndex>,
+    metering: Option<GlobalIndex>,
+}
+
+impl ModuleMiddleware for ProfilingMiddleware {
+    fn generate_function_middleware(&self, _: LocalFunctionIndex) -> Box<dyn FunctionMiddleware> {
+        Box::new(FunctionProfiling {
+            globals: self.globals.lock().unwrap().clone().unwrap(),
+            counts: [0; OperatorCategory::ALL.len()],
+            in_metering: false,
+        })
+    }
+
+    fn transform_module_info(&self, module_info: &mut ModuleInfo) {
+        // Remaining points global is read and written only by metering.
+        let metering = match module_info.exports.get("wasmer_metering_remaining_points") {
+            Some(ExportIndex::Global(index)) => Some(*index),
+            _ => None,
+        };
+
+        // Append exported counter for each category.
+        let counters = OperatorCategory::ALL
+            .into_iter()
+            .map(|category| {
+                let index = module_info
+                    .globals
+                    .push(GlobalType::new(Type::I64, Mutability::Var));
+                module_info
+                    .global_initializers
+                    .push(GlobalInit::I64Const(0));
+                module_info
+                    .exports
+                    .insert(category.global_name(), ExportIndex::Global(index));
+                index
+            })
+            .collect();
+
+        *self.globals.lock().unwrap() = Some(ProfilingGlobals { counters, metering });
+    }
+}
+
+/// Function-level part of [`ProfilingMiddleware`].
+#[derive(Debug)]
+struct FunctionProfiling {
+    globals: ProfilingGlobals,
+    /// Operators of the current basic block by category.
+    counts: [u64; OperatorCategory::ALL.len()],
+    /// Whether the operators are part of metering check, which starts by reading
+    /// remaining points and ends by writing them.
+    in_metering: bool,
+}
+
+impl FunctionMiddleware for FunctionProfiling {
+    fn feed<'a>(
+        &mut self,
+        operator: Operator<'a>,
+        state: &mut MiddlewareReaderState<'a>,
+    ) -> Result<(), MiddlewareError> {
+        let metering = self.globals.metering.map(|index| index.as_u32());
+        match operator {
+            Operator::GlobalGet { global_index } if Some(global_index) == metering => {
+                self.in_metering = true;
+            }
+            Operator::GlobalSet { global_index } if Some(global_index) == metering => {
+                self.in_metering = false;
+                state.push_operator(operator);
+                return Ok(());
+            }
+            _ => {}
+        }
+        if self.in_metering {
+            state.push_operator(operator);
+            return Ok(());
+        }
+
+        // Count the operator before finishing the block, so branches are counted as well.
+        let category = OperatorCategory::of(&operator);
+        self.counts[category as usize] += 1;
+
+        // Same block boundaries as in metering middleware.
+        if matches!(
+            operator,
+            Operator::Loop { .. }
+                | Operator::End
+                | Operator::Else
+                | Operator::Br { .. }
+                | Operator::BrTable { .. }
+                | Operator::BrIf { .. }
+                | Operator::Call { .. }
+                | Operator::CallIndirect { .. }
+                | Operator::Return
+        ) {
+            for (count, global) in self.counts.iter_mut().zip(&self.globals.counters) {
+                if *count > 0 {
+                    state.extend(&[
+                        Operator::GlobalGet {
+                            global_index: global.as_u32(),
+                        },
+                        Operator::I64Const {
+                            value: *count as i64,
+                        },
+                        Operator::I64Add,
+                        Operator::GlobalSet {
+                            global_index: global.as_u32(),
+                        },
+                    ]);
+                    *count = 0;
+                }
+            }
+        }
+        state.push_operator(operator);
+
+        Ok(())
+    }
+}
@@ -1,20 +1,35 @@
 use std::{
-    fmt::Debug,
-    fs::File,
+    borrow::Cow,
+    error::Error,
+    fmt::{Debug, Display},
     io::{Read, Write},
     path::PathBuf,
-    sync::Arc,
+    ptr::NonNull,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        mpsc, Arc, Mutex,
+    },
+    time::Duration,
 };
 
-use wasmer::{wasmparser::Operator, BaseTunables, Engine, NativeEngineExt, Pages};
-use wasmer_wasix::virtual_fs::TmpFileSystem;
+use sha2::{Digest, Sha256};
+use wasmer::{wasmparser::Operator, AsStoreMut, BaseTunables, Engine, NativeEngineExt, Pages};
+use wasmer_wasix::virtual_fs::{
+    FileOpener, FileSystem, FsError, Metadata, OpenOptions, OpenOptionsConfig, ReadDir,
+    TmpFileSystem, VirtualFile,
+};
 
 use crate::{
     common::runtime::{InputData, LimitingTunables},
-    compilers::CompiledCode,
+    compilers::{CompiledCode, Executable},
 };
 
-use super::{CodeRuntime, ExecutionResult};
+use super::{
+    wasm_cache::WasmModuleCache,
+    wasm_determinism::{DeterministicImports, WasmClock},
+    wasm_profiler::{OperatorCategory, ProfilingMiddleware, WasmProfiler},
+    CodeRuntime, ExecutionResult, SandboxFeature,
+};
 
 /// Runtime for wasm code.
 /// This uses `wasmer` to run the code.
@@ -36,6 +51,8 @@ pub struct WasmConfig {
 
     /// Custom metering cost function.
     /// This is used to calculate the cost of each instruction.
+    /// See [`WasmConfig::with_cost_function`] for setting it without wrapping,
+    /// and [`WasmConfig::memory_weighted`] or [`WasmConfig::control_flow_weighted`] for prebuilt ones.
     /// Default cost function: `|_| -> u64 { 1 }`
     #[allow(clippy::type_complexity)]
     pub cost_function: Option<Arc<dyn Fn(&Operator) -> u64 + Send + Sync>>,
@@ -46,6 +63,122 @@ pub struct WasmConfig {
     /// Compiler that should be used to compile the code.
     /// Default: `WasmCompiler::Cranelift`
     pub compiler: WasmCompiler,
+
+    /// Host directories that should be mounted in the code. <br/>
+    /// These are mounted in addition to the compiler's preopen dir (see [`WasmAdditionalData`]).
+    /// Default: no mounts
+    pub mounts: Vec<WasmMount>,
+
+    /// Files created in the code's file system before it's started. <br/>
+    /// Useful for code that reads its input from a path instead of stdin.
+    /// Default: no files
+    pub input_files: Vec<WasmInputFile>,
+
+    /// Exported function that should be called to run the code. <br/>
+    /// Default: `None` (`_start`, the entrypoint of WASI command modules) <br/>
+    /// For reactor modules, `_initialize` is called before the entrypoint if it's exported.
+    pub entrypoint: Option<String>,
+
+    /// Arguments passed to the entrypoint. <br/>
+    /// Values returned by the entrypoint can be obtained using [`WasmRuntime::call`].
+    /// Default: no arguments
+    pub entrypoint_args: Vec<wasmer::Value>,
+
+    /// Writes stderr of the code to the same pipe as stdout, so the streams are interleaved
+    /// in the order they were written. <br/>
+    /// If set, `stdout` of returned result contains both streams and `stderr` is `None`.
+    /// Default: false
+    pub merge_stderr: bool,
+
+    /// Whether the code is run in wasi environment. <br/>
+    /// If disabled, the module is instantiated without any imports and only the entrypoint is called,
+    /// which is faster and safer for pure computation modules. Modules importing wasi are rejected.
+    /// Such code has no stdin, stdout or file system.
+    /// Default: true
+    pub wasi: bool,
+
+    /// Maximum wall-clock time the entrypoint can run for, independent of gas. <br/>
+    /// Code exceeding it fails with [`WasmRuntimeError::Timeout`].
+    /// Code waiting in a wasi call is killed and pure computation is stopped by taking away its remaining gas,
+    /// so code is always metered when timeout is set (with unlimited gas if `gas` is 0). <br/>
+    /// Default: None (no limit)
+    pub timeout: Option<Duration>,
+
+    /// Profiler collecting number of executed operators by category (see [`WasmProfiler`]). <br/>
+    /// This is useful for explaining where the gas was spent, but makes the code slower.
+    /// Default: None (no profiling)
+    pub profiler: Option<WasmProfiler>,
+
+    /// Cache of compiled modules, useful when running the same code many times (e.g. against many inputs). <br/>
+    /// Modules are keyed by the executable contents, compiler, gas, memory limit and profiling.
+    /// Modules using custom cost function are not cached, as it can't be compared.
+    /// Default: None (module is compiled on every run)
+    pub module_cache: Option<WasmModuleCache>,
+
+    /// Clock seen by the code, see [`WasmClock`]. <br/>
+    /// Fixed clock makes code printing or depending on time reproducible.
+    /// This only affects reading the clock, sleeping still takes real time.
+    /// Default: `WasmClock::Host`
+    pub clock: WasmClock,
+
+    /// Seed of random data returned by wasi `random_get`. <br/>
+    /// If set, code gets the same random data (e.g. for hash map ordering) on every run.
+    /// Default: None (random data from the host)
+    pub random_seed: Option<u64>,
+}
+
+/// Host directory mounted in the code.
+#[derive(Debug, Clone)]
+pub struct WasmMount {
+    /// Directory on the host.
+    pub host: PathBuf,
+
+    /// Path under which the directory is visible to the code (e.g. `/input`).
+    pub guest: PathBuf,
+
+    /// If set, the code can read the directory, but can't modify it.
+    pub read_only: bool,
+}
+
+impl WasmMount {
+    /// Creates new writable mount.
+    pub fn new(host: impl Into<PathBuf>, guest: impl Into<PathBuf>) -> Self {
+        Self {
+            host: host.into(),
+            guest: guest.into(),
+            read_only: false,
+        }
+    }
+
+    /// Creates new read-only mount.
+    pub fn read_only(host: impl Into<PathBuf>, guest: impl Into<PathBuf>) -> Self {
+        Self {
+            read_only: true,
+            ..Self::new(host, guest)
+        }
+    }
+}
+
+/// Read-only file available to the code. <br/>
+/// The file is kept in memory of the sandbox, so nothing is written to the host.
+#[derive(Debug, Clone)]
+pub struct WasmInputFile {
+    /// Path under which the file is visible to the code (e.g. `/input.txt`). <br/>
+    /// Missing parent directories are created, but the path can't be inside a mount.
+    pub guest: PathBuf,
+
+    /// Contents of the file.
+    pub contents: Vec<u8>,
+}
+
+impl WasmInputFile {
+    /// Creates new input file.
+    pub fn new(guest: impl Into<PathBuf>, contents: impl Into<Vec<u8>>) -> Self {
+        Self {
+            guest: guest.into(),
+            contents: contents.into(),
+        }
+    }
 }
 
 /// Sets the compiler that should be used to compile the code.
@@ -84,6 +217,17 @@ impl Debug for WasmConfig {
             .field("gas", &self.gas)
             .field("cost_function", &self.cost_function.is_some())
             .field("stdin", &self.stdin)
+            .field("mounts", &self.mounts)
+            .field("input_files", &self.input_files.len())
+            .field("entrypoint", &self.entrypoint)
+            .field("entrypoint_args", &self.entrypoint_args)
+            .field("merge_stderr", &self.merge_stderr)
+            .field("wasi", &self.wasi)
+            .field("timeout", &self.timeout)
+            .field("profiler", &self.profiler.is_some())
+            .field("module_cache", &self.module_cache)
+            .field("clock", &self.clock)
+            .field("random_seed", &self.random_seed)
             .finish()
     }
 }
@@ -96,6 +240,84 @@ impl Default for WasmConfig {
             cost_function: None,
             stdin: InputData::Ignore,
             compiler: WasmCompiler::default(),
+            mounts: Vec::new(),
+            input_files: Vec::new(),
+            entrypoint: None,
+            entrypoint_args: Vec::new(),
+            merge_stderr: false,
+            wasi: true,
+            timeout: None,
+            profiler: None,
+            module_cache: None,
+            clock: WasmClock::Host,
+            random_seed: None,
+        }
+    }
+}
+
+impl WasmConfig {
+    /// Creates configuration calling exported function `name` with `args` directly, without wasi. <br/>
+    /// This is meant for modules exporting functions instead of being programs,
+    /// e.g. Rust compiled with [`RustCompilerConfig::wasm_library`](crate::compilers::rust_compiler::RustCompilerConfig::wasm_library).
+    /// Returned values can be obtained using [`WasmRuntime::call`].
+    pub fn export(name: impl Into<String>, args: Vec<wasmer::Value>) -> Self {
+        Self {
+            wasi: false,
+            entrypoint: Some(name.into()),
+            entrypoint_args: args,
+            ..Default::default()
+        }
+    }
+
+    /// Creates configuration where every operator costs 1 gas.
+    /// This is the default cost function, so compiled modules can still be cached.
+    pub fn uniform_cost(gas: usize) -> Self {
+        Self {
+            gas,
+            ..Default::default()
+        }
+    }
+
+    /// Creates configuration where memory operators (loads, stores, `memory.grow`, ...) cost 4 gas
+    /// and other operators cost 1 gas. <br/>
+    /// This is closer to real cost of memory heavy code, as memory access is slower than arithmetic.
+    pub fn memory_weighted(gas: usize) -> Self {
+        Self::uniform_cost(gas).with_cost_function(|operator| {
+            match OperatorCategory::of(operator) {
+                OperatorCategory::Memory => 4,
+                _ => 1,
+            }
+        })
+    }
+
+    /// Creates configuration where control flow operators (branches, calls, ...) cost 3 gas
+    /// and other operators cost 1 gas. <br/>
+    /// This penalizes branchy and recursive code, e.g. to favor iterative solutions.
+    pub fn control_flow_weighted(gas: usize) -> Self {
+        Self::uniform_cost(gas).with_cost_function(|operator| {
+            match OperatorCategory::of(operator) {
+                OperatorCategory::Control | OperatorCategory::Call => 3,
+                _ => 1,
+            }
+        })
+    }
+
+    /// Sets custom metering cost function, see [`WasmConfig::cost_function`].
+    pub fn with_cost_function(
+        mut self,
+        cost_function: impl Fn(&Operator) -> u64 + Send + Sync + 'static,
+    ) -> Self {
+        self.cost_function = Some(Arc::new(cost_function));
+        self
+    }
+
+    /// Returns gas the code is metered with, if any. <br/>
+    /// Code with timeout is always metered, so it can be interrupted.
+    fn metered_gas(&self) -> Option<u64> {
+        match (self.gas, self.timeout) {
+            (0, None) => None,
+            (0, Some(_)) => Some(u64::MAX),
+            (gas, _) => Some(gas as u64),
         }
     }
 }
@@ -114,7 +336,7 @@ pub struct WasmAdditionalData {
 
 /// Wasm runtime error.
 macro_rules! impl_wasm_error {
-    ($($errn:ident $(=> $ft:ty)?),*) => {
+    ($($errn:ident $(=> $ft:ty)?),*; $($other:tt)*) => {
         /// Wasm runtime error.
         /// This contains all possible errors that can occur while running the code.
         #[derive(Debug)]
@@ -122,6 +344,7 @@ macro_rules! impl_wasm_error {
             $(
                 $errn $(($ft))?,
             )*
+            $($other)*
         }
 
         $(
@@ -140,15 +363,84 @@ macro_rules! impl_wasm_error {
 impl_wasm_error!(
     IOCompileError => wasmer::IoCompileError,
     IOError => std::io::Error,
-    WasiRuntimeError => wasmer_wasix::WasiRuntimeError,
     WasiError => wasmer_wasix::WasiError,
-    InstantiationError => wasmer::InstantiationError,
     ExportError => wasmer::ExportError,
     RuntimeError => wasmer::RuntimeError,
     WasiStateCreationError => wasmer_wasix::WasiStateCreationError,
-    FsError => wasmer_wasix::FsError
+    FsError => wasmer_wasix::FsError,
+    CompileError => wasmer::CompileError,
+    SerializeError => wasmer::SerializeError,
+    DeserializeError => wasmer::DeserializeError;
+
+    /// Module couldn't be instantiated. <br/>
+    /// This is boxed, as it's much larger than other errors.
+    InstantiationError(Box<wasmer::InstantiationError>),
+    /// Wasi failed while running the code. <br/>
+    /// This is boxed, as it's much larger than other errors.
+    WasiRuntimeError(Box<wasmer_wasix::WasiRuntimeError>),
+    /// Code failed while running (e.g. trapped or exceeded its limits). <br/>
+    /// This contains output written before the failure, which is often the most useful for debugging.
+    Trapped {
+        /// Stdout written before the failure (empty when streaming stdout).
+        stdout: String,
+        /// Stderr written before the failure.
+        stderr: String,
+        /// Error that stopped the code.
+        source: Box<WasmRuntimeError>,
+    },
+    /// Module imports wasi (the contained import), but wasi is disabled in the config.
+    WasiDisabled(String),
+    /// Compiled code doesn't contain an executable.
+    NoExecutable,
+    /// Code exceeded its wall-clock time limit (the contained duration), see [`WasmConfig::timeout`].
+    Timeout(Duration),
 );
 
+impl From<wasmer::InstantiationError> for WasmRuntimeError {
+    fn from(err: wasmer::InstantiationError) -> Self {
+        Self::InstantiationError(Box::new(err))
+    }
+}
+
+impl From<wasmer_wasix::WasiRuntimeError> for WasmRuntimeError {
+    fn from(err: wasmer_wasix::WasiRuntimeError) -> Self {
+        Self::WasiRuntimeError(Box::new(err))
+    }
+}
+
+impl Display for WasmRuntimeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::IOCompileError(e) => write!(f, "Compile error: {}", e),
+            Self::IOError(e) => write!(f, "IO error: {}", e),
+            Self::WasiRuntimeError(e) => write!(f, "Wasi runtime error: {}", e),
+            Self::WasiError(e) => write!(f, "Wasi error: {}", e),
+            Self::InstantiationError(e) => write!(f, "Instantiation error: {}", e),
+            Self::ExportError(e) => write!(f, "Export error: {}", e),
+            Self::RuntimeError(e) => write!(f, "Runtime error: {}", e),
+            Self::WasiStateCreationError(e) => write!(f, "Wasi state creation error: {}", e),
+            Self::FsError(e) => write!(f, "Filesystem error: {}", e),
+            Self::CompileError(e) => write!(f, "Compile error: {}", e),
+            Self::SerializeError(e) => write!(f, "Module serialization error: {}", e),
+            Self::DeserializeError(e) => write!(f, "Module deserialization error: {}", e),
+            Self::Trapped { source, .. } => write!(f, "Code trapped: {}", source),
+            Self::WasiDisabled(import) => {
+                write!(f, "Module imports {}, but wasi is disabled", import)
+            }
+            Self::NoExecutable => write!(f, "Compiled code does not contain an executable"),
+            Self::Timeout(timeout) => write!(f, "Code timed out after {:?}", timeout),
+        }
+    }
+}
+impl Error for WasmRuntimeError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Trapped { source, .. } => Some(source.as_ref()),
+            _ => None,
+        }
+    }
+}
+
 /// Runtime for wasm code.
 impl CodeRuntime for WasmRuntime {
     /// Configuration for the runtime.
@@ -157,6 +449,8 @@ impl CodeRuntime for WasmRuntime {
     type AdditionalData = WasmAdditionalData;
     /// Error type for the runtime.
     type Error = WasmRuntimeError;
+    /// Name of the runtime.
+    const NAME: &'static str = "wasm";
 
     /// Uses `wasmtime` to run the code.
     fn run(
@@ -164,26 +458,175 @@ impl CodeRuntime for WasmRuntime {
         code: &CompiledCode<Self>,
         config: Self::Config,
     ) -> Result<ExecutionResult, Self::Error> {
+        self.call(code, config).map(|(result, _)| result)
+    }
+}
+
+impl WasmRuntime {
+    /// Runs the code like [`CodeRuntime::run`], additionally returning values
+    /// returned by the entrypoint (see [`WasmConfig::entrypoint`]).
+    pub fn call(
+        &self,
+        code: &CompiledCode<Self>,
+        config: WasmConfig,
+    ) -> Result<(ExecutionResult, Box<[wasmer::Value]>), WasmRuntimeError> {
+        let (stdout_tx, mut stdout_rx) = wasmer_wasix::Pipe::channel();
+        let (mut result, values) = match self.execute(code, config, stdout_tx) {
+            Ok(result) => result,
+            // Attach stdout written before the failure.
+            Err(WasmRuntimeError::Trapped { stderr, source, .. }) => {
+                let mut stdout = String::new();
+                stdout_rx.read_to_string(&mut stdout)?;
+                return Err(WasmRuntimeError::Trapped {
+                    stdout,
+                    stderr,
+                    source,
+                });
+            }
+            Err(e) => return Err(e),
+        };
+
+        // Read stdout pipe.
+        let mut stdout = String::new();
+        stdout_rx.read_to_string(&mut stdout)?;
+        result.stdout = Some(stdout);
+
+        Ok((result, values))
+    }
+
+    /// Runs the code, passing stdout to the callback as soon as it is produced. <br/>
+    /// This is useful for long-running programs or programs with huge output,
+    /// as stdout is not buffered in memory. Returned result has no stdout.
+    pub fn run_streaming(
+        &self,
+        code: &CompiledCode<Self>,
+        config: WasmConfig,
+        mut on_stdout: impl FnMut(&[u8]),
+    ) -> Result<ExecutionResult, WasmRuntimeError> {
+        let (stdout_tx, mut stdout_rx) = wasmer_wasix::Pipe::channel();
+
+        std::thread::scope(|scope| {
+            // Run the code in the background, so stdout can be read while it's running.
+            let execution = scope.spawn(move || self.execute(code, config, stdout_tx));
+
+            // Pass stdout chunks to the callback until the pipe is closed.
+            let mut buffer = [0; 8192];
+            let mut finished = false;
+            loop {
+                match stdout_rx.read(&mut buffer) {
+                    Ok(0) => break,
+                    Ok(read) => on_stdout(&buffer[..read]),
+                    // Timed out code may still hold the pipe, so stop once what's left is read.
+                    Err(e) if e.kind() == std::io::ErrorKind::WouldBlock && finished => break,
+                    // Nothing to read yet, but the code is still running.
+                    Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {
+                        finished = execution.is_finished();
+                        if !finished {
+                            std::thread::sleep(std::time::Duration::from_millis(1))
+                        }
+                    }
+                    Err(e) => return Err(e.into()),
+                }
+            }
+
+            execution.join().unwrap().map(|(result, _)| result)
+        })
+    }
+
+    /// Runs the code, writing its stdout to the given pipe.
+    /// Returned result contains everything except stdout, along with values returned by the entrypoint.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "exers::run", skip_all, fields(runtime = "wasm"))
+    )]
+    fn execute(
+        &self,
+        code: &CompiledCode<Self>,
+        config: WasmConfig,
+        stdout_tx: wasmer_wasix::Pipe,
+    ) -> Result<(ExecutionResult, Box<[wasmer::Value]>), WasmRuntimeError> {
+        match config.timeout {
+            Some(timeout) => Self::execute_with_timeout(code, config, stdout_tx, timeout),
+            None => Self::execute_code(code, config, stdout_tx, None),
+        }
+    }
+
+    /// Runs the code on a separate thread, failing with [`WasmRuntimeError::Timeout`]
+    /// if the entrypoint doesn't return in time. <br/>
+    /// On timeout, the code is interrupted (see [`WasmInterrupt`]) and this waits for it to stop,
+    /// so nothing keeps running in the background.
+    #[allow(clippy::result_large_err)]
+    fn execute_with_timeout(
+        code: &CompiledCode<Self>,
+        config: WasmConfig,
+        stdout_tx: wasmer_wasix::Pipe,
+        timeout: Duration,
+    ) -> Result<(ExecutionResult, Box<[wasmer::Value]>), WasmRuntimeError> {
+        std::thread::scope(|scope| {
+            let (started_tx, started_rx) = mpsc::channel();
+            let (result_tx, result_rx) = mpsc::channel();
+            let execution = scope.spawn(move || {
+                let result = Self::execute_code(code, config, stdout_tx, Some(started_tx));
+                result_tx.send(result).ok();
+            });
+
+            // Wait for the entrypoint to be called, so module compilation doesn't count towards the limit.
+            // If it never is, the sender is dropped and the result is already available.
+            let interrupt = started_rx.recv().ok();
+
+            match result_rx.recv_timeout(timeout) {
+                Ok(result) => result,
+                Err(mpsc::RecvTimeoutError::Timeout) => {
+                    trace_event!(?timeout, "code timed out");
+                    if let Some(interrupt) = interrupt {
+                        // Interrupt repeatedly, as the code may overwrite its gas right after it's taken away.
+                        while let Err(mpsc::RecvTimeoutError::Timeout) =
+                            result_rx.recv_timeout(Duration::from_millis(1))
+                        {
+                            interrupt.interrupt();
+                        }
+                    }
+                    execution.join().ok();
+                    Err(WasmRuntimeError::Timeout(timeout))
+                }
+                // Execution thread panicked.
+                Err(mpsc::RecvTimeoutError::Disconnected) => {
+                    std::panic::resume_unwind(execution.join().unwrap_err())
+                }
+            }
+        })
+    }
+
+    /// Runs the code, writing its stdout to the given pipe. <br/>
+    /// If `started` is set, handle interrupting the code is sent to it right before the entrypoint is called.
+    #[allow(clippy::result_large_err)]
+    fn execute_code(
+        code: &CompiledCode<Self>,
+        config: WasmConfig,
+        stdout_tx: wasmer_wasix::Pipe,
+        started: Option<mpsc::Sender<WasmInterrupt>>,
+    ) -> Result<(ExecutionResult, Box<[wasmer::Value]>), WasmRuntimeError> {
         // Create engine with metering.
-        let compiler_config = if config.gas != 0 {
+        let mut compiler_config = config.compiler.get_compiler();
+        if let Some(gas) = config.metered_gas() {
             // Get cost function.
             let cost_function = config
                 .cost_function
+                .clone()
                 .unwrap_or_else(|| Arc::new(|_| -> u64 { 1 }));
             // Wrap cost function.
             let cost_function = move |op: &Operator| -> u64 { cost_function(op) };
             // Create metering middleware.
-            let metering = Arc::new(wasmer_middlewares::Metering::new(
-                config.gas as u64,
-                cost_function,
-            ));
+            let metering = Arc::new(wasmer_middlewares::Metering::new(gas, cost_function));
 
-            let mut compiler_config = config.compiler.get_compiler();
             wasmer::CompilerConfig::push_middleware(&mut compiler_config, metering);
-            compiler_config
-        } else {
-            config.compiler.get_compiler()
-        };
+        }
+
+        // Add profiling after metering, so it can skip metering checks.
+        if config.profiler.is_some() {
+            let profiling = Arc::new(ProfilingMiddleware::default());
+            wasmer::CompilerConfig::push_middleware(&mut compiler_config, profiling);
+        }
 
         // Create engine
         let mut engine: Engine = wasmer::EngineBuilder::new(compiler_config).into();
@@ -199,29 +642,179 @@ impl CodeRuntime for WasmRuntime {
         // Create store.
         let mut store = wasmer::Store::new(engine);
 
-        // Create module.
-        let module = wasmer::Module::from_file(&store, code.executable.as_ref().unwrap())?;
+        // Create module, either from memory or from the executable file.
+        let executable = code
+            .executable
+            .as_ref()
+            .ok_or(WasmRuntimeError::NoExecutable)?;
+        let compile = || -> Result<wasmer::Module, WasmRuntimeError> {
+            Ok(match executable {
+                Executable::Bytes(bytes) => wasmer::Module::new(&store, bytes)?,
+                Executable::Path(path) => wasmer::Module::from_file(&store, path)?,
+            })
+        };
+        let module = match &config.module_cache {
+            Some(cache) if config.cost_function.is_none() => {
+                let key = Self::module_cache_key(code, &config)?;
+                cache.load_or_compile(&store, &key, compile)?
+            }
+            _ => compile()?,
+        };
 
-        // Crate wasi pipes.
-        let (mut stdin_tx, stdin_rx) = wasmer_wasix::Pipe::channel();
-        let (stdout_tx, mut stdout_rx) = wasmer_wasix::Pipe::channel();
-        let (stderr_tx, mut stderr_rx) = wasmer_wasix::Pipe::channel();
+        // Instantiate the module, either in wasi environment or with no imports at all.
+        let (instance, mut wasi_env, mut stderr_rx) = if config.wasi {
+            let (instance, wasi_env, stderr_rx) =
+                Self::instantiate_wasi(&mut store, &module, code, &config, stdout_tx)?;
+            (instance, Some(wasi_env), stderr_rx)
+        } else {
+            (Self::instantiate_plain(&mut store, &module)?, None, None)
+        };
 
-        // Write stdin to pipe.
-        match &config.stdin {
-            InputData::String(input) => {
-                stdin_tx.write_all(input.as_bytes())?;
-                stdin_tx.write_all(b"\n")?; // Add a newline to the end of input.
+        // Get entrypoint function.
+        let entrypoint = config.entrypoint.as_deref().unwrap_or("_start");
+        let start = instance.exports.get_function(entrypoint)?;
+
+        // Initialize reactor module.
+        if entrypoint != "_start" {
+            if let Ok(initialize) = instance.exports.get_function("_initialize") {
+                initialize.call(&mut store, &[])?;
             }
-            InputData::File(path) => {
-                let mut file = File::open(path)?;
-                let mut buf = Vec::new();
-                file.read_to_end(&mut buf)?;
-                stdin_tx.write_all(&buf)?;
+        }
+
+        // Notify the watchdog, so it can start measuring the timeout.
+        let remaining_points = Arc::new(Mutex::new(None));
+        if let Some(started) = started {
+            let process = wasi_env
+                .as_ref()
+                .map(|wasi_env| wasi_env.data(&store).process.clone());
+            *remaining_points.lock().unwrap() = instance
+                .exports
+                .get_extern("wasmer_metering_remaining_points")
+                .and_then(|global| match global.to_vm_extern() {
+                    wasmer_vm::VMExtern::Global(handle) => {
+                        Some(RemainingPoints(handle.get(store.objects_mut()).vmglobal()))
+                    }
+                    _ => None,
+                });
+            started
+                .send(WasmInterrupt {
+                    process,
+                    remaining_points: remaining_points.clone(),
+                })
+                .ok();
+        }
+
+        // Start time measurement.
+        let start_time = std::time::Instant::now();
+
+        // Run
+        let values = start.call(&mut store, &config.entrypoint_args);
+
+        // Stop the watchdog from touching the store, as it's dropped soon.
+        remaining_points.lock().unwrap().take();
+
+        // Read profile, even if the code trapped.
+        if let Some(profiler) = &config.profiler {
+            profiler.record(&mut store, &instance);
+        }
+
+        let values = match values {
+            Ok(values) => values,
+            Err(e) => {
+                trace_event!(error = %e, "code trapped");
+
+                // Drop the store to close pipes, so output written before the failure can be read.
+                drop(store);
+                let mut stderr = String::new();
+                if let Some(stderr_rx) = &mut stderr_rx {
+                    stderr_rx.read_to_string(&mut stderr)?;
+                }
+
+                return Err(WasmRuntimeError::Trapped {
+                    stdout: String::new(),
+                    stderr,
+                    source: Box::new(e.into()),
+                });
             }
-            InputData::Ignore => {}
+        };
+
+        // End time measurement.
+        let time_taken = start_time.elapsed();
+        trace_event!(?time_taken, "code finished");
+
+        // Cleanup wasi env.
+        if let Some(wasi_env) = &mut wasi_env {
+            wasi_env.cleanup(&mut store, None);
         }
 
+        // Get stderr from pipe.
+        let stderr = match &mut stderr_rx {
+            Some(stderr_rx) => {
+                let mut stderr = String::new();
+                stderr_rx.read_to_string(&mut stderr)?;
+                Some(stderr)
+            }
+            None => None,
+        };
+
+        let result = ExecutionResult {
+            stdout: None,
+            stderr,
+            time_taken,
+            exit_code: 0,
+            signal: None,
+            sandbox: vec![SandboxFeature::Wasm],
+            instructions: None,
+        };
+
+        Ok((result, values))
+    }
+
+    /// Returns key of the module in module cache, which covers everything affecting compilation.
+    fn module_cache_key(code: &CompiledCode<Self>, config: &WasmConfig) -> std::io::Result<String> {
+        let mut hasher = Sha256::new();
+        hasher.update(code.executable_bytes_hash()?);
+        hasher.update(format!(
+            "{:?}/{:?}/{}/{}",
+            config.compiler,
+            config.metered_gas(),
+            config.memory_limit,
+            config.profiler.is_some()
+        ));
+        Ok(format!("{:x}", hasher.finalize()))
+    }
+
+    /// Instantiates the module with wasi imports, writing its stdout to the given pipe.
+    /// Returns the instance, its wasi environment and stderr pipe (unless stderr is merged).
+    #[allow(clippy::type_complexity, clippy::result_large_err)]
+    fn instantiate_wasi(
+        store: &mut wasmer::Store,
+        module: &wasmer::Module,
+        code: &CompiledCode<Self>,
+        config: &WasmConfig,
+        stdout_tx: wasmer_wasix::Pipe,
+    ) -> Result<
+        (
+            wasmer::Instance,
+            wasmer_wasix::WasiFunctionEnv,
+            Option<wasmer_wasix::Pipe>,
+        ),
+        WasmRuntimeError,
+    > {
+        // Crate wasi pipes.
+        // Merged stderr is written to the stdout pipe, so it doesn't have its own pipe.
+        let (mut stdin_tx, stdin_rx) = wasmer_wasix::Pipe::channel();
+        let (stderr_tx, stderr_rx) = match config.merge_stderr {
+            true => (stdout_tx.clone(), None),
+            false => {
+                let (stderr_tx, stderr_rx) = wasmer_wasix::Pipe::channel();
+                (stderr_tx, Some(stderr_rx))
+            }
+        };
+
+        // Write stdin to pipe.
+        stdin_tx.write_all(&config.stdin.read_to_vec()?)?;
+
         // Create wasi instance.
         let mut wasi_env = wasmer_wasix::WasiEnv::builder("wasi_program")
             .stdin(Box::new(stdin_rx))
@@ -229,56 +822,171 @@ impl CodeRuntime for WasmRuntime {
             .stderr(Box::new(stderr_tx))
             .args(&code.additional_data.args);
 
-        // Add preopen dir if present.
-        if let Some(dir) = &code.additional_data.preopen_dir {
+        // Collect mounts, starting with preopen dir if present.
+        let mounts = code
+            .additional_data
+            .preopen_dir
+            .iter()
+            .map(|dir| WasmMount::new(dir, "/sandbox"))
+            .chain(config.mounts.iter().cloned())
+            .collect::<Vec<_>>();
+
+        if !mounts.is_empty() || !config.input_files.is_empty() {
             // Get host fs.
-            let host_fs: Arc<dyn wasmer_wasix::virtual_fs::FileSystem + Send + Sync + 'static> =
+            let host_fs: Arc<dyn FileSystem + Send + Sync + 'static> =
                 Arc::new(wasmer_wasix::virtual_fs::host_fs::FileSystem::default());
+            let read_only_fs: Arc<dyn FileSystem + Send + Sync + 'static> =
+                Arc::new(ReadOnlyFileSystem(host_fs.clone()));
 
             // Create tmp fs and mount host fs.
             let tmp_fs = TmpFileSystem::new();
-            tmp_fs.mount("/sandbox".into(), &host_fs, dir.clone())?;
+            for mount in mounts {
+                let fs = if mount.read_only {
+                    &read_only_fs
+                } else {
+                    &host_fs
+                };
+                tmp_fs.mount(mount.guest, fs, mount.host)?;
+            }
+
+            // Create input files along with their parent directories.
+            for file in &config.input_files {
+                let parents = file.guest.ancestors().skip(1).collect::<Vec<_>>();
+                for dir in parents.into_iter().rev() {
+                    match tmp_fs.create_dir(dir) {
+                        Ok(()) | Err(FsError::AlreadyExists) => {}
+                        Err(e) => return Err(e.into()),
+                    }
+                }
+                tmp_fs
+                    .new_open_options_ext()
+                    .insert_ro_file(&file.guest, Cow::Owned(file.contents.clone()))?;
+            }
             wasi_env = wasi_env.sandbox_fs(tmp_fs);
         }
 
-        let mut wasi_env = wasi_env.finalize(&mut store)?;
-
-        // Initialize wasi instance.
-        let import_object = wasi_env.import_object(&mut store, &module)?;
-        let instance = wasmer::Instance::new(&mut store, &module, &import_object)?;
+        let mut wasi_env = wasi_env.finalize(store)?;
+
+        // Initialize wasi instance, replacing clock and randomness if requested.
+        let mut import_object = wasi_env.import_object(store, module)?;
+        let deterministic_imports = DeterministicImports::define(
+            store,
+            module,
+            &mut import_object,
+            config.clock,
+            config.random_seed,
+        );
+        let instance = {
+            #[cfg(feature = "tracing")]
+            let _span = tracing::debug_span!("exers::wasm_instantiate").entered();
+            wasmer::Instance::new(store, module, &import_object)?
+        };
+        if let Some(deterministic_imports) = deterministic_imports {
+            deterministic_imports.initialize(store, &instance)?;
+        }
 
         // Initialize wasi env.
-        wasi_env.initialize(&mut store, instance.clone())?;
+        wasi_env.initialize(store, instance.clone())?;
 
-        // Get _start function.
-        let start = instance.exports.get_function("_start")?;
+        Ok((instance, wasi_env, stderr_rx))
+    }
 
-        // Start time measurement.
-        let start_time = std::time::Instant::now();
+    /// Instantiates the module without any imports, for modules that don't use wasi.
+    #[allow(clippy::result_large_err)]
+    fn instantiate_plain(
+        store: &mut wasmer::Store,
+        module: &wasmer::Module,
+    ) -> Result<wasmer::Instance, WasmRuntimeError> {
+        // Reject wasi modules early, as instantiation would fail with unclear link error.
+        if let Some(import) = module
+            .imports()
+            .find(|import| import.module().starts_with("wasi"))
+        {
+            return Err(WasmRuntimeError::WasiDisabled(format!(
+                "{}::{}",
+                import.module(),
+                import.name()
+            )));
+        }
 
-        // Run
-        start.call(&mut store, &[])?;
+        Ok(wasmer::Instance::new(store, module, &wasmer::imports! {})?)
+    }
+}
 
-        // End time measurement.
-        let time_taken = start_time.elapsed();
+/// Handle used by the timeout watchdog to stop running code.
+struct WasmInterrupt {
+    /// Wasi process, killed to stop code waiting in a wasi call.
+    process: Option<wasmer_wasix::WasiProcess>,
+    /// Remaining gas of the running code, zeroed to stop pure computation.
+    /// It's `None` once the entrypoint returns, as the store may be dropped afterwards.
+    remaining_points: Arc<Mutex<Option<RemainingPoints>>>,
+}
 
-        // Cleanup wasi env.
-        wasi_env.cleanup(&mut store, None);
+/// Metering global of the running code.
+struct RemainingPoints(NonNull<wasmer_vm::VMGlobalDefinition>);
 
-        // Get output from pipes.
-        let mut stdout = String::new();
-        let mut stderr = String::new();
+// Global is only accessed while the store is alive, which is guarded by the mutex.
+unsafe impl Send for RemainingPoints {}
 
-        // Read pipes
-        stdout_rx.read_to_string(&mut stdout)?;
-        stderr_rx.read_to_string(&mut stderr)?;
+impl WasmInterrupt {
+    /// Kills wasi process and takes away remaining gas, so the code traps at its next metering check.
+    fn interrupt(&self) {
+        if let Some(process) = &self.process {
+            process.signal_process(wasmer_wasix::wasmer_wasix_types::wasi::Signal::Sigkill);
+        }
+        if let Some(RemainingPoints(global)) = &*self.remaining_points.lock().unwrap() {
+            // Global is 8-byte aligned i64, so it's written atomically, even while the code updates it.
+            unsafe { (*(global.as_ptr() as *const AtomicU64)).store(0, Ordering::Relaxed) };
+        }
+    }
+}
 
-        Ok(ExecutionResult {
-            stdout: Some(stdout),
-            stderr: Some(stderr),
-            time_taken,
-            exit_code: 0,
-        })
+/// File system that forwards reads to the inner file system and rejects all modifications.
+#[derive(Debug)]
+struct ReadOnlyFileSystem(Arc<dyn FileSystem + Send + Sync + 'static>);
+
+impl FileSystem for ReadOnlyFileSystem {
+    fn read_dir(&self, path: &std::path::Path) -> Result<ReadDir, FsError> {
+        self.0.read_dir(path)
+    }
+
+    fn create_dir(&self, _path: &std::path::Path) -> Result<(), FsError> {
+        Err(FsError::PermissionDenied)
+    }
+
+    fn remove_dir(&self, _path: &std::path::Path) -> Result<(), FsError> {
+        Err(FsError::PermissionDenied)
+    }
+
+    fn rename(&self, _from: &std::path::Path, _to: &std::path::Path) -> Result<(), FsError> {
+        Err(FsError::PermissionDenied)
+    }
+
+    fn metadata(&self, path: &std::path::Path) -> Result<Metadata, FsError> {
+        self.0.metadata(path)
+    }
+
+    fn remove_file(&self, _path: &std::path::Path) -> Result<(), FsError> {
+        Err(FsError::PermissionDenied)
+    }
+
+    fn new_open_options(&self) -> OpenOptions<'_> {
+        OpenOptions::new(self)
+    }
+}
+
+impl FileOpener for ReadOnlyFileSystem {
+    fn open(
+        &self,
+        path: &std::path::Path,
+        conf: &OpenOptionsConfig,
+    ) -> Result<Box<dyn VirtualFile + Send + Sync + 'static>, FsError> {
+        // Only allow opening files for reading.
+        if conf.would_mutate() {
+            return Err(FsError::PermissionDenied);
+        }
+
+        self.0.new_open_options().options(conf.clone()).open(path)
     }
 }
 
@@ -357,6 +1065,552 @@ mod tests {
         assert!(result.time_taken.as_nanos() > 0);
     }
 
+    #[test]
+    fn test_wasm_runtime_streaming() {
+        let code = r#"
+            fn main() {
+                for i in 0..3 {
+                    println!("Line {}", i);
+                }
+            }
+        "#;
+
+        let compiled_code = RustCompiler
+            .compile(&mut code.as_bytes(), Default::default())
+            .unwrap();
+
+        let mut stdout = Vec::new();
+        let result = WasmRuntime
+            .run_streaming(&compiled_code, Default::default(), |chunk| {
+                stdout.extend_from_slice(chunk)
+            })
+            .unwrap();
+
+        assert_eq!(stdout, b"Line 0\nLine 1\nLine 2\n");
+        assert_eq!(result.stdout, None);
+    }
+
+    #[test]
+    fn test_wasm_runtime_from_memory() {
+        let code = r#"
+            fn main() {
+                println!("Hello, world!");
+            }
+        "#;
+
+        let compiled_code: CompiledCode<WasmRuntime> = RustCompiler
+            .compile(&mut code.as_bytes(), Default::default())
+            .unwrap();
+        let bytes = std::fs::read(compiled_code.executable_path().unwrap()).unwrap();
+
+        let in_memory_code = CompiledCode::<WasmRuntime> {
+            executable: Some(Executable::Bytes(bytes)),
+            temp_dir_handle: Default::default(),
+            additional_data: Default::default(),
+            warnings: String::new(),
+            command_line: None,
+            runtime_marker: std::marker::PhantomData,
+        };
+        let result = WasmRuntime
+            .run(&in_memory_code, Default::default())
+            .unwrap();
+
+        assert_eq!(result.stdout, Some("Hello, world!\n".to_owned()));
+    }
+
+    #[test]
+    fn test_wasm_runtime_no_executable() {
+        let compiled_code = CompiledCode::<WasmRuntime> {
+            executable: None,
+            temp_dir_handle: Default::default(),
+            additional_data: Default::default(),
+            warnings: String::new(),
+            command_line: None,
+            runtime_marker: std::marker::PhantomData,
+        };
+        let result = WasmRuntime.run(&compiled_code, Default::default());
+
+        assert!(matches!(result, Err(WasmRuntimeError::NoExecutable)));
+    }
+
+    #[test]
+    fn test_wasm_runtime_entrypoint() {
+        let code = r#"
+            (module
+                (memory (export "memory") 1)
+                (global $base (mut i32) (i32.const 0))
+                (func (export "_initialize")
+                    i32.const 40
+                    global.set $base)
+                (func (export "add") (param i32 i32) (result i32)
+                    local.get 0
+                    local.get 1
+                    i32.add
+                    global.get $base
+                    i32.add))
+        "#;
+
+        let compiled_code = CompiledCode::<WasmRuntime> {
+            executable: Some(Executable::Bytes(code.as_bytes().to_vec())),
+            temp_dir_handle: Default::default(),
+            additional_data: Default::default(),
+            warnings: String::new(),
+            command_line: None,
+            runtime_marker: std::marker::PhantomData,
+        };
+        let (_, values) = WasmRuntime
+            .call(
+                &compiled_code,
+                WasmConfig {
+                    entrypoint: Some("add".to_owned()),
+                    entrypoint_args: vec![wasmer::Value::I32(1), wasmer::Value::I32(1)],
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+
+        assert_eq!(values.as_ref(), &[wasmer::Value::I32(42)]);
+    }
+
+    #[test]
+    fn test_wasm_runtime_without_wasi() {
+        let code = r#"
+            (module
+                (func (export "square") (param i32) (result i32)
+                    local.get 0
+                    local.get 0
+                    i32.mul))
+        "#;
+
+        let compiled_code = CompiledCode::<WasmRuntime> {
+            executable: Some(Executable::Bytes(code.as_bytes().to_vec())),
+            temp_dir_handle: Default::default(),
+            additional_data: Default::default(),
+            warnings: String::new(),
+            command_line: None,
+            runtime_marker: std::marker::PhantomData,
+        };
+        let (_, values) = WasmRuntime
+            .call(
+                &compiled_code,
+                WasmConfig {
+                    wasi: false,
+                    entrypoint: Some("square".to_owned()),
+                    entrypoint_args: vec![wasmer::Value::I32(7)],
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+
+        assert_eq!(values.as_ref(), &[wasmer::Value::I32(49)]);
+    }
+
+    #[test]
+    fn test_wasm_runtime_profiler() {
+        use crate::runtimes::wasm_profiler::OperatorCategory;
+
+        let code = r#"
+            (module
+                (func (export "count") (param i32) (result i32)
+                    (loop $loop
+                        local.get 0
+                        i32.const 1
+                        i32.sub
+                        local.tee 0
+                        br_if $loop)
+                    local.get 0))
+        "#;
+
+        let compiled_code = CompiledCode::<WasmRuntime> {
+            executable: Some(Executable::Bytes(code.as_bytes().to_vec())),
+            temp_dir_handle: Default::default(),
+            additional_data: Default::default(),
+            warnings: String::new(),
+            command_line: None,
+            runtime_marker: std::marker::PhantomData,
+        };
+
+        // Profile should be the same with metering, as its checks are not counted.
+        for gas in [0, 1000] {
+            let profiler = WasmProfiler::new();
+            WasmRuntime
+                .call(
+                    &compiled_code,
+                    WasmConfig {
+                        wasi: false,
+                        gas,
+                        entrypoint: Some("count".to_owned()),
+                        entrypoint_args: vec![wasmer::Value::I32(5)],
+                        profiler: Some(profiler.clone()),
+                        ..Default::default()
+                    },
+                )
+                .unwrap();
+
+            // Loop body runs 5 times, loop header and both ends once.
+            let profile = profiler.profile();
+            assert_eq!(profile.count(OperatorCategory::Control), 8);
+            assert_eq!(profile.count(OperatorCategory::Variable), 11);
+            assert_eq!(profile.count(OperatorCategory::Constant), 5);
+            assert_eq!(profile.count(OperatorCategory::Numeric), 5);
+            assert_eq!(profile.count(OperatorCategory::Memory), 0);
+            assert_eq!(profile.total(), 29);
+        }
+    }
+
+    #[test]
+    fn test_wasm_config_cost_functions() {
+        let cost = |config: WasmConfig, operator: &Operator| {
+            assert_eq!(config.gas, 100);
+            config
+                .cost_function
+                .map_or(1, |cost_function| cost_function(operator))
+        };
+        let operators = [
+            Operator::I32Add,
+            Operator::DataDrop { data_index: 0 },
+            Operator::Nop,
+            Operator::Call { function_index: 0 },
+        ];
+
+        for operator in &operators {
+            assert_eq!(cost(WasmConfig::uniform_cost(100), operator), 1);
+        }
+        let memory = operators
+            .each_ref()
+            .map(|op| cost(WasmConfig::memory_weighted(100), op));
+        assert_eq!(memory, [1, 4, 1, 1]);
+        let control = operators
+            .each_ref()
+            .map(|op| cost(WasmConfig::control_flow_weighted(100), op));
+        assert_eq!(control, [1, 1, 3, 3]);
+
+        let config = WasmConfig::default().with_cost_function(|_| 7);
+        assert_eq!((config.cost_function.unwrap())(&Operator::Nop), 7);
+    }
+
+    #[test]
+    fn test_wasm_runtime_deterministic() {
+        // Clock is read twice, so the second read shows whether the time passes.
+        let code = r#"
+            (module
+                (import "wasi_snapshot_preview1" "clock_time_get"
+                    (func $clock_time_get (param i32 i64 i32) (result i32)))
+                (import "wasi_snapshot_preview1" "random_get"
+                    (func $random_get (param i32 i32) (result i32)))
+                (memory (export "memory") 1)
+                (func (export "clock") (result i64)
+                    (drop (call $clock_time_get (i32.const 0) (i64.const 1) (i32.const 0)))
+                    (drop (call $clock_time_get (i32.const 0) (i64.const 1) (i32.const 0)))
+                    (i64.load (i32.const 0)))
+                (func (export "random") (result i64)
+                    (drop (call $random_get (i32.const 8) (i32.const 8)))
+                    (i64.load (i32.const 8))))
+        "#;
+
+        let compiled_code = CompiledCode::<WasmRuntime> {
+            executable: Some(Executable::Bytes(code.as_bytes().to_vec())),
+            temp_dir_handle: Default::default(),
+            additional_data: Default::default(),
+            warnings: String::new(),
+            command_line: None,
+            runtime_marker: std::marker::PhantomData,
+        };
+        let call = |entrypoint: &str, clock, random_seed| {
+            let (_, values) = WasmRuntime
+                .call(
+                    &compiled_code,
+                    WasmConfig {
+                        entrypoint: Some(entrypoint.to_owned()),
+                        clock,
+                        random_seed,
+                        ..Default::default()
+                    },
+                )
+                .unwrap();
+            values[0].unwrap_i64()
+        };
+
+        let second = Duration::from_secs(1);
+        assert!(call("clock", WasmClock::Host, None) > 1_000_000_000);
+        assert_eq!(call("clock", WasmClock::Fixed(second), None), 1_000_000_000);
+        assert_eq!(
+            call("clock", WasmClock::FromZero { step: second }, None),
+            1_000_000_000
+        );
+
+        let random = call("random", WasmClock::Host, Some(42));
+        assert_eq!(call("random", WasmClock::Host, Some(42)), random);
+        assert_ne!(call("random", WasmClock::Host, Some(43)), random);
+        assert_ne!(
+            call("random", WasmClock::Host, None),
+            call("random", WasmClock::Host, None)
+        );
+    }
+
+    #[test]
+    fn test_wasm_runtime_module_cache() {
+        let code = r#"
+            (module
+                (func (export "square") (param i32) (result i32)
+                    local.get 0
+                    local.get 0
+                    i32.mul))
+        "#;
+
+        let compiled_code = CompiledCode::<WasmRuntime> {
+            executable: Some(Executable::Bytes(code.as_bytes().to_vec())),
+            temp_dir_handle: Default::default(),
+            additional_data: Default::default(),
+            warnings: String::new(),
+            command_line: None,
+            runtime_marker: std::marker::PhantomData,
+        };
+        let directory = tempfile::tempdir().unwrap();
+        let config = |cache: &WasmModuleCache, gas: usize, input: i32| WasmConfig {
+            wasi: false,
+            gas,
+            entrypoint: Some("square".to_owned()),
+            entrypoint_args: vec![wasmer::Value::I32(input)],
+            module_cache: Some(cache.clone()),
+            ..Default::default()
+        };
+
+        // Module is compiled once for the same configuration.
+        let cache = WasmModuleCache::in_directory(directory.path()).unwrap();
+        for input in 1..4 {
+            let (_, values) = WasmRuntime
+                .call(&compiled_code, config(&cache, 0, input))
+                .unwrap();
+            assert_eq!(values.as_ref(), &[wasmer::Value::I32(input * input)]);
+        }
+        assert_eq!(cache.len(), 1);
+
+        // Different gas changes the compiled module.
+        WasmRuntime
+            .call(&compiled_code, config(&cache, 1000, 2))
+            .unwrap();
+        assert_eq!(cache.len(), 2);
+
+        // Modules are loaded from the directory by other caches.
+        let cache = WasmModuleCache::in_directory(directory.path()).unwrap();
+        let (_, values) = WasmRuntime
+            .call(&compiled_code, config(&cache, 0, 5))
+            .unwrap();
+        assert_eq!(values.as_ref(), &[wasmer::Value::I32(25)]);
+        assert_eq!(std::fs::read_dir(directory.path()).unwrap().count(), 2);
+    }
+
+    #[test]
+    fn test_wasm_runtime_without_wasi_rejects_wasi_module() {
+        let code = r#"
+            fn main() {
+                println!("Hello, world!");
+            }
+        "#;
+
+        let compiled_code = RustCompiler
+            .compile(&mut code.as_bytes(), Default::default())
+            .unwrap();
+        let result = WasmRuntime.run(
+            &compiled_code,
+            WasmConfig {
+                wasi: false,
+                ..Default::default()
+            },
+        );
+
+        assert!(matches!(result, Err(WasmRuntimeError::WasiDisabled(_))));
+    }
+
+    #[test]
+    fn test_wasm_runtime_timeout() {
+        let code = r#"
+            fn main() {
+                if std::env::args().count() > 1 {
+                    loop {
+                        std::thread::sleep(std::time::Duration::from_millis(10));
+                    }
+                }
+                println!("Hello, world!");
+            }
+        "#;
+
+        let mut compiled_code = RustCompiler
+            .compile(&mut code.as_bytes(), Default::default())
+            .unwrap();
+        let config = WasmConfig {
+            timeout: Some(Duration::from_secs(1)),
+            ..Default::default()
+        };
+
+        // Code finishing in time is not affected.
+        let result = WasmRuntime.run(&compiled_code, config.clone()).unwrap();
+        assert_eq!(result.stdout, Some("Hello, world!\n".to_owned()));
+
+        // Code that never finishes is stopped.
+        compiled_code.additional_data.args.push("loop".to_owned());
+        let result = WasmRuntime.run(&compiled_code, config);
+        assert!(matches!(result, Err(WasmRuntimeError::Timeout(_))));
+    }
+
+    #[test]
+    fn test_wasm_runtime_timeout_pure_computation() {
+        let code = r#"
+            (module
+                (func (export "spin")
+                    (loop $spin
+                        br $spin)))
+        "#;
+
+        let compiled_code = CompiledCode::<WasmRuntime> {
+            executable: Some(Executable::Bytes(code.as_bytes().to_vec())),
+            temp_dir_handle: Default::default(),
+            additional_data: Default::default(),
+            warnings: String::new(),
+            command_line: None,
+            runtime_marker: std::marker::PhantomData,
+        };
+        let result = WasmRuntime.run(
+            &compiled_code,
+            WasmConfig {
+                timeout: Some(Duration::from_millis(100)),
+                ..WasmConfig::export("spin", vec![])
+            },
+        );
+        assert!(matches!(result, Err(WasmRuntimeError::Timeout(_))));
+
+        // Code is stopped instead of running in the background.
+        assert_eq!(Arc::strong_count(&compiled_code.temp_dir_handle), 1);
+    }
+
+    #[test]
+    fn test_wasm_runtime_mounts() {
+        let code = r#"
+            fn main() {
+                let input = std::fs::read_to_string("/input/data.txt").unwrap();
+                std::fs::write("/output/data.txt", input.to_uppercase()).unwrap();
+                println!("{}", std::fs::write("/input/data.txt", "").is_err());
+            }
+        "#;
+
+        let input_dir = tempfile::tempdir().unwrap();
+        let output_dir = tempfile::tempdir().unwrap();
+        std::fs::write(input_dir.path().join("data.txt"), "hello").unwrap();
+
+        let compiled_code = RustCompiler
+            .compile(&mut code.as_bytes(), Default::default())
+            .unwrap();
+        let result = WasmRuntime
+            .run(
+                &compiled_code,
+                WasmConfig {
+                    mounts: vec![
+                        WasmMount::read_only(input_dir.path(), "/input"),
+                        WasmMount::new(output_dir.path(), "/output"),
+                    ],
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+
+        assert_eq!(result.stdout, Some("true\n".to_owned()));
+        assert_eq!(
+            std::fs::read_to_string(output_dir.path().join("data.txt")).unwrap(),
+            "HELLO"
+        );
+        assert_eq!(
+            std::fs::read_to_string(input_dir.path().join("data.txt")).unwrap(),
+            "hello"
+        );
+    }
+
+    #[test]
+    fn test_wasm_runtime_input_files() {
+        let code = r#"
+            fn main() {
+                let path = std::env::args().nth(1).unwrap();
+                let input = std::fs::read_to_string(&path).unwrap();
+                println!("{}", input.to_uppercase());
+                println!("{}", std::fs::write(&path, "").is_err());
+            }
+        "#;
+
+        let mut compiled_code: CompiledCode<WasmRuntime> = RustCompiler
+            .compile(&mut code.as_bytes(), Default::default())
+            .unwrap();
+        compiled_code
+            .additional_data
+            .args
+            .push("/data/input.txt".to_owned());
+        let result = WasmRuntime
+            .run(
+                &compiled_code,
+                WasmConfig {
+                    input_files: vec![WasmInputFile::new("/data/input.txt", "hello")],
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+
+        assert_eq!(result.stdout, Some("HELLO\ntrue\n".to_owned()));
+    }
+
+    #[test]
+    fn test_wasm_runtime_trapped_output() {
+        let code = r#"
+            fn main() {
+                println!("Before");
+                eprintln!("Error");
+                panic!("Failure");
+            }
+        "#;
+
+        let compiled_code = RustCompiler
+            .compile(&mut code.as_bytes(), Default::default())
+            .unwrap();
+        let result = WasmRuntime.run(&compiled_code, Default::default());
+
+        match result {
+            Err(WasmRuntimeError::Trapped { stdout, stderr, .. }) => {
+                assert_eq!(stdout, "Before\n");
+                assert!(stderr.starts_with("Error\n"));
+            }
+            _ => panic!("expected trapped error"),
+        }
+    }
+
+    #[test]
+    fn test_wasm_runtime_merge_stderr() {
+        let code = r#"
+            fn main() {
+                for i in 0..3 {
+                    println!("out {}", i);
+                    eprintln!("err {}", i);
+                }
+            }
+        "#;
+
+        let compiled_code = RustCompiler
+            .compile(&mut code.as_bytes(), Default::default())
+            .unwrap();
+        let result = WasmRuntime
+            .run(
+                &compiled_code,
+                WasmConfig {
+                    merge_stderr: true,
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+
+        assert_eq!(
+            result.stdout,
+            Some("out 0\nerr 0\nout 1\nerr 1\nout 2\nerr 2\n".to_owned())
+        );
+        assert_eq!(result.stderr, None);
+    }
+
     #[test]
     fn wasm_test_security() {
         // Try to create file (should panic)
@@ -0,0 +1,384 @@
+use std::{
+    error::Error,
+    fmt::{Debug, Display},
+    path::PathBuf,
+};
+
+use wasi_common::{
+    pipe::{ReadPipe, WritePipe},
+    sync::{ambient_authority, Dir, WasiCtxBuilder},
+    I32Exit, WasiCtx,
+};
+use wasmtime::{Engine, Linker, Module, Store, StoreLimits, StoreLimitsBuilder, Trap};
+
+use crate::{
+    common::runtime::InputData,
+    compilers::{CompiledCode, Executable},
+};
+
+use super::{CodeRuntime, ExecutionResult, SandboxFeature};
+
+/// Runtime for wasm code.
+/// This uses `wasmtime` to run the code, as an alternative to wasmer based [`WasmRuntime`](super::wasm_runtime::WasmRuntime). <br/>
+/// It runs the same `.wasm` files produced by compilers for wasi.
+#[derive(Debug, Clone, Default)]
+pub struct WasmtimeRuntime;
+
+/// Configuration for wasmtime runtime.
+#[derive(Debug, Clone)]
+pub struct WasmtimeConfig {
+    /// Amount of fuel to be used by the code. <br/>
+    /// Default: 0 (no limit) <br/>
+    /// Every instruction consumes fuel, so this doesn't depend on the machine (like gas in wasm runtime).
+    pub fuel: u64,
+
+    /// Maximum amount of memory that can be used by the code. <br/>
+    /// Default: 0 (no limit)
+    /// Unit for this is pages, where each page is 64KiB.
+    pub memory_limit: usize,
+
+    /// File containing stdin to be used by the code.
+    pub stdin: InputData,
+}
+
+impl Default for WasmtimeConfig {
+    fn default() -> Self {
+        Self {
+            fuel: 0,
+            memory_limit: 0,
+            stdin: InputData::Ignore,
+        }
+    }
+}
+
+/// Additional data for wasmtime runtime.
+/// This can be used by the compiler to pass additional data to the runtime.
+#[derive(Debug, Clone, Default)]
+pub struct WasmtimeAdditionalData {
+    /// Additional arguments to be passed to the code.
+    pub args: Vec<String>,
+
+    /// Files that should be mounted in the code.
+    /// This will be mounted as `/sandbox` in the code.
+    pub preopen_dir: Option<PathBuf>,
+}
+
+/// Wasmtime runtime error.
+/// This contains all possible errors that can occur while running the code.
+#[derive(Debug)]
+pub enum WasmtimeRuntimeError {
+    /// Error reported by wasmtime (compilation, instantiation or trap).
+    WasmtimeError(wasmtime::Error),
+    /// IO error.
+    IOError(std::io::Error),
+    /// Invalid argument passed to the code.
+    StringArrayError(wasi_common::StringArrayError),
+    /// Error while setting up wasi context.
+    WasiError(wasi_common::Error),
+    /// Compiled code doesn't contain an executable.
+    NoExecutable,
+}
+
+impl From<wasmtime::Error> for WasmtimeRuntimeError {
+    fn from(err: wasmtime::Error) -> Self {
+        Self::WasmtimeError(err)
+    }
+}
+
+impl From<std::io::Error> for WasmtimeRuntimeError {
+    fn from(err: std::io::Error) -> Self {
+        Self::IOError(err)
+    }
+}
+
+impl From<wasi_common::StringArrayError> for WasmtimeRuntimeError {
+    fn from(err: wasi_common::StringArrayError) -> Self {
+        Self::StringArrayError(err)
+    }
+}
+
+impl From<wasi_common::Error> for WasmtimeRuntimeError {
+    fn from(err: wasi_common::Error) -> Self {
+        Self::WasiError(err)
+    }
+}
+
+impl Display for WasmtimeRuntimeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::WasmtimeError(e) => write!(f, "Wasmtime error: {}", e),
+            Self::IOError(e) => write!(f, "IO error: {}", e),
+            Self::StringArrayError(e) => write!(f, "Invalid argument: {}", e),
+            Self::WasiError(e) => write!(f, "Wasi error: {}", e),
+            Self::NoExecutable => write!(f, "Compiled code does not contain an executable"),
+        }
+    }
+}
+impl Error for WasmtimeRuntimeError {}
+
+/// State of the store, containing wasi context and limits.
+struct StoreState {
+    wasi: WasiCtx,
+    limits: StoreLimits,
+}
+
+/// Runtime for wasm code.
+impl CodeRuntime for WasmtimeRuntime {
+    /// Configuration for the runtime.
+    type Config = WasmtimeConfig;
+    /// Additional compilation data.
+    type AdditionalData = WasmtimeAdditionalData;
+    /// Error type for the runtime.
+    type Error = WasmtimeRuntimeError;
+    /// Name of the runtime.
+    const NAME: &'static str = "wasmtime";
+
+    /// Uses `wasmtime` to run the code.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "exers::run", skip_all, fields(runtime = "wasmtime"))
+    )]
+    fn run(
+        &self,
+        code: &CompiledCode<Self>,
+        config: Self::Config,
+    ) -> Result<ExecutionResult, Self::Error> {
+        // Create engine, consuming fuel only if it's limited.
+        let mut engine_config = wasmtime::Config::new();
+        engine_config.consume_fuel(config.fuel != 0);
+        let engine = Engine::new(&engine_config)?;
+
+        // Create module, either from memory or from the executable file.
+        let module = match code
+            .executable
+            .as_ref()
+            .ok_or(WasmtimeRuntimeError::NoExecutable)?
+        {
+            Executable::Bytes(bytes) => Module::new(&engine, bytes)?,
+            Executable::Path(path) => Module::from_file(&engine, path)?,
+        };
+
+        // Read stdin.
+        let stdin = config.stdin.read_to_vec()?;
+
+        // Create wasi context.
+        let stdout = WritePipe::new_in_memory();
+        let stderr = WritePipe::new_in_memory();
+        let mut wasi = WasiCtxBuilder::new();
+        wasi.stdin(Box::new(ReadPipe::from(stdin)))
+            .stdout(Box::new(stdout.clone()))
+            .stderr(Box::new(stderr.clone()))
+            .arg("wasi_program")?
+            .args(&code.additional_data.args)?;
+
+        // Mount preopen dir.
+        if let Some(dir) = &code.additional_data.preopen_dir {
+            let dir = Dir::open_ambient_dir(dir, ambient_authority())?;
+            wasi.preopened_dir(dir, "/sandbox")?;
+        }
+
+        // Set memory limit.
+        let mut limits = StoreLimitsBuilder::new();
+        if config.memory_limit != 0 {
+            limits = limits.memory_size(config.memory_limit * 64 * 1024);
+        }
+
+        // Create store.
+        let mut store = Store::new(
+            &engine,
+            StoreState {
+                wasi: wasi.build(),
+                limits: limits.build(),
+            },
+        );
+        store.limiter(|state| &mut state.limits);
+        if config.fuel != 0 {
+            store.set_fuel(config.fuel)?;
+        }
+
+        // Link wasi and instantiate the module.
+        let mut linker = Linker::new(&engine);
+        wasi_common::sync::add_to_linker(&mut linker, |state: &mut StoreState| &mut state.wasi)?;
+        let instance = {
+            #[cfg(feature = "tracing")]
+            let _span = tracing::debug_span!("exers::wasm_instantiate").entered();
+            linker.instantiate(&mut store, &module)?
+        };
+
+        // Get _start function.
+        let start = instance.get_typed_func::<(), ()>(&mut store, "_start")?;
+
+        // Start time measurement.
+        let start_time = std::time::Instant::now();
+
+        // Run, exit through `proc_exit` is not an error.
+        let exit_code = match start.call(&mut store, ()) {
+            Ok(()) => 0,
+            Err(e) => match e.downcast_ref::<I32Exit>() {
+                Some(exit) => exit.0,
+                None => return Err(e.into()),
+            },
+        };
+
+        // End time measurement.
+        let time_taken = start_time.elapsed();
+        trace_event!(exit_code, ?time_taken, "code finished");
+
+        // Drop the store, so pipes can be read.
+        drop(store);
+
+        Ok(ExecutionResult {
+            stdout: Some(pipe_contents(stdout)),
+            stderr: Some(pipe_contents(stderr)),
+            time_taken,
+            exit_code,
+            signal: None,
+            sandbox: vec![SandboxFeature::Wasm],
+            instructions: None,
+        })
+    }
+}
+
+impl WasmtimeRuntime {
+    /// Returns true if the error was caused by running out of fuel.
+    pub fn is_out_of_fuel(error: &WasmtimeRuntimeError) -> bool {
+        match error {
+            WasmtimeRuntimeError::WasmtimeError(e) => {
+                matches!(e.downcast_ref::<Trap>(), Some(Trap::OutOfFuel))
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Reads everything written to in-memory pipe.
+fn pipe_contents(pipe: WritePipe<std::io::Cursor<Vec<u8>>>) -> String {
+    let bytes = pipe
+        .try_into_inner()
+        .map(|cursor| cursor.into_inner())
+        .unwrap_or_default();
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::compilers::{rust_compiler::RustCompiler, Compiler};
+
+    use super::*;
+
+    /// Creates in-memory code from given wasm (or wat) module.
+    fn module(code: &str) -> CompiledCode<WasmtimeRuntime> {
+        CompiledCode {
+            executable: Some(Executable::Bytes(code.as_bytes().to_vec())),
+            temp_dir_handle: Default::default(),
+            additional_data: Default::default(),
+            warnings: String::new(),
+            command_line: None,
+            runtime_marker: std::marker::PhantomData,
+        }
+    }
+
+    /// Module printing "Hello, world!" and exiting with code 3.
+    const HELLO: &str = r#"
+        (module
+            (import "wasi_snapshot_preview1" "fd_write"
+                (func $fd_write (param i32 i32 i32 i32) (result i32)))
+            (import "wasi_snapshot_preview1" "proc_exit" (func $proc_exit (param i32)))
+            (memory (export "memory") 1)
+            (data (i32.const 8) "Hello, world!\n")
+            (func (export "_start")
+                (i32.store (i32.const 0) (i32.const 8))
+                (i32.store (i32.const 4) (i32.const 14))
+                (drop (call $fd_write (i32.const 1) (i32.const 0) (i32.const 1) (i32.const 32)))
+                (call $proc_exit (i32.const 3))))
+    "#;
+
+    #[test]
+    fn test_wasmtime_runtime_module() {
+        let result = WasmtimeRuntime
+            .run(&module(HELLO), Default::default())
+            .unwrap();
+
+        assert_eq!(result.stdout, Some("Hello, world!\n".to_owned()));
+        assert_eq!(result.exit_code, 3);
+    }
+
+    #[test]
+    fn test_wasmtime_no_executable() {
+        let mut code = module(HELLO);
+        code.executable = None;
+        let result = WasmtimeRuntime.run(&code, Default::default());
+
+        assert!(matches!(result, Err(WasmtimeRuntimeError::NoExecutable)));
+    }
+
+    #[test]
+    fn test_wasmtime_fuel_exceeded() {
+        let code = r#"
+            (module
+                (memory (export "memory") 1)
+                (func (export "_start")
+                    (loop $loop (br $loop))))
+        "#;
+
+        let result = WasmtimeRuntime.run(
+            &module(code),
+            WasmtimeConfig {
+                fuel: 10_000,
+                ..Default::default()
+            },
+        );
+
+        assert!(WasmtimeRuntime::is_out_of_fuel(&result.unwrap_err()));
+    }
+
+    #[test]
+    fn test_wasmtime_memory_limit_exceeded() {
+        // Traps if memory can't grow by 10 pages.
+        let code = r#"
+            (module
+                (memory (export "memory") 1)
+                (func (export "_start")
+                    (if (i32.eq (memory.grow (i32.const 10)) (i32.const -1))
+                        (then unreachable))))
+        "#;
+
+        let result = WasmtimeRuntime.run(
+            &module(code),
+            WasmtimeConfig {
+                memory_limit: 5,
+                ..Default::default()
+            },
+        );
+        assert!(result.is_err());
+
+        let result = WasmtimeRuntime.run(&module(code), Default::default());
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_wasmtime_runtime_rust() {
+        let code = r#"
+            fn main() {
+                let mut input = String::new();
+                std::io::stdin().read_line(&mut input).unwrap();
+                println!("Hello, {}!", input.trim());
+            }
+        "#;
+
+        let compiled_code = RustCompiler
+            .compile(&mut code.as_bytes(), Default::default())
+            .unwrap();
+        let result = WasmtimeRuntime
+            .run(
+                &compiled_code,
+                WasmtimeConfig {
+                    stdin: InputData::String("world".to_owned()),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+
+        assert_eq!(result.stdout, Some("Hello, world!\n".to_owned()));
+    }
+}